@@ -8,11 +8,12 @@ use winit::{
     window::{Window, WindowBuilder},
 };
 
-use renderer::{Error as RendererError, Renderer};
+use renderer::{shaders, Error as RendererError, Renderer};
+use scene::{bvh::Bvh, Scene};
 
 use crate::{
     data::camera_controller::{AxisMovement, CameraController},
-    input,
+    input, Args,
 };
 
 pub mod conf {
@@ -27,29 +28,74 @@ pub struct App {
     last_frame: Instant,
     inputs: input::State,
     camera_controller: CameraController,
+    bvh: Bvh,
+    // name of the node of each instance, for picking
+    instance_names: Vec<String>,
+    material_variant: Option<usize>,
     needs_resizing: bool,
 }
 
 impl App {
-    pub fn new(window: &Window, scene_file: &str) -> Self {
+    // loads the scene of the arguments, before the window as it sets the frame resolution
+    pub fn load_scene(args: &Args) -> Scene {
+        firestorm::profile_method!(load_scene);
+
+        let mut scene = scene::io::load(&args.scene_file);
+
+        for name in &args.hide {
+            if scene.hide(name) == 0 {
+                eprintln!("No node named {name:?} found");
+            }
+        }
+
+        if args.validate {
+            let report = scene.validate();
+            if !report.diagnostics.is_empty() {
+                eprintln!("{report}");
+            }
+            assert!(!report.has_errors(), "Scene failed validation");
+        }
+
+        scene
+    }
+
+    pub fn new(window: &Window, scene: Scene) -> Self {
         firestorm::profile_method!(new);
 
-        let scene = scene::io::load(scene_file);
+        let resolution = Self::frame_resolution(&scene);
 
-        let camera_controller = CameraController::new(
-            scene.info.bounding_box.size() * 1.2 + scene.info.bounding_box.center(),
-            scene.info.bounding_box.center(),
-            conf::FRAME_RESOLUTION,
-            conf::FOV_DEGREES,
+        let camera_controller = scene.info.cameras.first().map_or_else(
+            || {
+                CameraController::new(
+                    scene.info.bounding_box.size() * 1.2 + scene.info.bounding_box.center(),
+                    scene.info.bounding_box.center(),
+                    resolution,
+                    conf::FOV_DEGREES,
+                )
+            },
+            |camera| CameraController::new(camera.position, camera.target, resolution, camera.fov),
         );
 
         let inputs = input::State::default();
 
+        let bvh = Bvh::new(&scene);
+        let instance_names = scene
+            .info
+            .instances
+            .iter()
+            .map(|instance| {
+                instance
+                    .node
+                    .map(|node| scene.info.nodes[node].name.clone())
+                    .unwrap_or_default()
+            })
+            .collect();
+
         let renderer = Renderer::create(
             &window.title(),
             window,
             scene,
-            conf::FRAME_RESOLUTION,
+            resolution,
             camera_controller.camera(),
         );
 
@@ -59,6 +105,9 @@ impl App {
             last_frame: Instant::now(),
             inputs,
             camera_controller,
+            bvh,
+            instance_names,
+            material_variant: None,
             needs_resizing: false,
         }
     }
@@ -122,8 +171,68 @@ impl App {
         self.last_frame = now;
     }
 
-    pub fn window_builder() -> WindowBuilder {
-        WindowBuilder::new().with_inner_size(PhysicalSize::<u32>::from(conf::FRAME_RESOLUTION))
+    // cycles through the default materials and every variant
+    fn cycle_material_variant(&mut self) {
+        let count = self.renderer.material_variants().count();
+        if count == 0 {
+            return;
+        }
+
+        self.material_variant = match self.material_variant {
+            None => Some(0),
+            Some(variant) if variant + 1 < count => Some(variant + 1),
+            Some(_) => None,
+        };
+        let name = self
+            .material_variant
+            .and_then(|variant| self.renderer.material_variants().nth(variant))
+            .map(str::to_owned);
+
+        self.renderer.set_material_variant(name.as_deref());
+        println!("Material variant: {}", name.as_deref().unwrap_or("default"));
+    }
+
+    // cycles through the variants of the pathtracer's ray generation shader, default first
+    fn cycle_ray_generation(&mut self) {
+        let variants = shaders::PathtracerRgen::ALL;
+        let current = variants
+            .iter()
+            .position(|&variant| variant == self.renderer.ray_generation())
+            .unwrap_or_default();
+        let ray_generation = variants[(current + 1) % variants.len()];
+
+        self.renderer.set_ray_generation(ray_generation);
+        println!("Ray generation: {ray_generation:?}");
+    }
+
+    // picks what's at the center of the view and focuses the camera on it
+    fn pick(&mut self) {
+        let Some(hit) = self.bvh.closest_hit(&self.camera_controller.ray()) else {
+            println!("Nothing picked");
+            return;
+        };
+        println!(
+            "Picked {:?} (instance {}, primitive {}, material {}) at distance {:.3}",
+            self.instance_names[hit.instance],
+            hit.instance,
+            hit.primitive,
+            hit.material,
+            hit.distance
+        );
+        self.camera_controller.focus(hit.distance);
+    }
+
+    // the resolution of the scene's render settings, if any
+    pub fn frame_resolution(scene: &Scene) -> (u32, u32) {
+        scene
+            .info
+            .render_settings
+            .resolution
+            .unwrap_or(conf::FRAME_RESOLUTION)
+    }
+
+    pub fn window_builder(resolution: (u32, u32)) -> WindowBuilder {
+        WindowBuilder::new().with_inner_size(PhysicalSize::<u32>::from(resolution))
     }
 
     pub fn run(mut self, event_loop: EventLoop<()>) {
@@ -158,6 +267,36 @@ impl App {
                             },
                         ..
                     } => self.renderer.toggle_renderer(),
+                    WindowEvent::KeyboardInput {
+                        event:
+                            KeyEvent {
+                                physical_key: PhysicalKey::Code(KeyCode::KeyV),
+                                state: ElementState::Pressed,
+                                repeat: false,
+                                ..
+                            },
+                        ..
+                    } => self.cycle_material_variant(),
+                    WindowEvent::KeyboardInput {
+                        event:
+                            KeyEvent {
+                                physical_key: PhysicalKey::Code(KeyCode::KeyR),
+                                state: ElementState::Pressed,
+                                repeat: false,
+                                ..
+                            },
+                        ..
+                    } => self.cycle_ray_generation(),
+                    WindowEvent::KeyboardInput {
+                        event:
+                            KeyEvent {
+                                physical_key: PhysicalKey::Code(KeyCode::KeyF),
+                                state: ElementState::Pressed,
+                                repeat: false,
+                                ..
+                            },
+                        ..
+                    } => self.pick(),
                     WindowEvent::KeyboardInput {
                         event:
                             KeyEvent {
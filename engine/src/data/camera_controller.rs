@@ -57,6 +57,16 @@ impl CameraController {
             * self.direction;
     }
 
+    // ray through the center of the view
+    pub const fn ray(&self) -> scene::bvh::Ray {
+        scene::bvh::Ray::new(self.position, self.direction)
+    }
+
+    // adapts the movement speed to the distance of what's being looked at
+    pub const fn focus(&mut self, distance: f32) {
+        self.scale = distance;
+    }
+
     fn right_axis(&self) -> glam::Vec3 {
         glam::vec3(-self.direction.z, 0., self.direction.x)
     }
@@ -8,16 +8,24 @@ use winit::event_loop::EventLoop;
 
 use app::App;
 
+pub struct Args {
+    pub scene_file: String,
+    pub validate: bool,
+    pub hide: Vec<String>,
+}
+
 fn main() {
-    let scene_file = env::args().nth(1).expect("Please specify a scene file");
+    let args = Args::parse();
 
     let event_loop = EventLoop::new().expect("Failed to create event loop");
 
-    let window = App::window_builder()
+    let scene = App::load_scene(&args);
+
+    let window = App::window_builder(App::frame_resolution(&scene))
         .build(&event_loop)
         .expect("Failed to create window");
 
-    let app = App::new(&window, &scene_file);
+    let app = App::new(&window, scene);
 
     app.run(event_loop);
 
@@ -25,3 +33,27 @@ fn main() {
         firestorm::save("./profiling_results/").expect("Failed to save profiling results");
     }
 }
+
+impl Args {
+    fn parse() -> Self {
+        let mut scene_file = None;
+        let mut validate = false;
+        let mut hide = Vec::new();
+
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--validate" => validate = true,
+                "--hide" => hide.push(args.next().expect("--hide requires a node name")),
+                _ if arg.starts_with("--") => panic!("Unknown argument {arg}"),
+                _ => scene_file = Some(arg),
+            }
+        }
+
+        Self {
+            scene_file: scene_file.expect("Please specify a scene file"),
+            validate,
+            hide,
+        }
+    }
+}
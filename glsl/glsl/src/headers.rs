@@ -0,0 +1,182 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt::Write,
+};
+
+use crate::Language;
+
+// registered by the derives and #[glsl_const] for the headers they name, and by hand for
+// anything else
+pub struct GlslItem {
+    pub header: &'static str,
+    pub name: &'static str,
+    // names of the types it uses, the ones registered are defined or included before it
+    pub dependencies: fn() -> Vec<&'static str>,
+    pub definition: fn(Language) -> String,
+    // definitions keep the order they're declared in unless a dependency comes later
+    pub file: &'static str,
+    pub line: u32,
+}
+
+inventory::collect!(GlslItem);
+
+pub struct GlslHeader {
+    pub name: &'static str,
+    pub language: Language,
+    pub source: String,
+}
+
+impl GlslHeader {
+    pub fn file_name(&self) -> String {
+        file_name(self.name, self.language)
+    }
+}
+
+// every registered header in the languages it's used in, in name order
+pub fn headers(languages: impl Fn(&str) -> &'static [Language]) -> Vec<GlslHeader> {
+    let mut items = inventory::iter::<GlslItem>.into_iter().collect::<Vec<_>>();
+    items.sort_by_key(|item| (item.file, item.line));
+
+    let mut header_of = HashMap::new();
+    for item in &items {
+        if let Some(header) = header_of.insert(item.name, item.header) {
+            panic!(
+                "{} is registered in both {header} and {}",
+                item.name, item.header
+            );
+        }
+    }
+
+    let mut by_header = BTreeMap::<_, Vec<_>>::new();
+    for item in items {
+        by_header.entry(item.header).or_default().push(item);
+    }
+
+    let mut includes = BTreeMap::new();
+    let mut headers = Vec::new();
+    for (name, items) in by_header {
+        let (items, header_includes) = sorted(name, items, &header_of);
+        for &language in languages(name) {
+            for include in &header_includes {
+                assert!(
+                    languages(include).contains(&language),
+                    "{name} includes {include}, which isn't generated in {}",
+                    language.name()
+                );
+            }
+            headers.push(GlslHeader {
+                name,
+                language,
+                source: source(name, language, &items, &header_includes),
+            });
+        }
+        includes.insert(name, header_includes);
+    }
+    check_includes(&includes);
+    headers
+}
+
+fn file_name(name: &str, language: Language) -> String {
+    format!("{name}.{}", language.header_extension())
+}
+
+// the items in dependency order and the headers they need
+fn sorted<'a>(
+    name: &'static str,
+    mut pending: Vec<&'a GlslItem>,
+    header_of: &HashMap<&str, &'static str>,
+) -> (Vec<&'a GlslItem>, BTreeSet<&'static str>) {
+    let mut includes = BTreeSet::new();
+    let mut defined = HashSet::new();
+    let mut sorted = Vec::new();
+    while !pending.is_empty() {
+        let ready = pending.iter().position(|item| {
+            (item.dependencies)().iter().all(|dependency| {
+                header_of.get(dependency) != Some(&name)
+                    || *dependency == item.name
+                    || defined.contains(dependency)
+            })
+        });
+        let Some(idx) = ready else {
+            let names = pending.iter().map(|item| item.name).collect::<Vec<_>>();
+            panic!("{} depend on each other in {name}", names.join(", "));
+        };
+        let item = pending.remove(idx);
+        for dependency in (item.dependencies)() {
+            if let Some(&header) = header_of.get(dependency).filter(|&&header| header != name) {
+                includes.insert(header);
+            }
+        }
+        defined.insert(item.name);
+        sorted.push(item);
+    }
+    (sorted, includes)
+}
+
+// wgsl has no preprocessor, the headers it needs are only named
+fn source(
+    name: &str,
+    language: Language,
+    items: &[&GlslItem],
+    includes: &BTreeSet<&str>,
+) -> String {
+    let mut source = String::from("// AUTO-GENERATED: do not edit\n\n");
+    let guard = format!(
+        "{}_",
+        file_name(name, language).to_uppercase().replace('.', "_")
+    );
+    if language != Language::Wgsl {
+        writeln!(source, "#ifndef {guard}\n#define {guard}\n").unwrap();
+    }
+    for include in includes {
+        let include = file_name(include, language);
+        if language == Language::Wgsl {
+            writeln!(source, "// needs the definitions of {include}").unwrap();
+        } else {
+            writeln!(source, "#include \"{include}\"").unwrap();
+        }
+    }
+    if !includes.is_empty() {
+        source.push('\n');
+    }
+    // one-line definitions like constants are kept together
+    let definitions = items
+        .iter()
+        .map(|item| (item.definition)(language))
+        .collect::<Vec<_>>();
+    for (idx, definition) in definitions.iter().enumerate() {
+        let single_line = |definition: &String| definition.lines().count() == 1;
+        if idx > 0 && !(single_line(definition) && single_line(&definitions[idx - 1])) {
+            source.push('\n');
+        }
+        source.push_str(definition);
+    }
+    if language != Language::Wgsl {
+        source.push_str("\n#endif\n");
+    }
+    source
+}
+
+// the include guards would silently drop the definitions of headers including each other
+fn check_includes(includes: &BTreeMap<&str, BTreeSet<&str>>) {
+    fn visit<'a>(
+        header: &'a str,
+        includes: &BTreeMap<&str, BTreeSet<&'a str>>,
+        path: &mut Vec<&'a str>,
+    ) {
+        assert!(
+            !path.contains(&header),
+            "headers include each other: {} -> {header}",
+            path.join(" -> ")
+        );
+        path.push(header);
+        for include in includes.get(header).into_iter().flatten() {
+            visit(include, includes, path);
+        }
+        path.pop();
+    }
+
+    for header in includes.keys() {
+        visit(header, includes, &mut Vec::new());
+    }
+}
@@ -0,0 +1,132 @@
+use std::fmt::Write;
+
+use crate::{GlslField, Layout};
+
+// the languages the definitions can be written in, hlsl and slang blocks only follow the glsl
+// layout rules when compiled with -fvk-use-gl-layout or -fvk-use-scalar-layout
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Language {
+    Glsl,
+    Hlsl,
+    Slang,
+    Wgsl,
+}
+
+// glsl, hlsl and slang, wgsl (none if it can't be used in buffers)
+// hlsl matrices are column major by default, so they're laid out like glsl's but indexed by row
+const TYPES: [(&str, &str, Option<&str>); 18] = [
+    ("bool", "bool", None),
+    ("float", "float", Some("f32")),
+    ("double", "double", None),
+    ("int", "int", Some("i32")),
+    ("uint", "uint", Some("u32")),
+    ("int64_t", "int64_t", None),
+    ("uint64_t", "uint64_t", None),
+    ("vec2", "float2", Some("vec2<f32>")),
+    ("vec3", "float3", Some("vec3<f32>")),
+    ("vec4", "float4", Some("vec4<f32>")),
+    ("ivec2", "int2", Some("vec2<i32>")),
+    ("ivec3", "int3", Some("vec3<i32>")),
+    ("ivec4", "int4", Some("vec4<i32>")),
+    ("uvec2", "uint2", Some("vec2<u32>")),
+    ("uvec3", "uint3", Some("vec3<u32>")),
+    ("uvec4", "uint4", Some("vec4<u32>")),
+    ("mat3", "float3x3", Some("mat3x3<f32>")),
+    ("mat4", "float4x4", Some("mat4x4<f32>")),
+];
+
+impl Language {
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Glsl => "GLSL",
+            Self::Hlsl => "HLSL",
+            Self::Slang => "Slang",
+            Self::Wgsl => "WGSL",
+        }
+    }
+
+    pub const fn header_extension(self) -> &'static str {
+        match self {
+            Self::Glsl => "h.glsl",
+            Self::Hlsl => "hlsli",
+            Self::Slang => "slang",
+            Self::Wgsl => "wgsl",
+        }
+    }
+
+    // wgsl has uniform buffers laid out like std140 and storage buffers like std430 but nothing
+    // like scalar
+    pub const fn supports(self, layout: Layout) -> bool {
+        !matches!((self, layout), (Self::Wgsl, Layout::Scalar))
+    }
+
+    // structs keep their names in every language
+    pub fn type_name(self, glsl_name: &'static str) -> &'static str {
+        let Some(&(_, hlsl, wgsl)) = TYPES.iter().find(|(glsl, ..)| *glsl == glsl_name) else {
+            return glsl_name;
+        };
+        match self {
+            Self::Glsl => glsl_name,
+            Self::Hlsl | Self::Slang => hlsl,
+            Self::Wgsl => wgsl.unwrap_or_else(|| {
+                panic!("{glsl_name} has no {} equivalent in buffers", self.name())
+            }),
+        }
+    }
+
+    // suffixes of the literals of the types that need one
+    pub fn literal_suffix(self, glsl_name: &str) -> &'static str {
+        match (self, glsl_name) {
+            (_, "uint") => "u",
+            (Self::Glsl, "double") => "lf",
+            (Self::Glsl, "int64_t") | (Self::Hlsl | Self::Slang, "double") => "l",
+            (Self::Glsl, "uint64_t") => "ul",
+            (Self::Hlsl | Self::Slang, "int64_t") => "ll",
+            (Self::Hlsl | Self::Slang, "uint64_t") => "ull",
+            _ => "",
+        }
+    }
+
+    pub fn struct_definition(self, name: &str, layouts: &[Layout], fields: &[GlslField]) -> String {
+        assert!(
+            layouts.iter().any(|&layout| self.supports(layout)),
+            "{name} is only checked against {}, which {} doesn't support",
+            layouts
+                .iter()
+                .map(|layout| layout.name())
+                .collect::<Vec<_>>()
+                .join(", "),
+            self.name()
+        );
+
+        let mut def = format!("struct {name} {{\n");
+        for field in fields {
+            let ty = self.type_name(field.ty);
+            let lengths = (field.array_lengths)();
+            if self == Self::Wgsl {
+                let ty = lengths
+                    .iter()
+                    .rev()
+                    .fold(ty.to_owned(), |ty, len| format!("array<{ty}, {len}>"));
+                writeln!(def, "  {}: {ty},", field.name).unwrap();
+            } else {
+                write!(def, "  {ty} {}", field.name).unwrap();
+                for len in lengths {
+                    write!(def, "[{len}]").unwrap();
+                }
+                def.push_str(";\n");
+            }
+        }
+        def.push_str(if self == Self::Wgsl { "}\n" } else { "};\n" });
+        def
+    }
+
+    pub fn const_definition(self, glsl_type: &'static str, name: &str, literal: &str) -> String {
+        let ty = self.type_name(glsl_type);
+        match self {
+            Self::Glsl => format!("const {ty} {name} = {literal};\n"),
+            Self::Hlsl | Self::Slang => format!("static const {ty} {name} = {literal};\n"),
+            Self::Wgsl => format!("const {name}: {ty} = {literal};\n"),
+        }
+    }
+}
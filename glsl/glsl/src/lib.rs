@@ -1,50 +1,395 @@
-pub use glsl_derive::GlslStruct;
+mod headers;
+mod language;
+
+use bytemuck::{Pod, Zeroable};
+
+pub use glsl_derive::{glsl_const, GlslEnum, GlslStruct};
+pub use headers::{headers, GlslHeader, GlslItem};
+pub use language::Language;
+// used by the derives to register items
+pub use inventory;
+
+// block layout rules, std140 for uniform blocks, std430 for push constants and storage buffers,
+// scalar for blocks declared with GL_EXT_scalar_block_layout
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layout {
+    Std140,
+    Std430,
+    Scalar,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TypeLayout {
+    pub size: usize,
+    pub align: usize,
+}
 
 pub trait Glsl {
     const NAME: &'static str;
+    const STD140: TypeLayout;
+    const STD430: TypeLayout;
+    const SCALAR: TypeLayout;
+
+    // outermost first, NAME is the element type of arrays
+    fn array_lengths() -> Vec<usize> {
+        Vec::new()
+    }
 }
 
 pub struct GlslField {
     pub name: &'static str,
     pub ty: &'static str,
+    pub array_lengths: fn() -> Vec<usize>,
 }
 
 pub trait GlslStruct: Glsl {
+    // the layouts checked by the derive, the struct can only be written in languages supporting
+    // one of them
+    const LAYOUTS: &'static [Layout];
     const FIELDS: &'static [GlslField];
 
+    fn struct_definition(language: Language) -> String {
+        language.struct_definition(Self::NAME, Self::LAYOUTS, Self::FIELDS)
+    }
+
     fn glsl_struct_definition() -> String {
-        let mut def = String::from("struct ");
-        def.push_str(Self::NAME);
-        def.push_str(" {\n");
-        for field in Self::FIELDS {
-            def.push_str("  ");
-            def.push_str(field.ty);
-            def.push(' ');
-            def.push_str(field.name);
-            def.push_str(";\n");
-        }
-        def.push_str("};\n");
+        Self::struct_definition(Language::Glsl)
+    }
+}
+
+// fieldless enums, as uint constants named after the enum and the variant
+pub trait GlslEnum: Glsl {
+    const VARIANTS: &'static [(&'static str, u32)];
+
+    fn enum_definition(language: Language) -> String {
+        let mut def = String::new();
+        for (name, value) in Self::VARIANTS {
+            def.push_str(&language.const_definition(u32::NAME, name, &value.literal(language)));
+        }
         def
     }
+
+    fn glsl_enum_definition() -> String {
+        Self::enum_definition(Language::Glsl)
+    }
+}
+
+// types that can be written as literals, for constants
+pub trait GlslLiteral: Glsl {
+    fn literal(&self, language: Language) -> String;
+
+    fn glsl_literal(&self) -> String {
+        self.literal(Language::Glsl)
+    }
+}
+
+// generated by #[glsl_const] next to the rust constant
+pub struct GlslConst {
+    pub name: &'static str,
+    pub definition: fn(Language) -> String,
+}
+
+impl GlslConst {
+    pub fn const_definition(&self, language: Language) -> String {
+        (self.definition)(language)
+    }
+
+    pub fn glsl_const_definition(&self) -> String {
+        self.const_definition(Language::Glsl)
+    }
+}
+
+pub fn const_definition<T: GlslLiteral>(language: Language, name: &str, value: &T) -> String {
+    language.const_definition(T::NAME, name, &value.literal(language))
+}
+
+impl Layout {
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Std140 => "std140",
+            Self::Std430 => "std430",
+            Self::Scalar => "scalar",
+        }
+    }
+
+    pub const fn of<T: Glsl>(self) -> TypeLayout {
+        match self {
+            Self::Std140 => T::STD140,
+            Self::Std430 => T::STD430,
+            Self::Scalar => T::SCALAR,
+        }
+    }
+
+    // offsets of the members of a struct, in order
+    pub const fn offsets<const N: usize>(self, members: [TypeLayout; N]) -> [usize; N] {
+        let mut offsets = [0; N];
+        let mut end: usize = 0;
+        let mut idx = 0;
+        while idx < N {
+            offsets[idx] = end.next_multiple_of(members[idx].align);
+            end = offsets[idx] + members[idx].size;
+            idx += 1;
+        }
+        offsets
+    }
+
+    // the size is padded to the alignment, which is where the next member or array element starts
+    pub const fn struct_layout<const N: usize>(self, members: [TypeLayout; N]) -> TypeLayout {
+        let offsets = self.offsets(members);
+        let mut align = 1;
+        let mut idx = 0;
+        while idx < N {
+            if members[idx].align > align {
+                align = members[idx].align;
+            }
+            idx += 1;
+        }
+        if matches!(self, Self::Std140) {
+            align = align.next_multiple_of(16);
+        }
+        let end = if N > 0 {
+            offsets[N - 1] + members[N - 1].size
+        } else {
+            0
+        };
+        TypeLayout {
+            size: end.next_multiple_of(align),
+            align,
+        }
+    }
+
+    // std140 pads the elements to 16 bytes
+    pub const fn array(self, element: TypeLayout, len: usize) -> TypeLayout {
+        let align = if matches!(self, Self::Std140) {
+            element.align.next_multiple_of(16)
+        } else {
+            element.align
+        };
+        TypeLayout {
+            size: element.size.next_multiple_of(align) * len,
+            align,
+        }
+    }
+
+    // vec3 is aligned like vec4 but doesn't take its size
+    const fn vector(self, component: usize, components: usize) -> TypeLayout {
+        TypeLayout {
+            size: component * components,
+            align: if matches!(self, Self::Scalar) {
+                component
+            } else {
+                component * components.next_power_of_two()
+            },
+        }
+    }
+
+    // column major, laid out like an array of column vectors
+    const fn matrix(self, columns: usize, rows: usize) -> TypeLayout {
+        self.array(self.vector(4, rows), columns)
+    }
+}
+
+impl<T: Glsl, const N: usize> Glsl for [T; N] {
+    const NAME: &'static str = T::NAME;
+    const STD140: TypeLayout = Layout::Std140.array(T::STD140, N);
+    const STD430: TypeLayout = Layout::Std430.array(T::STD430, N);
+    const SCALAR: TypeLayout = Layout::Scalar.array(T::SCALAR, N);
+
+    fn array_lengths() -> Vec<usize> {
+        let mut lengths = vec![N];
+        lengths.extend(T::array_lengths());
+        lengths
+    }
+}
+
+// a bool with the size it has in blocks, anything but 0 reads as true in the shaders
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Pod, Zeroable)]
+pub struct Bool32(u32);
+
+impl From<bool> for Bool32 {
+    fn from(value: bool) -> Self {
+        Self(value.into())
+    }
+}
+
+impl From<Bool32> for bool {
+    fn from(value: Bool32) -> Self {
+        value.0 != 0
+    }
 }
 
 macro_rules! impl_glsl {
-    ($type:ty => $name:expr) => {
+    ($type:ty => $name:expr, $kind:ident($($arg:expr),*)) => {
         impl Glsl for $type {
             const NAME: &'static str = $name;
+            const STD140: TypeLayout = Layout::Std140.$kind($($arg),*);
+            const STD430: TypeLayout = Layout::Std430.$kind($($arg),*);
+            const SCALAR: TypeLayout = Layout::Scalar.$kind($($arg),*);
+        }
+    };
+}
+
+// bools take 4 bytes in blocks, unlike in rust, so struct fields use Bool32 and plain bools are
+// only for constants
+impl_glsl!(bool => "bool", vector(4, 1));
+impl_glsl!(Bool32 => "bool", vector(4, 1));
+impl_glsl!(f32 => "float", vector(4, 1));
+impl_glsl!(f64 => "double", vector(8, 1));
+impl_glsl!(i32 => "int", vector(4, 1));
+impl_glsl!(u32 => "uint", vector(4, 1));
+impl_glsl!(i64 => "int64_t", vector(8, 1));
+impl_glsl!(u64 => "uint64_t", vector(8, 1));
+
+impl_glsl!(glam::Vec2 => "vec2", vector(4, 2));
+impl_glsl!(glam::Vec3 => "vec3", vector(4, 3));
+impl_glsl!(glam::Vec4 => "vec4", vector(4, 4));
+impl_glsl!(glam::IVec2 => "ivec2", vector(4, 2));
+impl_glsl!(glam::IVec3 => "ivec3", vector(4, 3));
+impl_glsl!(glam::IVec4 => "ivec4", vector(4, 4));
+impl_glsl!(glam::UVec2 => "uvec2", vector(4, 2));
+impl_glsl!(glam::UVec3 => "uvec3", vector(4, 3));
+impl_glsl!(glam::UVec4 => "uvec4", vector(4, 4));
+
+impl_glsl!(glam::Mat3 => "mat3", matrix(3, 3));
+impl_glsl!(glam::Mat4 => "mat4", matrix(4, 4));
+
+macro_rules! impl_glsl_literal {
+    ($type:ty, $format:literal) => {
+        impl GlslLiteral for $type {
+            fn literal(&self, language: Language) -> String {
+                format!($format, self) + language.literal_suffix(Self::NAME)
+            }
         }
     };
+    ($type:ty, $component:ty) => {
+        impl GlslLiteral for $type {
+            fn literal(&self, language: Language) -> String {
+                let components = self
+                    .to_array()
+                    .iter()
+                    .map(|component| <$component>::literal(component, language))
+                    .collect::<Vec<_>>();
+                format!(
+                    "{}({})",
+                    language.type_name(Self::NAME),
+                    components.join(", ")
+                )
+            }
+        }
+    };
+}
+
+impl_glsl_literal!(bool, "{}");
+
+impl GlslLiteral for Bool32 {
+    fn literal(&self, language: Language) -> String {
+        bool::from(*self).literal(language)
+    }
 }
 
-impl_glsl!(f32 => "float");
-impl_glsl!(f64 => "double");
-impl_glsl!(i32 => "int");
-impl_glsl!(u32 => "uint");
-impl_glsl!(i64 => "int64_t");
-impl_glsl!(u64 => "uint64_t");
+impl_glsl_literal!(f32, "{:?}");
+impl_glsl_literal!(f64, "{:?}");
+impl_glsl_literal!(i32, "{}");
+impl_glsl_literal!(u32, "{}");
+impl_glsl_literal!(i64, "{}");
+impl_glsl_literal!(u64, "{}");
+
+impl_glsl_literal!(glam::Vec2, f32);
+impl_glsl_literal!(glam::Vec3, f32);
+impl_glsl_literal!(glam::Vec4, f32);
+impl_glsl_literal!(glam::IVec2, i32);
+impl_glsl_literal!(glam::IVec3, i32);
+impl_glsl_literal!(glam::IVec4, i32);
+impl_glsl_literal!(glam::UVec2, u32);
+impl_glsl_literal!(glam::UVec3, u32);
+impl_glsl_literal!(glam::UVec4, u32);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const fn layout(size: usize, align: usize) -> TypeLayout {
+        TypeLayout { size, align }
+    }
+
+    #[test]
+    fn vec3_alignment() {
+        assert_eq!(Layout::Std140.of::<glam::Vec3>(), layout(12, 16));
+        assert_eq!(Layout::Std430.of::<glam::Vec3>(), layout(12, 16));
+        assert_eq!(Layout::Scalar.of::<glam::Vec3>(), layout(12, 4));
+
+        // a float fits in the padding after a vec3, but a vec3 doesn't after a float
+        let members = [f32::STD140, glam::Vec3::STD140, f32::STD140];
+        assert_eq!(Layout::Std140.offsets(members), [0, 16, 28]);
+        let members = [f32::SCALAR, glam::Vec3::SCALAR, f32::SCALAR];
+        assert_eq!(Layout::Scalar.offsets(members), [0, 4, 16]);
+    }
+
+    #[test]
+    fn array_stride() {
+        // std140 rounds the stride up to 16 bytes, even for scalars
+        assert_eq!(<[f32; 4]>::STD140, layout(64, 16));
+        assert_eq!(<[f32; 4]>::STD430, layout(16, 4));
+        assert_eq!(<[f32; 4]>::SCALAR, layout(16, 4));
+
+        assert_eq!(<[glam::Vec2; 3]>::STD140, layout(48, 16));
+        assert_eq!(<[glam::Vec2; 3]>::STD430, layout(24, 8));
+
+        // vec3 elements are padded to their alignment, except in scalar
+        assert_eq!(<[glam::Vec3; 2]>::STD140, layout(32, 16));
+        assert_eq!(<[glam::Vec3; 2]>::STD430, layout(32, 16));
+        assert_eq!(<[glam::Vec3; 2]>::SCALAR, layout(24, 4));
+
+        // arrays of arrays have the stride of the inner array
+        assert_eq!(<[[f32; 2]; 3]>::STD140, layout(96, 16));
+        assert_eq!(<[[f32; 2]; 3]>::STD430, layout(24, 4));
+        assert_eq!(<[[f32; 2]; 3]>::array_lengths(), [3, 2]);
+    }
 
-impl_glsl!(glam::Vec2 => "vec2");
-impl_glsl!(glam::Vec3 => "vec3");
-impl_glsl!(glam::Vec4 => "vec4");
+    #[test]
+    fn matrices() {
+        // three vec3 columns, each padded to a vec4
+        assert_eq!(glam::Mat3::STD140, layout(48, 16));
+        assert_eq!(glam::Mat3::STD430, layout(48, 16));
+        assert_eq!(glam::Mat3::SCALAR, layout(36, 4));
 
-impl_glsl!(glam::Mat4 => "mat4");
+        assert_eq!(glam::Mat4::STD140, layout(64, 16));
+        assert_eq!(glam::Mat4::STD430, layout(64, 16));
+        assert_eq!(glam::Mat4::SCALAR, layout(64, 4));
+    }
+
+    #[test]
+    fn nested_structs() {
+        // struct Inner { vec2 a; float b; }
+        let inner =
+            |layout: Layout| layout.struct_layout([layout.of::<glam::Vec2>(), layout.of::<f32>()]);
+        // std140 rounds the alignment of structs up to 16, and their size with it
+        assert_eq!(inner(Layout::Std140), layout(16, 16));
+        assert_eq!(inner(Layout::Std430), layout(16, 8));
+        assert_eq!(inner(Layout::Scalar), layout(12, 4));
+
+        // struct Outer { float a; Inner b; float c; }
+        let outer = |layout: Layout| {
+            let members = [layout.of::<f32>(), inner(layout), layout.of::<f32>()];
+            (layout.offsets(members), layout.struct_layout(members))
+        };
+        assert_eq!(outer(Layout::Std140), ([0, 16, 32], layout(48, 16)));
+        assert_eq!(outer(Layout::Std430), ([0, 8, 24], layout(32, 8)));
+        assert_eq!(outer(Layout::Scalar), ([0, 4, 16], layout(20, 4)));
+
+        // arrays of structs
+        assert_eq!(
+            Layout::Std140.array(inner(Layout::Std140), 2),
+            layout(32, 16)
+        );
+        assert_eq!(
+            Layout::Std430.array(inner(Layout::Std430), 2),
+            layout(32, 8)
+        );
+        assert_eq!(
+            Layout::Scalar.array(inner(Layout::Scalar), 2),
+            layout(24, 4)
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use std::marker::PhantomData;
+
+use glsl::{glsl_const, Bool32, GlslEnum, GlslStruct, Language};
+
+#[glsl_const(header = "snapshot")]
+const MAX_LIGHTS: u32 = 8;
+
+#[glsl_const(header = "snapshot")]
+const SHADOWS: bool = true;
+
+#[repr(u32)]
+#[derive(GlslEnum)]
+#[glsl(header = "snapshot")]
+#[allow(dead_code)]
+enum LightKind {
+    Point,
+    Spot = 4,
+    #[glsl(rename = "SUN")]
+    Directional,
+}
+
+#[repr(C)]
+#[derive(GlslStruct)]
+#[glsl(header = "snapshot")]
+struct Light {
+    position: glam::Vec3,
+    kind: u32,
+    #[glsl(rename = "castsShadows")]
+    casts_shadows: Bool32,
+    #[glsl(skip)]
+    marker: PhantomData<u32>,
+}
+
+#[repr(C)]
+#[derive(GlslStruct)]
+#[glsl(header = "snapshot_lights")]
+struct Lights {
+    lights: [Light; 2],
+}
+
+fn source(name: &str) -> String {
+    glsl::headers(|_| &[Language::Glsl])
+        .into_iter()
+        .find(|header| header.name == name)
+        .unwrap()
+        .source
+}
+
+#[test]
+fn header() {
+    assert_eq!(
+        source("snapshot"),
+        "// AUTO-GENERATED: do not edit
+
+#ifndef SNAPSHOT_H_GLSL_
+#define SNAPSHOT_H_GLSL_
+
+const uint MAX_LIGHTS = 8u;
+const bool SHADOWS = true;
+
+const uint LIGHT_KIND_POINT = 0u;
+const uint LIGHT_KIND_SPOT = 4u;
+const uint SUN = 5u;
+
+struct Light {
+  vec3 position;
+  uint kind;
+  bool castsShadows;
+};
+
+#endif
+"
+    );
+}
+
+#[test]
+fn includes() {
+    assert_eq!(
+        source("snapshot_lights"),
+        "// AUTO-GENERATED: do not edit
+
+#ifndef SNAPSHOT_LIGHTS_H_GLSL_
+#define SNAPSHOT_LIGHTS_H_GLSL_
+
+#include \"snapshot.h.glsl\"
+
+struct Lights {
+  Light lights[2];
+};
+
+#endif
+"
+    );
+}
+
+#[test]
+fn rust_constants() {
+    assert_eq!(MAX_LIGHTS, 8);
+    assert_eq!(
+        GLSL_SHADOWS.glsl_const_definition(),
+        "const bool SHADOWS = true;\n"
+    );
+    assert_eq!(
+        GLSL_MAX_LIGHTS.glsl_const_definition(),
+        "const uint MAX_LIGHTS = 8u;\n"
+    );
+    assert_eq!(LightKind::Directional as u32, 5);
+    assert!(bool::from(
+        Light {
+            position: glam::Vec3::ZERO,
+            kind: LightKind::Point as u32,
+            casts_shadows: true.into(),
+            marker: PhantomData,
+        }
+        .casts_shadows
+    ));
+}
@@ -0,0 +1,7 @@
+// each misuse of the derives, and each mismatch between a struct and the layouts it's checked
+// against, fails to compile pointing at the offending item, the expected errors are in the .stderr
+// files next to the cases
+#[test]
+fn compile_errors() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
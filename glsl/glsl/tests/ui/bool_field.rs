@@ -0,0 +1,11 @@
+use glsl::GlslStruct;
+
+// rust bools are a byte, the shaders would read 3 bytes of padding with them
+#[derive(GlslStruct)]
+#[repr(C)]
+struct Flags {
+    visible: bool,
+    kinds: [u32; 2],
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use glsl::GlslStruct;
+
+// the field would be declared without a name
+#[derive(GlslStruct)]
+#[repr(C)]
+struct Light {
+    #[glsl(rename = "")]
+    color: glam::Vec4,
+}
+
+fn main() {}
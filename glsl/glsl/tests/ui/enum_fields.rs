@@ -0,0 +1,11 @@
+use glsl::GlslEnum;
+
+// only the discriminants become GLSL constants
+#[derive(GlslEnum)]
+#[repr(u32)]
+enum Light {
+    Point,
+    Spot(f32),
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use glsl::GlslEnum;
+
+// the shaders read a uint, which a byte sized enum isn't laid out like
+#[derive(GlslEnum)]
+#[repr(u8)]
+enum Kind {
+    Point,
+    Spot,
+}
+
+fn main() {}
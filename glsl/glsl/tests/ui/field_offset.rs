@@ -0,0 +1,12 @@
+use glsl::GlslStruct;
+
+// std140 aligns the vec3 to 16 bytes, after 12 bytes of padding rust doesn't have
+#[derive(GlslStruct)]
+#[glsl(layout(std140))]
+#[repr(C)]
+struct Light {
+    intensity: f32,
+    color: glam::Vec3,
+}
+
+fn main() {}
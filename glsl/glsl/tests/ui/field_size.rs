@@ -0,0 +1,11 @@
+use glsl::GlslStruct;
+
+// std430 arrays are tightly packed, but std140 pads each element to 16 bytes
+#[derive(GlslStruct)]
+#[glsl(layout(std430, std140))]
+#[repr(C)]
+struct Weights {
+    weights: [f32; 4],
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use glsl::GlslStruct;
+
+// the layout would depend on T, which the checks can't name
+#[derive(GlslStruct)]
+#[repr(C)]
+struct Pair<T> {
+    first: T,
+    second: T,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use glsl::GlslStruct;
+
+// scalar aligns vectors to their components, but rust aligns glam's vec4 to 16 bytes
+#[derive(GlslStruct)]
+#[repr(C)]
+struct Plane {
+    offset: f32,
+    normal: glam::Vec4,
+}
+
+fn main() {}
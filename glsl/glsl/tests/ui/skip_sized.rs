@@ -0,0 +1,12 @@
+use glsl::GlslStruct;
+
+// skipped fields would shift the offsets of the GLSL ones
+#[derive(GlslStruct)]
+#[repr(C)]
+struct Light {
+    color: glam::Vec4,
+    #[glsl(skip)]
+    kind: u32,
+}
+
+fn main() {}
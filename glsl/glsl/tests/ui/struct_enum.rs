@@ -0,0 +1,11 @@
+use glsl::GlslStruct;
+
+// enums are uints in the shaders, not blocks
+#[derive(GlslStruct)]
+#[repr(u32)]
+enum Kind {
+    Point,
+    Spot,
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use glsl::GlslStruct;
+
+// the fields match, but std140 rounds the size of the struct up to 16 bytes
+#[derive(GlslStruct)]
+#[glsl(layout(std140))]
+#[repr(C)]
+struct Sphere {
+    center: glam::Vec2,
+    radius: f32,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use glsl::GlslStruct;
+
+// GLSL has no unions
+#[derive(GlslStruct)]
+#[repr(C)]
+union Value {
+    float: f32,
+    uint: u32,
+}
+
+fn main() {}
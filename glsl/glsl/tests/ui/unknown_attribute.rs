@@ -0,0 +1,11 @@
+use glsl::GlslStruct;
+
+// a typo of rename
+#[derive(GlslStruct)]
+#[repr(C)]
+struct Light {
+    #[glsl(name = "lightColor")]
+    color: glam::Vec4,
+}
+
+fn main() {}
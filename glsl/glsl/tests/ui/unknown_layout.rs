@@ -0,0 +1,11 @@
+use glsl::GlslStruct;
+
+// only std140, std430 and scalar are checked
+#[derive(GlslStruct)]
+#[glsl(layout(std140, packed))]
+#[repr(C)]
+struct Light {
+    color: glam::Vec4,
+}
+
+fn main() {}
@@ -1,43 +1,441 @@
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::{parse_macro_input, Data, DataStruct, DeriveInput, Fields};
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use syn::{
+    parse_macro_input, spanned::Spanned, Attribute, Data, DataEnum, DataStruct, DeriveInput, Error,
+    Fields, ItemConst, LitStr, Member, Result,
+};
 
-#[proc_macro_derive(GlslStruct)]
+const LAYOUTS: [&str; 3] = ["std140", "std430", "scalar"];
+// the layout of the buffer references in the shaders
+const DEFAULT_LAYOUT: &str = "scalar";
+
+// struct attributes: #[glsl(layout(std140, ...), rename = "Name", header = "name")], the
+// layouts the struct must match are checked at compile time and the definition is registered in
+// name.h.glsl
+// field attributes: #[glsl(rename = "name")] or #[glsl(skip)] for zero sized fields only used in
+// rust, e.g. markers
+// bool fields are rejected since they're a byte in rust, glsl::Bool32 has their block layout
+#[proc_macro_derive(GlslStruct, attributes(glsl))]
 pub fn derive_macro_glsl(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
+    glsl_struct(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
 
-    let name = input.ident;
-    let name_str = name.to_string();
-    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
-
-    let fields = match input.data {
-        Data::Struct(DataStruct {
-            fields: Fields::Named(ref fields),
-            ..
-        }) => fields.named.iter().map(|field| {
-            let field_name_str = field.ident.as_ref().unwrap().to_string();
-            let field_type = &field.ty;
-
-            quote! {
-                ::glsl::GlslField {
-                    name: #field_name_str,
-                    ty: <#field_type as ::glsl::Glsl>::NAME,
-                }
-            }
-        }),
-        _ => unimplemented!(),
+// enum attributes: #[glsl(rename = "Name", header = "name")], variant attributes: #[glsl(rename = "NAME")]
+#[proc_macro_derive(GlslEnum, attributes(glsl))]
+pub fn derive_macro_glsl_enum(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    glsl_enum(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+// #[glsl_const] on a const item describes it in a GLSL_<NAME> constant,
+// #[glsl_const(header = "name")] also registers it in name.h.glsl
+#[proc_macro_attribute]
+pub fn glsl_const(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = TokenStream2::from(args);
+    let item = parse_macro_input!(item as ItemConst);
+    glsl_const_item(args, &item)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+#[derive(Default)]
+struct Attributes {
+    rename: Option<String>,
+    header: Option<String>,
+    skip: bool,
+    layouts: Vec<String>,
+}
+
+struct Field<'a> {
+    member: Member,
+    name: String,
+    ty: &'a syn::Type,
+}
+
+fn glsl_struct(input: &DeriveInput) -> Result<TokenStream2> {
+    let name = &input.ident;
+    let attributes = parse_attributes(&input.attrs, &["layout", "rename", "header"])?;
+    let name_str = attributes.rename.unwrap_or_else(|| name.to_string());
+
+    // offset_of! can't name a generic struct outside of it
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(
+            input.generics.span(),
+            "GlslStruct can't check the layout of generic structs",
+        ));
+    }
+
+    let fields = match &input.data {
+        Data::Struct(DataStruct { fields, .. }) => fields,
+        Data::Enum(data) => {
+            return Err(Error::new(
+                data.enum_token.span,
+                "GlslStruct only supports structs, fieldless enums can derive GlslEnum",
+            ))
+        }
+        Data::Union(data) => {
+            return Err(Error::new(
+                data.union_token.span,
+                "GlslStruct only supports structs",
+            ))
+        }
     };
 
-    quote! {
-        impl #impl_generics ::glsl::Glsl for #name #ty_generics #where_clause {
+    let mut glsl_fields = Vec::new();
+    let mut skip_checks = Vec::new();
+    for (idx, field) in fields.iter().enumerate() {
+        let attributes = parse_attributes(&field.attrs, &["rename", "skip"])?;
+        if attributes.skip {
+            skip_checks.push(skip_check(&field.ty));
+            continue;
+        }
+        reject_bools(&field.ty)?;
+        let member = field
+            .ident
+            .clone()
+            .map_or_else(|| Member::from(idx), Member::Named);
+        let name = attributes.rename.unwrap_or_else(|| match &member {
+            Member::Named(ident) => ident.to_string(),
+            Member::Unnamed(index) => format!("_{}", index.index),
+        });
+        glsl_fields.push(Field {
+            member,
+            name,
+            ty: &field.ty,
+        });
+    }
+    if glsl_fields.is_empty() {
+        return Err(Error::new(
+            name.span(),
+            "GLSL structs need at least one field",
+        ));
+    }
+
+    let field_names = glsl_fields.iter().map(|field| &field.name);
+    let field_types = glsl_fields.iter().map(|field| field.ty).collect::<Vec<_>>();
+
+    let [std140, std430, scalar] = LAYOUTS.map(|layout| {
+        let variant = layout_variant(layout);
+        quote! {
+            ::glsl::Layout::#variant.struct_layout([
+                #( ::glsl::Layout::#variant.of::<#field_types>(), )*
+            ])
+        }
+    });
+
+    let mut layouts = attributes.layouts;
+    if layouts.is_empty() {
+        layouts.push(DEFAULT_LAYOUT.to_owned());
+    }
+    let layout_variants = layouts.iter().map(|layout| layout_variant(layout));
+    let checks = layouts
+        .iter()
+        .map(|layout| layout_check(name, &name_str, layout, &glsl_fields));
+
+    let registration = attributes.header.map(|header| {
+        registration(
+            &header,
+            &name_str,
+            &quote! { ::std::vec![ #( <#field_types as ::glsl::Glsl>::NAME, )* ] },
+            &quote! { <#name as ::glsl::GlslStruct>::struct_definition },
+        )
+    });
+
+    Ok(quote! {
+        impl ::glsl::Glsl for #name {
             const NAME: &'static str = #name_str;
+            const STD140: ::glsl::TypeLayout = #std140;
+            const STD430: ::glsl::TypeLayout = #std430;
+            const SCALAR: ::glsl::TypeLayout = #scalar;
         }
 
-        impl #impl_generics ::glsl::GlslStruct for #name #ty_generics #where_clause {
+        impl ::glsl::GlslStruct for #name {
+            const LAYOUTS: &'static [::glsl::Layout] = &[ #( ::glsl::Layout::#layout_variants, )* ];
             const FIELDS: &'static [::glsl::GlslField] = &[
-                #( #fields, )*
+                #( ::glsl::GlslField {
+                    name: #field_names,
+                    ty: <#field_types as ::glsl::Glsl>::NAME,
+                    array_lengths: <#field_types as ::glsl::Glsl>::array_lengths,
+                }, )*
             ];
         }
+
+        #( #skip_checks )*
+
+        #( #checks )*
+
+        #registration
+    })
+}
+
+fn registration(
+    header: &str,
+    name: &str,
+    dependencies: &TokenStream2,
+    definition: &TokenStream2,
+) -> TokenStream2 {
+    quote! {
+        ::glsl::inventory::submit! {
+            ::glsl::GlslItem {
+                header: #header,
+                name: #name,
+                dependencies: || #dependencies,
+                definition: #definition,
+                file: ::core::file!(),
+                line: ::core::line!(),
+            }
+        }
+    }
+}
+
+// fields of type bool or arrays of them
+fn reject_bools(ty: &syn::Type) -> Result<()> {
+    match ty {
+        syn::Type::Path(path) if path.qself.is_none() && path.path.is_ident("bool") => {
+            Err(Error::new(
+                ty.span(),
+                "bools are a byte in rust but 4 in GLSL blocks, use glsl::Bool32",
+            ))
+        }
+        syn::Type::Array(array) => reject_bools(&array.elem),
+        syn::Type::Paren(paren) => reject_bools(&paren.elem),
+        syn::Type::Group(group) => reject_bools(&group.elem),
+        _ => Ok(()),
+    }
+}
+
+// anything but zero sized fields would be read by the shaders as part of the fields after them
+fn skip_check(ty: &syn::Type) -> TokenStream2 {
+    quote_spanned! {ty.span()=>
+        const _: () = assert!(
+            ::core::mem::size_of::<#ty>() == 0,
+            "only zero sized fields can be skipped, GLSL has no room for them"
+        );
+    }
+}
+
+fn layout_check(name: &syn::Ident, name_str: &str, layout: &str, fields: &[Field]) -> TokenStream2 {
+    let variant = layout_variant(layout);
+    let field_types = fields.iter().map(|field| field.ty);
+    let offset_checks = fields.iter().enumerate().map(|(idx, field)| {
+        let member = &field.member;
+        let message = format!(
+            "`{name_str}::{}` is not at its {layout} offset, \
+             reorder the fields or add padding before it",
+            field.name
+        );
+        let ty = field.ty;
+        // e.g. std140 pads array elements, the shaders would read the padding as elements
+        let size_message = format!(
+            "`{name_str}::{}` doesn't have its {layout} size, use a type matching its layout",
+            field.name
+        );
+        quote_spanned! {ty.span()=>
+            assert!(::core::mem::offset_of!(#name, #member) == offsets[#idx], #message);
+            assert!(
+                ::core::mem::size_of::<#ty>() == ::glsl::Layout::#variant.of::<#ty>().size,
+                #size_message
+            );
+        }
+    });
+    let size_message =
+        format!("`{name_str}` doesn't have its {layout} size, add or remove padding at its end");
+    let size_check = quote_spanned! {name.span()=>
+        assert!(
+            ::core::mem::size_of::<#name>() == ::glsl::Layout::#variant.of::<#name>().size,
+            #size_message
+        );
+    };
+    quote! {
+        const _: () = {
+            let offsets = ::glsl::Layout::#variant.offsets([
+                #( ::glsl::Layout::#variant.of::<#field_types>(), )*
+            ]);
+            #( #offset_checks )*
+            #size_check
+        };
+    }
+}
+
+fn glsl_const_item(args: TokenStream2, item: &ItemConst) -> Result<TokenStream2> {
+    let mut header = None;
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("header") {
+            header = Some(meta.value()?.parse::<LitStr>()?.value());
+            Ok(())
+        } else {
+            Err(meta.error("unknown glsl_const argument, expected header"))
+        }
+    });
+    syn::parse::Parser::parse2(parser, args)?;
+
+    let ItemConst { vis, ident, ty, .. } = item;
+    let name_str = ident.to_string();
+    let glsl_ident = format_ident!("GLSL_{}", ident);
+
+    let registration = header.map(|header| {
+        registration(
+            &header,
+            &name_str,
+            &quote! { ::std::vec::Vec::new() },
+            &quote! { |language| #glsl_ident.const_definition(language) },
+        )
+    });
+
+    Ok(quote! {
+        #item
+
+        #vis const #glsl_ident: ::glsl::GlslConst = ::glsl::GlslConst {
+            name: #name_str,
+            definition: |language| ::glsl::const_definition::<#ty>(language, #name_str, &#ident),
+        };
+
+        #registration
+    })
+}
+
+fn glsl_enum(input: &DeriveInput) -> Result<TokenStream2> {
+    let name = &input.ident;
+    let attributes = parse_attributes(&input.attrs, &["rename", "header"])?;
+    let name_str = attributes.rename.unwrap_or_else(|| name.to_string());
+    let prefix = upper_snake_case(&name_str);
+
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(
+            input.generics.span(),
+            "GlslEnum doesn't support generic enums",
+        ));
+    }
+
+    let Data::Enum(DataEnum { variants, .. }) = &input.data else {
+        return Err(Error::new(name.span(), "GlslEnum only supports enums"));
+    };
+
+    // laid out like the uint the shaders read
+    let is_u32 = input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("repr"))
+        .any(|attr| {
+            attr.parse_args::<syn::Ident>()
+                .is_ok_and(|repr| repr == "u32")
+        });
+    if !is_u32 {
+        return Err(Error::new(
+            name.span(),
+            "GlslEnum needs #[repr(u32)] to be laid out like a uint",
+        ));
+    }
+
+    let mut constants = Vec::new();
+    for variant in variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(Error::new(
+                variant.fields.span(),
+                "GlslEnum only supports fieldless variants",
+            ));
+        }
+        let attributes = parse_attributes(&variant.attrs, &["rename"])?;
+        let constant = attributes.rename.unwrap_or_else(|| {
+            format!("{prefix}_{}", upper_snake_case(&variant.ident.to_string()))
+        });
+        let ident = &variant.ident;
+        constants.push(quote! { (#constant, #name::#ident as u32) });
+    }
+
+    let registration = attributes.header.map(|header| {
+        registration(
+            &header,
+            &name_str,
+            &quote! { ::std::vec::Vec::new() },
+            &quote! { <#name as ::glsl::GlslEnum>::enum_definition },
+        )
+    });
+
+    Ok(quote! {
+        impl ::glsl::Glsl for #name {
+            const NAME: &'static str = <u32 as ::glsl::Glsl>::NAME;
+            const STD140: ::glsl::TypeLayout = <u32 as ::glsl::Glsl>::STD140;
+            const STD430: ::glsl::TypeLayout = <u32 as ::glsl::Glsl>::STD430;
+            const SCALAR: ::glsl::TypeLayout = <u32 as ::glsl::Glsl>::SCALAR;
+        }
+
+        impl ::glsl::GlslEnum for #name {
+            const VARIANTS: &'static [(&'static str, u32)] = &[ #( #constants, )* ];
+        }
+
+        #registration
+    })
+}
+
+// parses the #[glsl(...)] attributes, rejecting keys that aren't allowed where they're used
+fn parse_attributes(attrs: &[Attribute], allowed: &[&str]) -> Result<Attributes> {
+    let mut attributes = Attributes::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("glsl")) {
+        attr.parse_nested_meta(|meta| {
+            let key = meta.path.to_token_stream().to_string();
+            if !allowed.contains(&key.as_str()) {
+                return Err(meta.error(format!(
+                    "unknown glsl attribute, expected one of {}",
+                    allowed.join(", ")
+                )));
+            }
+            match key.as_str() {
+                "rename" => {
+                    let name = meta.value()?.parse::<LitStr>()?;
+                    if name.value().is_empty() {
+                        return Err(Error::new(name.span(), "GLSL names can't be empty"));
+                    }
+                    attributes.rename = Some(name.value());
+                }
+                "header" => {
+                    let header = meta.value()?.parse::<LitStr>()?;
+                    if header.value().is_empty() {
+                        return Err(Error::new(header.span(), "header names can't be empty"));
+                    }
+                    attributes.header = Some(header.value());
+                }
+                "skip" => attributes.skip = true,
+                _ => meta.parse_nested_meta(|layout| {
+                    let name = layout.path.to_token_stream().to_string();
+                    if !LAYOUTS.contains(&name.as_str()) {
+                        return Err(layout.error(format!(
+                            "unknown glsl layout, expected one of {}",
+                            LAYOUTS.join(", ")
+                        )));
+                    }
+                    attributes.layouts.push(name);
+                    Ok(())
+                })?,
+            }
+            Ok(())
+        })?;
+    }
+    Ok(attributes)
+}
+
+fn layout_variant(layout: &str) -> syn::Ident {
+    let mut chars = layout.chars();
+    let first = chars.next().unwrap().to_ascii_uppercase();
+    format_ident!("{}{}", first, chars.as_str())
+}
+
+// MaterialKind to MATERIAL_KIND
+fn upper_snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    let mut previous_lowercase = false;
+    for char in name.chars() {
+        if char.is_uppercase() && previous_lowercase {
+            snake.push('_');
+        }
+        previous_lowercase = char.is_lowercase() || char.is_ascii_digit();
+        snake.push(char.to_ascii_uppercase());
     }
-    .into()
+    snake
 }
@@ -0,0 +1,403 @@
+mod texture;
+
+use std::path::Path;
+
+use rayon::prelude::*;
+
+use scene::{
+    bvh::{Bvh, Ray},
+    Scene,
+};
+use shared::{
+    bsdf::{self, MaterialHit},
+    inputs,
+    rng::Rng,
+};
+
+use texture::Texture;
+
+// the constants of the gpu path tracer, and mirrors the engine's camera
+pub mod conf {
+    pub use shared::conf::T_MIN;
+
+    pub const Z_NEAR: f32 = 1e-1;
+    pub const Z_FAR: f32 = 1e+4;
+}
+
+// CPU counterpart of the ray tracing pipeline, to produce reference images without a GPU
+pub struct PathTracer<'a> {
+    scene: &'a Scene,
+    bvh: Bvh,
+    images: Vec<Texture>,
+}
+
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<glam::Vec3>,
+}
+
+// mirrors the payload filled in by pathtracer.rchit.glsl
+struct HitInfo {
+    position: glam::Vec3,
+    normal: glam::Vec3,
+    uv: glam::Vec2,
+    color: glam::Vec3,
+    material: u32,
+}
+
+impl<'a> PathTracer<'a> {
+    pub fn new(scene: &'a Scene) -> Self {
+        firestorm::profile_method!(new);
+
+        let images = scene
+            .data
+            .images
+            .par_iter()
+            .map(|image| Texture::load(&image.source))
+            .collect();
+
+        Self {
+            scene,
+            bvh: Bvh::new(scene),
+            images,
+        }
+    }
+
+    // averages as many frames as the gpu path tracer would accumulate
+    pub fn render(&self, camera: &inputs::Camera, resolution: (u32, u32), frames: u32) -> Image {
+        firestorm::profile_method!(render);
+
+        let (width, height) = resolution;
+        let pixels = (0..height)
+            .into_par_iter()
+            .flat_map_iter(|y| {
+                (0..width).map(move |x| {
+                    let pixel = glam::UVec2::new(x, y);
+                    (0..frames).fold(glam::Vec3::ZERO, |color, frame| {
+                        let radiance = self.radiance(camera, pixel, resolution, frame);
+                        if frame > 0 {
+                            color.lerp(radiance, 1. / (frame + 1) as f32)
+                        } else {
+                            radiance
+                        }
+                    })
+                })
+            })
+            .collect();
+
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    // mirrors pathtracer.rgen.glsl
+    fn radiance(
+        &self,
+        camera: &inputs::Camera,
+        pixel: glam::UVec2,
+        resolution: (u32, u32),
+        frame: u32,
+    ) -> glam::Vec3 {
+        let mut rng = Rng::new(pixel, frame);
+
+        // anti-aliased pixel
+        let position = pixel.as_vec2() + rng.vec2();
+        let resolution = glam::UVec2::from(resolution).as_vec2();
+        let coords = 2. * (position / resolution) - 1.;
+
+        let origin = camera.view / glam::Vec4::W;
+        let target = camera.proj / coords.extend(1.).extend(1.);
+        let direction = camera.view / target.truncate().normalize().extend(0.);
+
+        let mut origin = origin.truncate();
+        let mut direction = direction.truncate();
+
+        let environment = self.scene.info.environment.color;
+        let (min_bounces, max_bounces) = self.scene.info.render_settings.bounces();
+
+        let mut radiance = glam::Vec3::ZERO;
+        let mut throughput = glam::Vec3::ONE;
+        for depth in 0..max_bounces {
+            let Some(hit) = self.trace(origin, direction) else {
+                radiance += throughput * environment;
+                break;
+            };
+
+            let wo = -direction;
+            let mut n = hit.normal;
+            if n.dot(wo) < 0. {
+                n = -n;
+            }
+
+            let material = self.material_at_hit(hit.material, hit.uv, hit.color);
+
+            radiance += throughput * material.emittance;
+
+            // Don't need to sample BSDF on last bounce
+            if depth == max_bounces - 1 {
+                break;
+            }
+
+            // Russian Roulette
+            if depth > min_bounces {
+                let p_rr = bsdf::luminance(throughput).min(0.95);
+                if p_rr < rng.float() {
+                    break;
+                }
+                throughput /= p_rr;
+            }
+
+            // BSDF evaluation
+            #[allow(clippy::float_cmp)]
+            let mut is_specular = material.metallic == 1. && material.roughness == 0.;
+            if !is_specular {
+                let p_spec = bsdf::specular_probability(&material, wo, n);
+
+                if rng.float() < p_spec {
+                    is_specular = true;
+                    throughput /= p_spec;
+                } else {
+                    throughput /= 1. - p_spec;
+                }
+            }
+
+            // Importance sample the BSDF
+            let Some((wi, weight)) = bsdf::sample(&material, is_specular, wo, n, rng.vec2()) else {
+                break;
+            };
+
+            throughput *= weight;
+
+            origin = hit.position;
+            direction = wi;
+        }
+        radiance
+    }
+
+    // mirrors pathtracer.rchit.glsl
+    fn trace(&self, origin: glam::Vec3, direction: glam::Vec3) -> Option<HitInfo> {
+        // the bvh only reports hits in front of the origin, so it's moved by the minimum distance
+        let hit = self
+            .bvh
+            .closest_hit(&Ray::new(origin + direction * conf::T_MIN, direction))?;
+
+        let instance = &self.scene.info.instances[hit.instance];
+        let primitive = &self.scene.info.primitive_infos[hit.primitive];
+        let indices =
+            &self.scene.data.indices[primitive.indices_offset as usize + 3 * hit.triangle..];
+        let idx = [indices[0], indices[1], indices[2]];
+        let vertices =
+            idx.map(|index| self.scene.data.vertices[(primitive.vertices_offset + index) as usize]);
+
+        let bary = glam::Vec3::new(
+            1. - hit.barycentrics.x - hit.barycentrics.y,
+            hit.barycentrics.x,
+            hit.barycentrics.y,
+        );
+        let interpolate = |attribute: fn(&scene::Vertex) -> glam::Vec4| {
+            attribute(&vertices[0]) * bary.x
+                + attribute(&vertices[1]) * bary.y
+                + attribute(&vertices[2]) * bary.z
+        };
+
+        let position = interpolate(|vertex| vertex.position).truncate();
+        let normal = interpolate(|vertex| vertex.normal).truncate().normalize();
+        let uv = interpolate(|vertex| vertex.tex_coords)
+            .truncate()
+            .truncate();
+
+        let color = u32::try_from(primitive.colors_offset).map_or(glam::Vec3::ONE, |offset| {
+            let colors = idx.map(|index| self.scene.data.colors[(offset + index) as usize]);
+            (colors[0] * bary.x + colors[1] * bary.y + colors[2] * bary.z).truncate()
+        });
+
+        Some(HitInfo {
+            position: instance.transform.transform_point3(position),
+            normal: instance.transform.transform_vector3(normal).normalize(),
+            uv,
+            color,
+            material: primitive.material,
+        })
+    }
+
+    // mirrors `material_info_at_hit` in pathtracer.rgen.glsl
+    fn material_at_hit(
+        &self,
+        material: u32,
+        coords: glam::Vec2,
+        vertex_color: glam::Vec3,
+    ) -> MaterialHit {
+        let material = &self.scene.data.materials[material as usize];
+        let texture = |texture: i32| {
+            usize::try_from(texture).ok().map(|texture| {
+                let image = self.scene.info.textures[texture].image_index;
+                self.images[image as usize].sample(coords)
+            })
+        };
+
+        let mut info = MaterialHit {
+            base_color: material.color * vertex_color,
+            metallic: material.metallic,
+            emittance: material.emittance,
+            roughness: material.roughness,
+        };
+        if let Some(color) = texture(material.color_texture) {
+            info.base_color *= color.truncate();
+        }
+        if let Some(emittance) = texture(material.emittance_texture) {
+            info.emittance *= emittance.truncate();
+        }
+        if let Some(metallic_roughness) = texture(material.metallic_roughness_texture) {
+            info.metallic *= metallic_roughness.z;
+            info.roughness *= metallic_roughness.y;
+        }
+        info
+    }
+}
+
+// same projection as the engine's camera controller
+pub fn camera(camera: &scene::Camera, resolution: (u32, u32)) -> inputs::Camera {
+    let aspect_ratio = resolution.0 as f32 / resolution.1 as f32;
+    inputs::Camera {
+        view: inputs::Transform::new(glam::Mat4::look_at_rh(
+            camera.position,
+            camera.target,
+            glam::Vec3::Y,
+        )),
+        proj: inputs::Transform::proj(glam::Mat4::perspective_rh(
+            camera.fov.to_radians(),
+            aspect_ratio,
+            conf::Z_NEAR,
+            conf::Z_FAR,
+        )),
+    }
+}
+
+impl Image {
+    // the format is picked from the extension, either OpenEXR or Radiance HDR
+    pub fn save(&self, filename: impl AsRef<Path>) {
+        let filename = filename.as_ref();
+        assert!(
+            filename
+                .extension()
+                .is_some_and(|extension| extension == "exr" || extension == "hdr"),
+            "Reference images can only be saved as exr or hdr"
+        );
+
+        let data = self.pixels.iter().flat_map(glam::Vec3::to_array).collect();
+        let image = image::Rgb32FImage::from_raw(self.width, self.height, data)
+            .expect("Image size does not match its resolution");
+        image::DynamicImage::ImageRgb32F(image)
+            .save(filename)
+            .expect("Failed to save reference image");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scene::generate;
+
+    const RESOLUTION: (u32, u32) = (32, 24);
+
+    fn render(scene: &Scene, frames: u32) -> Image {
+        PathTracer::new(scene).render(
+            &camera(&scene.info.cameras[0], RESOLUTION),
+            RESOLUTION,
+            frames,
+        )
+    }
+
+    fn pixel(image: &Image, x: u32, y: u32) -> glam::Vec3 {
+        image.pixels[(y * image.width + x) as usize]
+    }
+
+    // mean over the pixels in [min, max)
+    fn region(image: &Image, min: (u32, u32), max: (u32, u32)) -> glam::Vec3 {
+        let pixels = (min.1..max.1)
+            .flat_map(|y| (min.0..max.0).map(move |x| (x, y)))
+            .map(|(x, y)| pixel(image, x, y))
+            .collect::<Vec<_>>();
+        pixels.iter().sum::<glam::Vec3>() / pixels.len() as f32
+    }
+
+    // a white mirror reflects the whole environment, so it's invisible
+    #[test]
+    fn mirror_furnace() {
+        let mut scene = generate::white_furnace();
+        scene.data.materials[0].metallic = 1.;
+        scene.data.materials[0].roughness = 0.;
+        let environment = scene.info.environment.color;
+
+        let image = render(&scene, 4);
+        for (idx, &pixel) in image.pixels.iter().enumerate() {
+            assert!(
+                pixel.abs_diff_eq(environment, 1e-4),
+                "pixel {idx} is {pixel}, expected {environment}"
+            );
+        }
+    }
+
+    // the white diffuse sphere only darkens by the energy its BSDF doesn't reflect, which is
+    // what single scattering GGX and the fresnel transmission of the diffuse lobe lose, at most
+    // about a tenth facing the camera and more towards the grazing angles of its silhouette
+    #[test]
+    fn furnace() {
+        let scene = generate::white_furnace();
+        let environment = scene.info.environment.color;
+
+        let image = render(&scene, 256);
+        for (idx, &pixel) in image.pixels.iter().enumerate() {
+            assert!(
+                pixel.cmple(environment + 1e-3).all(),
+                "pixel {idx} is {pixel}, brighter than the environment {environment}"
+            );
+            assert!(
+                pixel.abs_diff_eq(environment, 0.3),
+                "pixel {idx} is {pixel}, too far from the environment {environment}"
+            );
+        }
+
+        let center = pixel(&image, RESOLUTION.0 / 2, RESOLUTION.1 / 2);
+        assert!(
+            center.abs_diff_eq(environment, 0.12),
+            "center is {center}, too far from the environment {environment}"
+        );
+        let mean = image.pixels.iter().sum::<glam::Vec3>() / image.pixels.len() as f32;
+        assert!(
+            mean.abs_diff_eq(environment, 0.05),
+            "mean is {mean}, too far from the environment {environment}"
+        );
+
+        // the background is hit by every sample of the corners
+        assert_eq!(pixel(&image, 0, 0), environment);
+    }
+
+    #[test]
+    fn cornell_box() {
+        let image = render(&generate::cornell_box(), 64);
+        assert!(image
+            .pixels
+            .iter()
+            .all(|pixel| pixel.is_finite() && pixel.min_element() >= 0.));
+
+        // the walls take the color of their material, and the light is brighter than all of them
+        let left = region(&image, (5, 6), (8, 18));
+        let right = region(&image, (24, 6), (27, 18));
+        let back = region(&image, (12, 8), (20, 14));
+        let light = region(&image, (14, 3), (18, 4));
+        assert!(left.x > 1.5 * left.y.max(left.z), "left wall is {left}");
+        assert!(
+            right.y > 1.5 * right.x.max(right.z),
+            "right wall is {right}"
+        );
+        assert!(back.min_element() > 0., "back wall is {back}");
+        assert!(
+            light.min_element() > left.max(right).max(back).max_element(),
+            "light is {light}"
+        );
+    }
+}
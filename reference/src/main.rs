@@ -0,0 +1,146 @@
+use std::{env, path::PathBuf, time::Instant};
+
+use reference::PathTracer;
+
+mod conf {
+    // same as the engine
+    pub const FRAME_RESOLUTION: (u32, u32) = (1600, 1200);
+    pub const FOV_DEGREES: f32 = 45.;
+    pub const FRAMES: u32 = 64;
+}
+
+enum Source {
+    File(PathBuf),
+    Generator(String),
+}
+
+struct Args {
+    source: Source,
+    output: PathBuf,
+    frames: u32,
+    resolution: Option<(u32, u32)>,
+    camera: usize,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let scene = match &args.source {
+        Source::File(filename) => scene::io::load(filename),
+        Source::Generator(name) => scene::generate::generate(name).unwrap_or_else(|| {
+            panic!(
+                "Unknown generator {name}, expected one of {}",
+                scene::generate::GENERATORS.join(", ")
+            )
+        }),
+    };
+
+    let resolution = args
+        .resolution
+        .or(scene.info.render_settings.resolution)
+        .unwrap_or(conf::FRAME_RESOLUTION);
+
+    // the engine's default camera when the scene has none
+    let camera = if scene.info.cameras.is_empty() {
+        let bounding_box = scene.info.bounding_box;
+        scene::Camera {
+            name: String::new(),
+            position: bounding_box.size() * 1.2 + bounding_box.center(),
+            target: bounding_box.center(),
+            fov: conf::FOV_DEGREES,
+        }
+    } else {
+        scene
+            .info
+            .cameras
+            .get(args.camera)
+            .unwrap_or_else(|| panic!("No camera {} in the scene", args.camera))
+            .clone()
+    };
+
+    let start = Instant::now();
+    let pathtracer = PathTracer::new(&scene);
+    let image = pathtracer.render(
+        &reference::camera(&camera, resolution),
+        resolution,
+        args.frames,
+    );
+    println!(
+        "Rendered {}x{} with {} samples per pixel in {:.2?}",
+        resolution.0,
+        resolution.1,
+        args.frames,
+        start.elapsed()
+    );
+
+    image.save(&args.output);
+    println!("Reference image saved to {}", args.output.display());
+
+    if firestorm::enabled() {
+        firestorm::save("./profiling_results/").expect("Failed to save profiling results");
+    }
+}
+
+impl Args {
+    // reference <scene.tsnasset> <output.exr|hdr> [options]
+    // reference --generate NAME <output.exr|hdr> [options]
+    //
+    // options: [--frames COUNT] [--resolution WIDTHxHEIGHT] [--camera INDEX]
+    fn parse() -> Self {
+        let mut positional = Vec::new();
+        let mut generator = None;
+        let mut frames = conf::FRAMES;
+        let mut resolution = None;
+        let mut camera = 0;
+
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .unwrap_or_else(|| panic!("{arg} requires a value"))
+            };
+            match arg.as_str() {
+                "--generate" => generator = Some(value()),
+                "--frames" => {
+                    let count = value();
+                    frames = count
+                        .parse()
+                        .unwrap_or_else(|_| panic!("Invalid frame count {count}"));
+                    assert!(frames > 0, "At least one frame is needed");
+                }
+                "--resolution" => {
+                    let size = value();
+                    resolution = Some(
+                        size.split_once('x')
+                            .and_then(|(width, height)| {
+                                Some((width.parse().ok()?, height.parse().ok()?))
+                            })
+                            .unwrap_or_else(|| panic!("Invalid resolution {size}")),
+                    );
+                }
+                "--camera" => {
+                    let index = value();
+                    camera = index
+                        .parse()
+                        .unwrap_or_else(|_| panic!("Invalid camera index {index}"));
+                }
+                _ if arg.starts_with("--") => panic!("Unknown argument {arg}"),
+                _ => positional.push(PathBuf::from(arg)),
+            }
+        }
+
+        let (source, output) = match (generator, positional.as_slice()) {
+            (Some(name), [output]) => (Source::Generator(name), output.clone()),
+            (None, [scene_file, output]) => (Source::File(scene_file.clone()), output.clone()),
+            _ => panic!("Expected a scene file, or --generate NAME, and an output image filename"),
+        };
+
+        Self {
+            source,
+            output,
+            frames,
+            resolution,
+            camera,
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use std::path::Path;
+
+// linear filtering and repeat addressing of an sRGB image, like the renderer's sampler
+pub struct Texture {
+    width: u32,
+    height: u32,
+    texels: Vec<glam::Vec4>,
+}
+
+impl Texture {
+    pub fn load(source: &Path) -> Self {
+        let image = image::open(source)
+            .unwrap_or_else(|_| panic!("Unable to open image {}", source.display()))
+            .into_rgba8();
+
+        // textures are uploaded as R8G8B8A8_SRGB, so every channel but alpha is decoded
+        let texels = image
+            .pixels()
+            .map(|pixel| {
+                let [r, g, b, a] = pixel.0.map(|channel| f32::from(channel) / 255.);
+                glam::Vec4::new(srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b), a)
+            })
+            .collect();
+
+        Self {
+            width: image.width(),
+            height: image.height(),
+            texels,
+        }
+    }
+
+    pub fn sample(&self, uv: glam::Vec2) -> glam::Vec4 {
+        let size = glam::Vec2::new(self.width as f32, self.height as f32);
+        let position = uv * size - 0.5;
+        let base = position.floor();
+        let fraction = position - base;
+
+        let wrap = |coordinate: f32, size: u32| {
+            usize::try_from((coordinate as i64).rem_euclid(i64::from(size))).unwrap_or_default()
+        };
+        let texel = |dx: f32, dy: f32| {
+            let x = wrap(base.x + dx, self.width);
+            let y = wrap(base.y + dy, self.height);
+            self.texels[y * self.width as usize + x]
+        };
+
+        let top = texel(0., 0.).lerp(texel(1., 0.), fraction.x);
+        let bottom = texel(0., 1.).lerp(texel(1., 1.), fraction.x);
+        top.lerp(bottom, fraction.y)
+    }
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
@@ -1,114 +1,219 @@
-use std::{collections::HashMap, path::Path};
+#[path = "src/shader_compiler.rs"]
+mod shader_compiler;
+#[path = "src/shader_reflection.rs"]
+mod shader_reflection;
 
-struct Compiler {
-    compiler: shaderc::Compiler,
-    sources: HashMap<String, String>,
-}
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    hash::{DefaultHasher, Hash, Hasher},
+    iter,
+    path::Path,
+};
 
-type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+use shader_compiler::{Compiler, Result, VARIANTS_MANIFEST};
 
 impl Compiler {
-    fn new(shaders_dir: impl AsRef<Path>) -> Result<Self> {
-        let compiler = shaderc::Compiler::new().ok_or("Unable to initialize compiler")?;
-
-        let shaders_dir = shaders_dir
-            .as_ref()
-            .to_str()
-            .ok_or("Unable to read directory name")?;
-        println!("cargo:rerun-if-changed={shaders_dir}");
-
-        let mut sources = HashMap::new();
-        for entry in std::fs::read_dir(shaders_dir)? {
-            let entry = entry?;
-            let file_name = entry.file_name();
-            let file_name = file_name.to_str().ok_or("Unable to read file name")?;
-            sources.insert(file_name.into(), std::fs::read_to_string(entry.path())?);
+    // the headers are committed, so they're only written when the registered items change, in
+    // every language generate_glsl_headers writes them in, and its --check catches commits
+    // with stale ones
+    fn generate_headers(&mut self, shaders_dir: &str) -> Result<()> {
+        for header in glsl::headers(shared::headers::languages) {
+            let file_name = header.file_name();
+            if self.sources.get(&file_name) != Some(&header.source) {
+                std::fs::write(Path::new(shaders_dir).join(&file_name), &header.source)?;
+                self.sources.insert(file_name, header.source);
+            }
         }
 
-        Ok(Self { compiler, sources })
+        Ok(())
     }
 
-    fn options(&self) -> Result<shaderc::CompileOptions> {
-        let mut options =
-            shaderc::CompileOptions::new().ok_or("Unable to create shader options")?;
-        options.set_target_env(
-            shaderc::TargetEnv::Vulkan,
-            shaderc::EnvVersion::Vulkan1_3 as _,
-        );
-        options.set_target_spirv(shaderc::SpirvVersion::V1_6);
-        options.set_source_language(shaderc::SourceLanguage::GLSL);
-        options.set_optimization_level(shaderc::OptimizationLevel::Performance);
-        options.set_generate_debug_info();
-        options.set_warnings_as_errors();
-        options.set_include_callback(|source, _, _, _| {
-            self.sources.get(source).map_or_else(
-                || Err(format!("Unable to resolve source {source}")),
-                |content| {
-                    Ok(shaderc::ResolvedInclude {
-                        resolved_name: source.into(),
-                        content: content.into(),
-                    })
-                },
-            )
-        });
-        Ok(options)
-    }
+    // compiles every variant and writes the lookup of their files to shaders.rs, along with the
+    // table hot-reload recompiles them from, each shader and the files it includes are tracked,
+    // fails if a variant doesn't match the layouts of the pipelines using it
+    fn compile_shaders(&self, shaders_dir: &str) -> Result<()> {
+        let manifest = self.manifest()?;
+        let mut mismatches = Vec::new();
+        for layout in shared::pipelines::ALL {
+            for shader in layout.shaders {
+                if Self::shader_kind(shader)?.is_none() || !self.sources.contains_key(*shader) {
+                    mismatches.push(format!("{} layout: {shader} isn't a shader", layout.name));
+                }
+            }
+        }
+        let out_dir = std::env::var("OUT_DIR")?;
+        let mut tracked = BTreeSet::new();
+        if self.sources.contains_key(VARIANTS_MANIFEST) {
+            tracked.insert(VARIANTS_MANIFEST.to_owned());
+        }
 
-    fn compile_shaders(&self, options: &shaderc::CompileOptions) -> Result<()> {
-        for (name, contents) in &self.sources {
-            if let Some(shader_kind) = Self::shader_kind(name)? {
-                self.compile_shader(name, contents, shader_kind, options)?;
+        let mut names = self.sources.keys().collect::<Vec<_>>();
+        names.sort();
+
+        let mut lookup = String::from("// AUTO-GENERATED by build.rs from variants.toml\n");
+        let mut table = String::from("\npub const FILES: &[ShaderFile] = &[\n");
+        for name in names {
+            if Self::shader_kind(name)?.is_none() {
+                continue;
+            }
+            let includes = self.includes(name);
+            tracked.insert(name.clone());
+            tracked.extend(includes.iter().cloned());
+
+            let mut files = Vec::new();
+            for (variant, defines) in manifest.variants(name) {
+                let file_name = if variant == "default" {
+                    name.clone()
+                } else {
+                    format!("{name}.{variant}")
+                };
+                let out_file = Path::new(&out_dir).join(file_name);
+                let hash = self.input_hash(name, &includes, defines);
+                self.compile_shader(name, &out_file, defines, hash)?;
+                mismatches.extend(
+                    shader_reflection::check(name, &std::fs::read(&out_file)?)
+                        .into_iter()
+                        .map(|mismatch| format!("{mismatch} ({variant})")),
+                );
+                let out_file = out_file
+                    .to_str()
+                    .ok_or("Unable to read output filename")?
+                    .to_owned();
+                writeln!(
+                    table,
+                    "    ShaderFile {{ source: {name:?}, variant: {variant:?}, path: {out_file:?} }},"
+                )?;
+                files.push((pascal_case(variant), out_file));
             }
+            lookup.push_str(&shader_lookup(name, &files));
+        }
+        lookup.push_str(&table);
+        lookup.push_str("];\n");
+        std::fs::write(Path::new(&out_dir).join("shaders.rs"), lookup)?;
+
+        // the directories too, so that added shaders and variants are compiled, cargo looks at
+        // every file under them but only the shaders whose inputs changed are recompiled
+        let dirs = self
+            .sources
+            .keys()
+            .filter_map(|file| Some(Path::new(file).parent()?.to_str()?.to_owned()))
+            .collect::<BTreeSet<_>>();
+        for dir in dirs {
+            let dir = if dir.is_empty() {
+                shaders_dir.to_owned()
+            } else {
+                format!("{shaders_dir}/{dir}")
+            };
+            println!("cargo:rerun-if-changed={dir}");
+        }
+        for file in tracked {
+            println!("cargo:rerun-if-changed={shaders_dir}/{file}");
+        }
+
+        if !mismatches.is_empty() {
+            return Err(format!(
+                "Shaders don't match the pipeline layouts:\n{}",
+                mismatches.join("\n")
+            )
+            .into());
         }
 
         Ok(())
     }
 
+    // of everything the output depends on, the options set by this script included
+    fn input_hash(
+        &self,
+        name: &str,
+        includes: &BTreeSet<String>,
+        defines: &BTreeMap<String, String>,
+    ) -> String {
+        let mut hasher = DefaultHasher::new();
+        include_str!("build.rs").hash(&mut hasher);
+        include_str!("src/shader_compiler.rs").hash(&mut hasher);
+        defines.hash(&mut hasher);
+        for file in iter::once(name).chain(includes.iter().map(String::as_str)) {
+            file.hash(&mut hasher);
+            self.sources[file].hash(&mut hasher);
+        }
+        format!("{:016x}", hasher.finish())
+    }
+
+    // skipped if the output was compiled from the same inputs, cargo reruns the script for
+    // every change to a tracked file
     fn compile_shader(
         &self,
         name: &str,
-        source: &str,
-        shader_kind: shaderc::ShaderKind,
-        options: &shaderc::CompileOptions,
+        out_file: &Path,
+        defines: &BTreeMap<String, String>,
+        hash: String,
     ) -> Result<()> {
-        let assembly =
-            self.compiler
-                .compile_into_spirv(source, shader_kind, name, "main", Some(options))?;
-        let out_dir = std::env::var("OUT_DIR")?;
-        let out_file = Path::new(&out_dir).join(name);
-        std::fs::write(&out_file, assembly.as_binary_u8())?;
-        println!(
-            "cargo:rustc-env={name}={}",
-            out_file.to_str().ok_or("Unable to read output filename")?
-        );
+        let hash_file = Path::new(&format!("{}.hash", out_file.display())).to_owned();
+        if out_file.exists() && std::fs::read_to_string(&hash_file).is_ok_and(|old| old == hash) {
+            return Ok(());
+        }
+
+        let spirv = self.compile(name, defines)?;
+        if let Some(dir) = out_file.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(out_file, spirv)?;
+        std::fs::write(hash_file, hash)?;
 
         Ok(())
     }
+}
 
-    fn shader_kind(file: impl AsRef<Path>) -> Result<Option<shaderc::ShaderKind>> {
-        let file_stem = file.as_ref().file_stem().ok_or(
-            "Unable to read file stem, shader files should ideally have the .glsl extension",
-        )?;
-        let extension = Path::new(file_stem)
-            .extension()
-            .ok_or("Unable to read file kind, files should generally be named <name>.<kind>.glsl")?
-            .to_str();
-
-        Ok(match extension {
-            Some("vert") => Some(shaderc::ShaderKind::Vertex),
-            Some("frag") => Some(shaderc::ShaderKind::Fragment),
-            Some("rgen") => Some(shaderc::ShaderKind::RayGeneration),
-            Some("rmiss") => Some(shaderc::ShaderKind::Miss),
-            Some("rchit") => Some(shaderc::ShaderKind::ClosestHit),
-            _ => None,
-        })
+// pathtracer.rgen.glsl -> PathtracerRgen and post/bloom.comp.glsl -> PostBloomComp, with a
+// variant for each of its files, listed in ALL with the default first
+fn shader_lookup(name: &str, files: &[(String, String)]) -> String {
+    let ty = pascal_case(name.trim_end_matches(".glsl"));
+    let mut lookup = String::new();
+    writeln!(
+        lookup,
+        "\n#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]\npub enum {ty} {{\n    #[default]"
+    )
+    .unwrap();
+    for (variant, _) in files {
+        writeln!(lookup, "    {variant},").unwrap();
+    }
+    writeln!(
+        lookup,
+        "}}\n\nimpl {ty} {{\n    pub const ALL: &[Self] = &["
+    )
+    .unwrap();
+    for (variant, _) in files {
+        writeln!(lookup, "        Self::{variant},").unwrap();
     }
+    writeln!(
+        lookup,
+        "    ];\n\n    pub const fn path(self) -> &'static str {{\n        match self {{"
+    )
+    .unwrap();
+    for (variant, file) in files {
+        writeln!(lookup, "            Self::{variant} => {file:?},").unwrap();
+    }
+    lookup.push_str("        }\n    }\n}\n");
+    lookup
+}
+
+fn pascal_case(name: &str) -> String {
+    name.split(['/', '.', '_'])
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map_or_else(String::new, |first| {
+                first.to_ascii_uppercase().to_string() + chars.as_str()
+            })
+        })
+        .collect()
 }
 
 fn main() -> Result<()> {
-    let compiler = Compiler::new("../shaders")?;
-    let options = compiler.options()?;
-    compiler.compile_shaders(&options)?;
+    let shaders_dir = "../shaders";
+    let mut compiler = Compiler::new(shaders_dir)?;
+    compiler.generate_headers(shaders_dir)?;
+    compiler.compile_shaders(shaders_dir)?;
 
     Ok(())
 }
@@ -78,6 +78,32 @@ impl Buffer {
         buffer
     }
 
+    // the buffer must have been created with `create_with_staged_data`
+    pub fn update_with_staged_data(
+        &self,
+        ctx: &Context,
+        scope: &mut Scope,
+        name: String,
+        data: &[u8],
+    ) {
+        firestorm::profile_method!(update_with_staged_data);
+
+        let staging = Self::create_with_data(
+            ctx,
+            name + " - Staging",
+            vk::BufferCreateInfo::default().usage(vk::BufferUsageFlags::TRANSFER_SRC),
+            data,
+        );
+        self.cmd_copy_from(
+            ctx,
+            scope.commands.buffer,
+            &staging,
+            std::mem::size_of_val(data) as _,
+        );
+
+        scope.add_resource(staging);
+    }
+
     pub fn fill_with<T: bytemuck::Pod>(&self, ctx: &Context, data: &T) {
         self.fill_from(ctx, bytemuck::bytes_of(data));
     }
@@ -1,4 +1,10 @@
+use std::iter;
+
 use ash::vk;
+use shared::{
+    descriptors::{DescriptorSet, Resource, Stage},
+    pipelines::PipelineLayout,
+};
 
 use crate::{context::Context, Destroy};
 
@@ -8,6 +14,143 @@ pub struct Descriptors {
     pub sets: Vec<vk::DescriptorSet>,
 }
 
+impl Descriptors {
+    // a layout, a pool and num_sets sets, from the description shared with the shaders
+    pub fn create(ctx: &Context, description: &DescriptorSet, num_sets: u32) -> Self {
+        firestorm::profile_method!(create);
+
+        let layout = {
+            let bindings: Vec<_> = description
+                .bindings
+                .iter()
+                .map(|binding| {
+                    vk::DescriptorSetLayoutBinding::default()
+                        .binding(binding.binding)
+                        .descriptor_type(descriptor_type(binding.resource))
+                        .descriptor_count(descriptor_count(binding.resource))
+                        .stage_flags(stage_flags(binding.stages))
+                })
+                .collect();
+            let binding_flags: Vec<_> = description
+                .bindings
+                .iter()
+                .map(|binding| binding_flags(binding.resource))
+                .collect();
+            let mut binding_flags_info = vk::DescriptorSetLayoutBindingFlagsCreateInfo::default()
+                .binding_flags(&binding_flags);
+            let info = vk::DescriptorSetLayoutCreateInfo::default()
+                .bindings(&bindings)
+                .push_next(&mut binding_flags_info);
+            unsafe {
+                ctx.create_descriptor_set_layout(&info, None)
+                    .expect("Failed to create descriptor set layout")
+            }
+        };
+
+        let pool = {
+            let sizes: Vec<_> = description
+                .bindings
+                .iter()
+                .map(|binding| {
+                    vk::DescriptorPoolSize::default()
+                        .ty(descriptor_type(binding.resource))
+                        .descriptor_count(descriptor_count(binding.resource) * num_sets)
+                })
+                .collect();
+
+            let info = vk::DescriptorPoolCreateInfo::default()
+                .pool_sizes(&sizes)
+                .max_sets(num_sets);
+
+            unsafe {
+                ctx.create_descriptor_pool(&info, None)
+                    .expect("Failed to create descriptor pool")
+            }
+        };
+
+        let sets = {
+            let variable_counts: Vec<_> = description
+                .variable_count()
+                .into_iter()
+                .flat_map(|count| iter::repeat_n(count, num_sets as usize))
+                .collect();
+            let mut set_counts = vk::DescriptorSetVariableDescriptorCountAllocateInfo::default()
+                .descriptor_counts(&variable_counts);
+
+            let layouts = vec![layout; num_sets as usize];
+            let mut info = vk::DescriptorSetAllocateInfo::default()
+                .descriptor_pool(pool)
+                .set_layouts(&layouts);
+            if !variable_counts.is_empty() {
+                info = info.push_next(&mut set_counts);
+            }
+
+            unsafe {
+                ctx.allocate_descriptor_sets(&info)
+                    .expect("Failed to allocate descriptor sets")
+            }
+        };
+
+        Self { layout, pool, sets }
+    }
+}
+
+const fn descriptor_type(resource: Resource) -> vk::DescriptorType {
+    match resource {
+        Resource::UniformBuffer { .. } => vk::DescriptorType::UNIFORM_BUFFER,
+        Resource::AccelerationStructure => vk::DescriptorType::ACCELERATION_STRUCTURE_KHR,
+        Resource::StorageImage { .. } => vk::DescriptorType::STORAGE_IMAGE,
+        Resource::Texture | Resource::Textures { .. } => vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+    }
+}
+
+const fn descriptor_count(resource: Resource) -> u32 {
+    match resource {
+        Resource::Textures { max_count } => max_count,
+        _ => 1,
+    }
+}
+
+fn binding_flags(resource: Resource) -> vk::DescriptorBindingFlags {
+    match resource {
+        Resource::Textures { .. } => {
+            vk::DescriptorBindingFlags::PARTIALLY_BOUND
+                | vk::DescriptorBindingFlags::VARIABLE_DESCRIPTOR_COUNT
+        }
+        _ => vk::DescriptorBindingFlags::empty(),
+    }
+}
+
+// the shaders of the layout read the push constants from the start
+pub fn push_constant_range(layout: &PipelineLayout) -> Option<vk::PushConstantRange> {
+    layout
+        .push_constants
+        .map(|push_constants| vk::PushConstantRange {
+            stage_flags: stage_flags(push_constants.stages),
+            offset: 0,
+            size: push_constants.size,
+        })
+}
+
+fn stage_flags(stages: &[Stage]) -> vk::ShaderStageFlags {
+    stages
+        .iter()
+        .map(|stage| match stage {
+            Stage::Vertex => vk::ShaderStageFlags::VERTEX,
+            Stage::Fragment => vk::ShaderStageFlags::FRAGMENT,
+            Stage::RayGeneration => vk::ShaderStageFlags::RAYGEN_KHR,
+            Stage::Miss => vk::ShaderStageFlags::MISS_KHR,
+            Stage::ClosestHit => vk::ShaderStageFlags::CLOSEST_HIT_KHR,
+            Stage::AnyHit => vk::ShaderStageFlags::ANY_HIT_KHR,
+            Stage::Intersection => vk::ShaderStageFlags::INTERSECTION_KHR,
+            Stage::Callable => vk::ShaderStageFlags::CALLABLE_KHR,
+            Stage::Compute => vk::ShaderStageFlags::COMPUTE,
+            Stage::Task => vk::ShaderStageFlags::TASK_EXT,
+            Stage::Mesh => vk::ShaderStageFlags::MESH_EXT,
+        })
+        .fold(vk::ShaderStageFlags::empty(), |flags, stage| flags | stage)
+}
+
 impl Destroy<Context> for Descriptors {
     unsafe fn destroy_with(&mut self, ctx: &Context) {
         firestorm::profile_method!(destroy_with);
@@ -0,0 +1,157 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::Path,
+    time::{Instant, SystemTime},
+};
+
+use crate::{
+    shader_compiler::{Compiler, Result},
+    shader_reflection, shaders,
+};
+
+mod conf {
+    use std::time::Duration;
+
+    pub const SHADERS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../shaders");
+    pub const OUT_DIR: &str = concat!(env!("OUT_DIR"), "/hot-reload");
+    pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+}
+
+// watches the shaders directory by polling the modification times of its files
+pub struct HotReload {
+    modified: HashMap<String, SystemTime>,
+    last_poll: Instant,
+    // the changes of a reload that failed as a whole, retried along with the next change
+    pending: BTreeSet<String>,
+}
+
+impl Default for HotReload {
+    fn default() -> Self {
+        Self {
+            modified: modified_times(Path::new(conf::SHADERS_DIR), "").unwrap_or_default(),
+            last_poll: Instant::now(),
+            pending: BTreeSet::new(),
+        }
+    }
+}
+
+impl HotReload {
+    // recompiles the shaders that are or include a changed file and points their paths to the
+    // new spir-v, returns the built paths of the ones that compiled and match their layouts,
+    // errors are only reported so the pipelines keep their last good shaders
+    pub fn poll(&mut self, paths: &mut shaders::Paths) -> BTreeSet<&'static str> {
+        firestorm::profile_method!(poll);
+
+        if self.last_poll.elapsed() < conf::POLL_INTERVAL {
+            return BTreeSet::new();
+        }
+        self.last_poll = Instant::now();
+
+        let modified = match modified_times(Path::new(conf::SHADERS_DIR), "") {
+            Ok(modified) => modified,
+            Err(err) => {
+                eprintln!("Unable to watch shaders: {err}");
+                return BTreeSet::new();
+            }
+        };
+        let mut changed = modified
+            .iter()
+            .filter(|&(file, time)| self.modified.get(file) != Some(time))
+            .map(|(file, _)| file.clone())
+            .collect::<BTreeSet<_>>();
+        if changed.is_empty() {
+            return BTreeSet::new();
+        }
+        changed.append(&mut self.pending);
+        self.modified = modified;
+
+        Self::recompile(&changed, paths).unwrap_or_else(|err| {
+            eprintln!("Unable to reload shaders: {err}");
+            self.pending = changed;
+            BTreeSet::new()
+        })
+    }
+
+    fn recompile(
+        changed: &BTreeSet<String>,
+        paths: &mut shaders::Paths,
+    ) -> Result<BTreeSet<&'static str>> {
+        let compiler = Compiler::new(conf::SHADERS_DIR)?;
+        let manifest = compiler.manifest()?;
+        std::fs::create_dir_all(conf::OUT_DIR)?;
+
+        let mut reloaded = BTreeSet::new();
+        for file in shaders::FILES {
+            // removed shaders and variants keep what they were built with
+            if !compiler.sources.contains_key(file.source) {
+                continue;
+            }
+            let Some((_, defines)) = manifest
+                .variants(file.source)
+                .find(|&(variant, _)| variant == file.variant)
+            else {
+                continue;
+            };
+            let includes = compiler.includes(file.source);
+            if !changed.contains(file.source)
+                && !includes
+                    .iter()
+                    .any(|include| changed.contains(include.as_str()))
+            {
+                continue;
+            }
+
+            let spirv = match compiler.compile(file.source, defines) {
+                Ok(spirv) => spirv,
+                Err(err) => {
+                    eprintln!(
+                        "Failed to compile {} ({}):\n{err}",
+                        file.source, file.variant
+                    );
+                    continue;
+                }
+            };
+            let mismatches = shader_reflection::check(file.source, &spirv);
+            if !mismatches.is_empty() {
+                for mismatch in mismatches {
+                    eprintln!("{mismatch} ({})", file.variant);
+                }
+                continue;
+            }
+
+            let out_file = format!(
+                "{}/{}.{}.spv",
+                conf::OUT_DIR,
+                file.source.replace('/', "."),
+                file.variant
+            );
+            std::fs::write(&out_file, spirv)?;
+            println!("Reloaded {} ({})", file.source, file.variant);
+            paths.reload(file.path, out_file);
+            reloaded.insert(file.path);
+        }
+
+        Ok(reloaded)
+    }
+}
+
+// of every file under the directory, keyed by its path from it like the compiler's sources
+fn modified_times(dir: &Path, prefix: &str) -> Result<HashMap<String, SystemTime>> {
+    let mut modified = HashMap::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_name = format!(
+            "{prefix}{}",
+            entry
+                .file_name()
+                .to_str()
+                .ok_or("Unable to read file name")?
+        );
+        if entry.file_type()?.is_dir() {
+            modified.extend(modified_times(&entry.path(), &format!("{file_name}/"))?);
+        } else {
+            modified.insert(file_name, entry.metadata()?.modified()?);
+        }
+    }
+    Ok(modified)
+}
@@ -5,6 +5,8 @@ mod buffer;
 mod commands;
 mod context;
 mod descriptors;
+#[cfg(feature = "hot-reload")]
+mod hot_reload;
 mod image;
 mod memory;
 mod passes;
@@ -13,6 +15,12 @@ mod query_pool;
 mod sampler;
 mod scope;
 mod shader_binding_table;
+#[cfg(feature = "hot-reload")]
+mod shader_compiler;
+// also built for its tests, as build.rs doesn't run those of the modules it includes
+#[cfg(any(test, feature = "hot-reload"))]
+mod shader_reflection;
+pub mod shaders;
 mod swapchain;
 mod sync_info;
 mod sync_state;
@@ -46,6 +54,10 @@ pub struct Renderer {
     rasterizer_pipeline: passes::rasterizer::Pipeline,
     tonemap_pipeline:
         passes::tonemap::Pipeline<{ conf::INTERMEDIATE_FORMAT }, { image::Format::Swapchain }>,
+    ray_generation: shaders::PathtracerRgen,
+    shader_paths: shaders::Paths,
+    #[cfg(feature = "hot-reload")]
+    hot_reload: hot_reload::HotReload,
 
     swapchain: Swapchain,
 
@@ -75,9 +87,15 @@ impl Renderer {
 
         let data = passes::Data::create(&ctx, scene, resolution, camera);
 
-        let pathtracer_pipeline = passes::pathtracer::Pipeline::create(&ctx, &data);
-        let rasterizer_pipeline = passes::rasterizer::Pipeline::create(&ctx, &data);
-        let tonemap_pipeline = passes::tonemap::Pipeline::create(&ctx, &data);
+        let ray_generation = shaders::PathtracerRgen::Default;
+        let shader_paths = shaders::Paths::default();
+        let pathtracer_pipeline =
+            passes::pathtracer::Pipeline::create(&ctx, &data, ray_generation, &shader_paths)
+                .expect("Failed to create pathtracer pipeline");
+        let rasterizer_pipeline = passes::rasterizer::Pipeline::create(&ctx, &data, &shader_paths)
+            .expect("Failed to create rasterizer pipeline");
+        let tonemap_pipeline = passes::tonemap::Pipeline::create(&ctx, &data, &shader_paths)
+            .expect("Failed to create tonemap pipeline");
 
         let swapchain = Swapchain::create(&ctx);
 
@@ -88,6 +106,10 @@ impl Renderer {
             pathtracer_pipeline,
             rasterizer_pipeline,
             tonemap_pipeline,
+            ray_generation,
+            shader_paths,
+            #[cfg(feature = "hot-reload")]
+            hot_reload: hot_reload::HotReload::default(),
 
             swapchain,
 
@@ -102,6 +124,9 @@ impl Renderer {
     pub fn render(&mut self) -> Result<(), Error> {
         firestorm::profile_method!(render);
 
+        #[cfg(feature = "hot-reload")]
+        self.reload_shaders();
+
         unsafe {
             self.ctx
                 .wait_for_fences(
@@ -170,11 +195,128 @@ impl Renderer {
         self.frame = 0;
     }
 
+    pub fn material_variants(&self) -> impl Iterator<Item = &str> {
+        self.data
+            .world
+            .info
+            .host
+            .variants
+            .iter()
+            .map(|variant| variant.name.as_str())
+    }
+
+    // `None` restores the default materials, returns false if there is no variant with that name
+    pub fn set_material_variant(&mut self, name: Option<&str>) -> bool {
+        firestorm::profile_method!(set_material_variant);
+
+        let variant = match name {
+            Some(name) => match self.data.world.info.host.find_variant(name) {
+                Some(variant) => Some(variant),
+                None => return false,
+            },
+            None => None,
+        };
+
+        unsafe {
+            self.ctx.wait_idle();
+        }
+        self.data.world.set_material_variant(&self.ctx, variant);
+        self.frame = 0;
+        true
+    }
+
+    pub const fn ray_generation(&self) -> shaders::PathtracerRgen {
+        self.ray_generation
+    }
+
+    // rebuilds the pathtracer pipeline with another variant of its ray generation shader
+    pub fn set_ray_generation(&mut self, ray_generation: shaders::PathtracerRgen) {
+        firestorm::profile_method!(set_ray_generation);
+
+        if ray_generation == self.ray_generation {
+            return;
+        }
+
+        unsafe {
+            self.ctx.wait_idle();
+        }
+        let replaced = replace_pipeline(
+            &self.ctx,
+            "pathtracer",
+            &mut self.pathtracer_pipeline,
+            passes::pathtracer::Pipeline::create(
+                &self.ctx,
+                &self.data,
+                ray_generation,
+                &self.shader_paths,
+            ),
+        );
+        if replaced {
+            self.ray_generation = ray_generation;
+            self.frame = 0;
+        }
+    }
+
     pub fn toggle_renderer(&mut self) {
         self.use_pathtracer = !self.use_pathtracer;
         self.frame = 0;
     }
 
+    // the pipelines using a shader that compiled again are rebuilt, the shaders that failed to
+    // compile stay at their last good spir-v and the pipelines that failed to build at their
+    // last good pipeline
+    #[cfg(feature = "hot-reload")]
+    fn reload_shaders(&mut self) {
+        firestorm::profile_method!(reload_shaders);
+
+        let reloaded = self.hot_reload.poll(&mut self.shader_paths);
+        if reloaded.is_empty() {
+            return;
+        }
+        let uses_reloaded =
+            |shaders: &[&str]| shaders.iter().any(|shader| reloaded.contains(shader));
+
+        unsafe {
+            self.ctx.wait_idle();
+        }
+
+        if uses_reloaded(&passes::pathtracer::Pipeline::shaders(self.ray_generation)) {
+            replace_pipeline(
+                &self.ctx,
+                "pathtracer",
+                &mut self.pathtracer_pipeline,
+                passes::pathtracer::Pipeline::create(
+                    &self.ctx,
+                    &self.data,
+                    self.ray_generation,
+                    &self.shader_paths,
+                ),
+            );
+        }
+        if uses_reloaded(&passes::rasterizer::Pipeline::SHADERS) {
+            replace_pipeline(
+                &self.ctx,
+                "rasterizer",
+                &mut self.rasterizer_pipeline,
+                passes::rasterizer::Pipeline::create(&self.ctx, &self.data, &self.shader_paths),
+            );
+        }
+        if uses_reloaded(&passes::tonemap::Pipeline::<
+            { conf::INTERMEDIATE_FORMAT },
+            { image::Format::Swapchain },
+        >::SHADERS)
+        {
+            replace_pipeline(
+                &self.ctx,
+                "tonemap",
+                &mut self.tonemap_pipeline,
+                passes::tonemap::Pipeline::create(&self.ctx, &self.data, &self.shader_paths),
+            );
+        }
+
+        self.frame = 0;
+    }
+
     pub fn recreate(&mut self) -> bool {
         firestorm::profile_method!(recreate);
 
@@ -195,6 +337,28 @@ impl Renderer {
     }
 }
 
+// the old pipeline is only destroyed once the new one is created, otherwise it's kept and the
+// error reported, true if it was replaced
+fn replace_pipeline<T: Destroy<Context>>(
+    ctx: &Context,
+    name: &str,
+    pipeline: &mut T,
+    created: ash::prelude::VkResult<T>,
+) -> bool {
+    match created {
+        Ok(created) => {
+            unsafe {
+                std::mem::replace(pipeline, created).destroy_with(ctx);
+            }
+            true
+        }
+        Err(err) => {
+            eprintln!("Unable to rebuild the {name} pipeline, keeping the last one: {err}");
+            false
+        }
+    }
+}
+
 impl Drop for Renderer {
     fn drop(&mut self) {
         firestorm::profile_method!(drop);
@@ -5,17 +5,13 @@ pub mod tonemap;
 use std::slice;
 
 use ash::vk;
-use shared::inputs;
+use shared::{conf, descriptors, inputs};
 
 use crate::{
     commands::Commands, context::Context, descriptors::Descriptors, image, memory,
     uniforms::Uniforms, world::World, Destroy,
 };
 
-mod conf {
-    pub const MAX_NUM_TEXTURES: u32 = 128;
-}
-
 pub struct Data<const FORMAT: image::Format> {
     pub descriptors: Descriptors,
     pub uniforms: Uniforms,
@@ -32,7 +28,7 @@ impl<const FORMAT: image::Format> Data<FORMAT> {
     ) -> Self {
         firestorm::profile_method!(create);
 
-        let descriptors = Self::create_descriptors(ctx);
+        let descriptors = Descriptors::create(ctx, &descriptors::SCENE, 1);
         let uniforms = Uniforms::create(ctx, camera);
         let world = World::create(ctx, scene);
 
@@ -74,107 +70,6 @@ impl<const FORMAT: image::Format> Data<FORMAT> {
         data
     }
 
-    pub fn create_descriptors(ctx: &Context) -> Descriptors {
-        firestorm::profile_method!(create_descriptors);
-
-        let layout = {
-            let bindings = [
-                vk::DescriptorSetLayoutBinding::default()
-                    .binding(0)
-                    .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
-                    .descriptor_count(1)
-                    .stage_flags(vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::RAYGEN_KHR),
-                vk::DescriptorSetLayoutBinding::default()
-                    .binding(1)
-                    .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
-                    .descriptor_count(1)
-                    .stage_flags(
-                        vk::ShaderStageFlags::FRAGMENT
-                            | vk::ShaderStageFlags::RAYGEN_KHR
-                            | vk::ShaderStageFlags::CLOSEST_HIT_KHR,
-                    ),
-                vk::DescriptorSetLayoutBinding::default()
-                    .binding(2)
-                    .descriptor_type(vk::DescriptorType::ACCELERATION_STRUCTURE_KHR)
-                    .descriptor_count(1)
-                    .stage_flags(vk::ShaderStageFlags::RAYGEN_KHR),
-                vk::DescriptorSetLayoutBinding::default()
-                    .binding(3)
-                    .descriptor_type(vk::DescriptorType::STORAGE_IMAGE)
-                    .descriptor_count(1)
-                    .stage_flags(vk::ShaderStageFlags::RAYGEN_KHR),
-                vk::DescriptorSetLayoutBinding::default()
-                    .binding(4)
-                    .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
-                    .descriptor_count(conf::MAX_NUM_TEXTURES)
-                    .stage_flags(vk::ShaderStageFlags::FRAGMENT | vk::ShaderStageFlags::RAYGEN_KHR),
-            ];
-            let binding_flags = [
-                vk::DescriptorBindingFlags::empty(),
-                vk::DescriptorBindingFlags::empty(),
-                vk::DescriptorBindingFlags::empty(),
-                vk::DescriptorBindingFlags::empty(),
-                vk::DescriptorBindingFlags::PARTIALLY_BOUND
-                    | vk::DescriptorBindingFlags::VARIABLE_DESCRIPTOR_COUNT,
-            ];
-            let mut binding_flags_info = vk::DescriptorSetLayoutBindingFlagsCreateInfo::default()
-                .binding_flags(&binding_flags);
-            let info = vk::DescriptorSetLayoutCreateInfo::default()
-                .bindings(&bindings)
-                .push_next(&mut binding_flags_info);
-            unsafe {
-                ctx.create_descriptor_set_layout(&info, None)
-                    .expect("Failed to create descriptor set layout")
-            }
-        };
-
-        let pool = {
-            let sizes = [
-                vk::DescriptorPoolSize::default()
-                    .ty(vk::DescriptorType::UNIFORM_BUFFER)
-                    .descriptor_count(1),
-                vk::DescriptorPoolSize::default()
-                    .ty(vk::DescriptorType::UNIFORM_BUFFER)
-                    .descriptor_count(1),
-                vk::DescriptorPoolSize::default()
-                    .ty(vk::DescriptorType::ACCELERATION_STRUCTURE_KHR)
-                    .descriptor_count(1),
-                vk::DescriptorPoolSize::default()
-                    .ty(vk::DescriptorType::STORAGE_IMAGE)
-                    .descriptor_count(1),
-                vk::DescriptorPoolSize::default()
-                    .ty(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
-                    .descriptor_count(conf::MAX_NUM_TEXTURES),
-            ];
-
-            let info = vk::DescriptorPoolCreateInfo::default()
-                .pool_sizes(&sizes)
-                .max_sets(1);
-
-            unsafe {
-                ctx.create_descriptor_pool(&info, None)
-                    .expect("Failed to create descriptor pool")
-            }
-        };
-
-        let sets = {
-            let mut set_counts = vk::DescriptorSetVariableDescriptorCountAllocateInfo::default()
-                .descriptor_counts(&[conf::MAX_NUM_TEXTURES]);
-
-            let info = vk::DescriptorSetAllocateInfo::default()
-                .descriptor_pool(pool)
-                .set_layouts(slice::from_ref(&layout))
-                .push_next(&mut set_counts);
-
-            unsafe {
-                ctx.allocate_descriptor_sets(&info)
-                    .expect("Failed to allocate descriptor sets")
-            }
-        };
-
-        Descriptors { layout, pool, sets }
-    }
-
     fn bind_to_descriptor_sets(&self, ctx: &Context) {
         firestorm::profile_method!(bind_to_descriptor_sets);
 
@@ -207,28 +102,28 @@ impl<const FORMAT: image::Format> Data<FORMAT> {
             let writes = [
                 vk::WriteDescriptorSet::default()
                     .dst_set(set)
-                    .dst_binding(0)
+                    .dst_binding(conf::UNIFORMS_BINDING)
                     .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
                     .buffer_info(slice::from_ref(&uniforms_info)),
                 vk::WriteDescriptorSet::default()
                     .dst_set(set)
-                    .dst_binding(1)
+                    .dst_binding(conf::SCENE_DESC_BINDING)
                     .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
                     .buffer_info(slice::from_ref(&scene_desc_info)),
                 vk::WriteDescriptorSet::default()
                     .dst_set(set)
-                    .dst_binding(2)
+                    .dst_binding(conf::TLAS_BINDING)
                     .descriptor_type(vk::DescriptorType::ACCELERATION_STRUCTURE_KHR)
                     .descriptor_count(1)
                     .push_next(&mut accel_info),
                 vk::WriteDescriptorSet::default()
                     .dst_set(set)
-                    .dst_binding(3)
+                    .dst_binding(conf::OUTPUT_IMAGE_BINDING)
                     .descriptor_type(vk::DescriptorType::STORAGE_IMAGE)
                     .image_info(slice::from_ref(&target_info)),
                 vk::WriteDescriptorSet::default()
                     .dst_set(set)
-                    .dst_binding(4)
+                    .dst_binding(conf::TEXTURES_BINDING)
                     .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
                     .image_info(&textures_info),
             ];
@@ -1,22 +1,22 @@
 use std::slice;
 
-use ash::vk;
+use ash::{prelude::VkResult, vk};
 
-use shared::inputs;
+use shared::{inputs, pipelines};
 
 use crate::{
     context::Context,
-    image, pipeline,
+    descriptors, image, pipeline,
     shader_binding_table::{RayTracingShaders, ShaderBindingTable},
+    shaders,
     sync_info::SyncInfo,
     Destroy,
 };
 
 pub mod conf {
     pub const NAME: &str = "Pathtracer";
-    pub const SHADER_RAY_GENERATION: &str = env!("pathtracer.rgen.glsl");
-    pub const SHADER_MISSES: &[&str] = &[env!("pathtracer.rmiss.glsl")];
-    pub const SHADER_CLOSEST_HITS: &[&str] = &[env!("pathtracer.rchit.glsl")];
+    pub const SHADER_MISSES: &[&str] = &[crate::shaders::PathtracerRmiss::Default.path()];
+    pub const SHADER_CLOSEST_HITS: &[&str] = &[crate::shaders::PathtracerRchit::Default.path()];
 }
 
 pub struct Pipeline {
@@ -25,17 +25,39 @@ pub struct Pipeline {
 }
 
 impl Pipeline {
-    pub fn create<const FORMAT: image::Format>(ctx: &Context, data: &super::Data<FORMAT>) -> Self {
+    #[cfg(feature = "hot-reload")]
+    pub fn shaders(ray_generation: shaders::PathtracerRgen) -> Vec<&'static str> {
+        std::iter::once(ray_generation.path())
+            .chain(conf::SHADER_MISSES.iter().copied())
+            .chain(conf::SHADER_CLOSEST_HITS.iter().copied())
+            .collect()
+    }
+
+    // the ray generation variant selects the debug views and bounce counts, fails without
+    // leaking anything so a rebuild can keep the pipeline it replaces
+    pub fn create<const FORMAT: image::Format>(
+        ctx: &Context,
+        data: &super::Data<FORMAT>,
+        ray_generation: shaders::PathtracerRgen,
+        shaders: &shaders::Paths,
+    ) -> VkResult<Self> {
         firestorm::profile_method!(create);
 
-        let ray_tracing_shaders = RayTracingShaders::new(
+        let paths = |files: &[&'static str]| {
+            files
+                .iter()
+                .map(|&file| shaders.get(file))
+                .collect::<Vec<_>>()
+        };
+        let mut ray_tracing_shaders = RayTracingShaders::new(
             ctx,
-            conf::SHADER_RAY_GENERATION,
-            conf::SHADER_MISSES,
-            conf::SHADER_CLOSEST_HITS,
+            shaders.get(ray_generation.path()),
+            &paths(conf::SHADER_MISSES),
+            &paths(conf::SHADER_CLOSEST_HITS),
         );
 
-        let (layout, pipeline) = Self::create_pipeline(ctx, data, &ray_tracing_shaders);
+        let (layout, pipeline) = Self::create_pipeline(ctx, data, &ray_tracing_shaders)
+            .inspect_err(|_| unsafe { ray_tracing_shaders.destroy_with(ctx) })?;
 
         let shader_binding_table = ShaderBindingTable::create(ctx, ray_tracing_shaders, pipeline);
 
@@ -51,33 +73,26 @@ impl Pipeline {
             1,
         );
 
-        Self {
+        Ok(Self {
             pipeline,
             shader_binding_table,
-        }
+        })
     }
 
     fn create_pipeline<const FORMAT: image::Format>(
         ctx: &Context,
         data: &super::Data<FORMAT>,
         ray_tracing_shaders: &RayTracingShaders,
-    ) -> (vk::PipelineLayout, vk::Pipeline) {
+    ) -> VkResult<(vk::PipelineLayout, vk::Pipeline)> {
         firestorm::profile_method!(create_pipeline);
 
-        let push_constant_ranges = vk::PushConstantRange {
-            stage_flags: vk::ShaderStageFlags::RAYGEN_KHR,
-            offset: 0,
-            size: std::mem::size_of::<inputs::PathtracerConstants>() as _,
-        };
+        let push_constant_range = descriptors::push_constant_range(&pipelines::PATHTRACER);
 
         let layout_create_info = vk::PipelineLayoutCreateInfo::default()
             .set_layouts(slice::from_ref(&data.descriptors.layout))
-            .push_constant_ranges(slice::from_ref(&push_constant_ranges));
+            .push_constant_ranges(push_constant_range.as_slice());
 
-        let layout = unsafe {
-            ctx.create_pipeline_layout(&layout_create_info, None)
-                .expect("Failed to create pipeline layout")
-        };
+        let layout = unsafe { ctx.create_pipeline_layout(&layout_create_info, None)? };
 
         let stages = ray_tracing_shaders.stages_create_infos();
         let groups = ray_tracing_shaders.groups_create_infos();
@@ -88,18 +103,23 @@ impl Pipeline {
             .layout(layout);
 
         let pipeline = unsafe {
-            ctx.ext
-                .ray_tracing
-                .create_ray_tracing_pipelines(
-                    vk::DeferredOperationKHR::null(),
-                    vk::PipelineCache::null(),
-                    slice::from_ref(&create_info),
-                    None,
-                )
-                .expect("Failed to create pipeline")[0]
+            ctx.ext.ray_tracing.create_ray_tracing_pipelines(
+                vk::DeferredOperationKHR::null(),
+                vk::PipelineCache::null(),
+                slice::from_ref(&create_info),
+                None,
+            )
         };
 
-        (layout, pipeline)
+        match pipeline {
+            Ok(pipelines) => Ok((layout, pipelines[0])),
+            Err((_, err)) => {
+                unsafe {
+                    ctx.destroy_pipeline_layout(layout, None);
+                }
+                Err(err)
+            }
+        }
     }
 
     pub fn run<const FORMAT: image::Format>(
@@ -113,7 +133,15 @@ impl Pipeline {
 
         let commands = self.pipeline.begin_pipeline(ctx, 0);
 
-        let push_constants = inputs::PathtracerConstants { frame };
+        let info = &data.world.info.host;
+        let (min_bounces, max_bounces) = info.render_settings.bounces();
+        let push_constants = inputs::PathtracerConstants {
+            environment: info.environment.color,
+            frame,
+            min_bounces,
+            max_bounces,
+            ..Default::default()
+        };
 
         unsafe {
             ctx.cmd_bind_pipeline(
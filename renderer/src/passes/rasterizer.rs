@@ -1,17 +1,18 @@
 use std::slice;
 
-use ash::vk;
+use ash::{prelude::VkResult, vk};
 
-use shared::{inputs, scene};
+use shared::{inputs, pipelines, scene};
 
 use crate::{
-    commands::Commands, context::Context, image, memory, pipeline, sync_info::SyncInfo, Destroy,
+    commands::Commands, context::Context, descriptors, image, memory, pipeline, shaders,
+    sync_info::SyncInfo, Destroy,
 };
 
 pub mod conf {
     pub const NAME: &str = "Rasterizer";
-    pub const SHADER_VERT: &str = env!("rasterizer.vert.glsl");
-    pub const SHADER_FRAG: &str = env!("rasterizer.frag.glsl");
+    pub const SHADER_VERT: &str = crate::shaders::RasterizerVert::Default.path();
+    pub const SHADER_FRAG: &str = crate::shaders::RasterizerFrag::Default.path();
 }
 
 pub struct Pipeline {
@@ -20,9 +21,19 @@ pub struct Pipeline {
 }
 
 impl Pipeline {
-    pub fn create<const FORMAT: image::Format>(ctx: &Context, data: &super::Data<FORMAT>) -> Self {
+    #[cfg(feature = "hot-reload")]
+    pub const SHADERS: [&'static str; 2] = [conf::SHADER_VERT, conf::SHADER_FRAG];
+
+    pub fn create<const FORMAT: image::Format>(
+        ctx: &Context,
+        data: &super::Data<FORMAT>,
+        shaders: &shaders::Paths,
+    ) -> VkResult<Self> {
         firestorm::profile_method!(create);
 
+        // first, so nothing else is created if it fails
+        let (layout, pipeline) = Self::create_pipeline(ctx, data, shaders)?;
+
         let commands = Commands::begin_on_queue(
             ctx,
             format!("{} - Initialization", conf::NAME),
@@ -42,8 +53,6 @@ impl Pipeline {
             )
         };
 
-        let (layout, pipeline) = Self::create_pipeline(ctx, data);
-
         let descriptor_sets = data.descriptors.sets.iter().copied().map(|a| [a]);
 
         let pipeline = pipeline::Pipeline::new(
@@ -58,32 +67,26 @@ impl Pipeline {
 
         commands.finish(ctx, &vk::SubmitInfo::default(), None);
 
-        Self { depth, pipeline }
+        Ok(Self { depth, pipeline })
     }
 
     fn create_pipeline<const FORMAT: image::Format>(
         ctx: &Context,
         data: &super::Data<FORMAT>,
-    ) -> (vk::PipelineLayout, vk::Pipeline) {
+        shaders: &shaders::Paths,
+    ) -> VkResult<(vk::PipelineLayout, vk::Pipeline)> {
         firestorm::profile_method!(create_pipeline);
 
-        let push_constant_ranges = vk::PushConstantRange {
-            stage_flags: vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
-            offset: 0,
-            size: std::mem::size_of::<inputs::RasterizerConstants>() as _,
-        };
+        let push_constant_range = descriptors::push_constant_range(&pipelines::RASTERIZER);
 
         let layout_create_info = vk::PipelineLayoutCreateInfo::default()
             .set_layouts(slice::from_ref(&data.descriptors.layout))
-            .push_constant_ranges(slice::from_ref(&push_constant_ranges));
+            .push_constant_ranges(push_constant_range.as_slice());
 
-        let layout = unsafe {
-            ctx.create_pipeline_layout(&layout_create_info, None)
-                .expect("Failed to create pipeline layout")
-        };
+        let layout = unsafe { ctx.create_pipeline_layout(&layout_create_info, None)? };
 
-        let shader_module_vert = ctx.create_shader_module_from_file(conf::SHADER_VERT);
-        let shader_module_frag = ctx.create_shader_module_from_file(conf::SHADER_FRAG);
+        let shader_module_vert = ctx.create_shader_module_from_file(shaders.get(conf::SHADER_VERT));
+        let shader_module_frag = ctx.create_shader_module_from_file(shaders.get(conf::SHADER_FRAG));
         let shader_stages = [
             vk::PipelineShaderStageCreateInfo::default()
                 .stage(vk::ShaderStageFlags::VERTEX)
@@ -167,7 +170,6 @@ impl Pipeline {
                 slice::from_ref(&create_info),
                 None,
             )
-            .expect("Failed to create pipeline")[0]
         };
 
         unsafe {
@@ -175,7 +177,15 @@ impl Pipeline {
             ctx.destroy_shader_module(shader_module_frag, None);
         }
 
-        (layout, pipeline)
+        match pipeline {
+            Ok(pipelines) => Ok((layout, pipelines[0])),
+            Err((_, err)) => {
+                unsafe {
+                    ctx.destroy_pipeline_layout(layout, None);
+                }
+                Err(err)
+            }
+        }
     }
 
     fn vertex_binding_info() -> (
@@ -264,6 +274,7 @@ impl Pipeline {
             let push_constants = inputs::RasterizerConstants {
                 model_transform: instance.transform,
                 material_index: scene_info.primitive_infos[instance.primitive_index].material,
+                primitive_index: instance.primitive_index as _,
                 ..Default::default()
             };
 
@@ -1,16 +1,17 @@
 use std::{ops::Deref, slice};
 
-use ash::vk;
+use ash::{prelude::VkResult, vk};
+use shared::descriptors;
 
 use crate::{
-    context::Context, descriptors::Descriptors, image, pipeline, sampler::Sampler,
+    context::Context, descriptors::Descriptors, image, pipeline, sampler::Sampler, shaders,
     sync_info::SyncInfo, Destroy,
 };
 
 mod conf {
     pub const NAME: &str = "Tonemap";
-    pub const SHADER_VERT: &str = env!("tonemap.vert.glsl");
-    pub const SHADER_FRAG: &str = env!("tonemap.frag.glsl");
+    pub const SHADER_VERT: &str = crate::shaders::TonemapVert::Default.path();
+    pub const SHADER_FRAG: &str = crate::shaders::TonemapFrag::Default.path();
 }
 
 pub struct Data<const FORMAT: image::Format> {
@@ -28,7 +29,11 @@ impl<const FORMAT: image::Format> Data<FORMAT> {
     pub fn create(ctx: &Context, data: &super::Data<FORMAT>) -> Self {
         firestorm::profile_method!(create);
 
-        let descriptors = Self::create_descriptors(ctx);
+        let descriptors = Descriptors::create(
+            ctx,
+            &descriptors::TONEMAP_INPUT,
+            ctx.surface.config.image_count,
+        );
 
         let input_image = image::Image::new(
             ctx,
@@ -47,51 +52,6 @@ impl<const FORMAT: image::Format> Data<FORMAT> {
         data
     }
 
-    fn create_descriptors(ctx: &Context) -> Descriptors {
-        firestorm::profile_method!(create_descriptors);
-
-        let layout = {
-            let binding = vk::DescriptorSetLayoutBinding::default()
-                .binding(0)
-                .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
-                .descriptor_count(1)
-                .stage_flags(vk::ShaderStageFlags::FRAGMENT);
-            let info =
-                vk::DescriptorSetLayoutCreateInfo::default().bindings(slice::from_ref(&binding));
-            unsafe {
-                ctx.create_descriptor_set_layout(&info, None)
-                    .expect("Failed to create descriptor set layout")
-            }
-        };
-
-        let pool = {
-            let num_frames = ctx.surface.config.image_count;
-            let size = vk::DescriptorPoolSize::default()
-                .ty(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
-                .descriptor_count(num_frames);
-            let info = vk::DescriptorPoolCreateInfo::default()
-                .pool_sizes(slice::from_ref(&size))
-                .max_sets(num_frames);
-            unsafe {
-                ctx.create_descriptor_pool(&info, None)
-                    .expect("Failed to create descriptor pool")
-            }
-        };
-
-        let sets = {
-            let layouts = vec![layout; ctx.surface.config.image_count as usize];
-            let info = vk::DescriptorSetAllocateInfo::default()
-                .descriptor_pool(pool)
-                .set_layouts(&layouts);
-            unsafe {
-                ctx.allocate_descriptor_sets(&info)
-                    .expect("Failed to allocate descriptor sets")
-            }
-        };
-
-        Descriptors { layout, pool, sets }
-    }
-
     fn bind_to_descriptor_sets(&self, ctx: &Context) {
         firestorm::profile_method!(bind_to_descriptor_sets);
 
@@ -117,12 +77,20 @@ impl<const FORMAT: image::Format> Data<FORMAT> {
 impl<const INPUT_FORMAT: image::Format, const OUTPUT_FORMAT: image::Format>
     Pipeline<INPUT_FORMAT, OUTPUT_FORMAT>
 {
-    pub fn create(ctx: &Context, data: &super::Data<INPUT_FORMAT>) -> Self {
+    #[cfg(feature = "hot-reload")]
+    pub const SHADERS: [&'static str; 2] = [conf::SHADER_VERT, conf::SHADER_FRAG];
+
+    pub fn create(
+        ctx: &Context,
+        data: &super::Data<INPUT_FORMAT>,
+        shaders: &shaders::Paths,
+    ) -> VkResult<Self> {
         firestorm::profile_method!(create);
 
-        let data = Data::create(ctx, data);
+        let mut data = Data::create(ctx, data);
 
-        let (layout, pipeline) = Self::create_pipeline(ctx, data.descriptors.layout);
+        let (layout, pipeline) = Self::create_pipeline(ctx, data.descriptors.layout, shaders)
+            .inspect_err(|_| unsafe { data.destroy_with(ctx) })?;
 
         let descriptor_sets = data.descriptors.sets.iter().copied().map(|a| [a]);
 
@@ -136,17 +104,23 @@ impl<const INPUT_FORMAT: image::Format, const OUTPUT_FORMAT: image::Format>
             ctx.surface.config.image_count as _,
         );
 
-        Self { data, pipeline }
+        Ok(Self { data, pipeline })
     }
 
     fn create_pipeline(
         ctx: &Context,
         descriptor_set_layout: vk::DescriptorSetLayout,
-    ) -> (vk::PipelineLayout, vk::Pipeline) {
+        shaders: &shaders::Paths,
+    ) -> VkResult<(vk::PipelineLayout, vk::Pipeline)> {
         firestorm::profile_method!(create_pipeline);
 
-        let shader_module_vert = ctx.create_shader_module_from_file(conf::SHADER_VERT);
-        let shader_module_frag = ctx.create_shader_module_from_file(conf::SHADER_FRAG);
+        let layout_create_info = vk::PipelineLayoutCreateInfo::default()
+            .set_layouts(slice::from_ref(&descriptor_set_layout));
+
+        let layout = unsafe { ctx.create_pipeline_layout(&layout_create_info, None)? };
+
+        let shader_module_vert = ctx.create_shader_module_from_file(shaders.get(conf::SHADER_VERT));
+        let shader_module_frag = ctx.create_shader_module_from_file(shaders.get(conf::SHADER_FRAG));
         let shader_stages = [
             vk::PipelineShaderStageCreateInfo::default()
                 .stage(vk::ShaderStageFlags::VERTEX)
@@ -200,14 +174,6 @@ impl<const INPUT_FORMAT: image::Format, const OUTPUT_FORMAT: image::Format>
         let dynamic_state_info =
             vk::PipelineDynamicStateCreateInfo::default().dynamic_states(&dynamic_states);
 
-        let layout_create_info = vk::PipelineLayoutCreateInfo::default()
-            .set_layouts(slice::from_ref(&descriptor_set_layout));
-
-        let layout = unsafe {
-            ctx.create_pipeline_layout(&layout_create_info, None)
-                .expect("Failed to create pipeline layout")
-        };
-
         let color_formats = [OUTPUT_FORMAT.into()];
         let mut rendering_info =
             vk::PipelineRenderingCreateInfo::default().color_attachment_formats(&color_formats);
@@ -231,7 +197,6 @@ impl<const INPUT_FORMAT: image::Format, const OUTPUT_FORMAT: image::Format>
                 slice::from_ref(&create_info),
                 None,
             )
-            .expect("Failed to create pipeline")[0]
         };
 
         unsafe {
@@ -239,7 +204,15 @@ impl<const INPUT_FORMAT: image::Format, const OUTPUT_FORMAT: image::Format>
             ctx.destroy_shader_module(shader_module_frag, None);
         }
 
-        (layout, pipeline)
+        match pipeline {
+            Ok(pipelines) => Ok((layout, pipelines[0])),
+            Err((_, err)) => {
+                unsafe {
+                    ctx.destroy_pipeline_layout(layout, None);
+                }
+                Err(err)
+            }
+        }
     }
 
     pub fn run(
@@ -0,0 +1,265 @@
+// shared by build.rs and the runtime recompilation of hot-reload
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::Path,
+};
+
+use serde::Deserialize;
+use shared::descriptors::Stage;
+
+pub const VARIANTS_MANIFEST: &str = "variants.toml";
+
+static NO_DEFINES: BTreeMap<String, String> = BTreeMap::new();
+
+// shader file -> variant name -> macro definitions, each variant is compiled next to the default
+// and selected through the enum generated for its shader
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    #[serde(default)]
+    variants: BTreeMap<String, BTreeMap<String, BTreeMap<String, String>>>,
+}
+
+// sources are keyed by their path from the shaders directory, with / separators
+pub struct Compiler {
+    compiler: shaderc::Compiler,
+    pub sources: HashMap<String, String>,
+}
+
+pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+impl Manifest {
+    // the default first, without any definitions
+    pub fn variants<'a>(
+        &'a self,
+        shader: &str,
+    ) -> impl Iterator<Item = (&'a str, &'a BTreeMap<String, String>)> {
+        std::iter::once(("default", &NO_DEFINES)).chain(
+            self.variants
+                .get(shader)
+                .into_iter()
+                .flatten()
+                .map(|(variant, defines)| (variant.as_str(), defines)),
+        )
+    }
+}
+
+impl Compiler {
+    pub fn new(shaders_dir: impl AsRef<Path>) -> Result<Self> {
+        let compiler = shaderc::Compiler::new().ok_or("Unable to initialize compiler")?;
+
+        let mut sources = HashMap::new();
+        Self::read_sources(shaders_dir.as_ref(), "", &mut sources)?;
+
+        Ok(Self { compiler, sources })
+    }
+
+    fn read_sources(dir: &Path, prefix: &str, sources: &mut HashMap<String, String>) -> Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let file_name = entry.file_name();
+            let file_name = format!(
+                "{prefix}{}",
+                file_name.to_str().ok_or("Unable to read file name")?
+            );
+            if entry.file_type()?.is_dir() {
+                Self::read_sources(&entry.path(), &format!("{file_name}/"), sources)?;
+            } else {
+                sources.insert(file_name, std::fs::read_to_string(entry.path())?);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn compile(&self, name: &str, defines: &BTreeMap<String, String>) -> Result<Vec<u8>> {
+        let source = self
+            .sources
+            .get(name)
+            .ok_or_else(|| format!("No shader named {name}"))?;
+        let (shader_kind, stage) =
+            Self::shader_kind(name)?.ok_or_else(|| format!("{name} isn't a shader"))?;
+
+        let assembly = self.compiler.compile_into_spirv(
+            source,
+            shader_kind,
+            name,
+            "main",
+            Some(&self.options(stage, defines)?),
+        )?;
+
+        Ok(assembly.as_binary_u8().to_owned())
+    }
+
+    // the stage macro selects the descriptor declarations of descriptors.h.glsl
+    fn options(
+        &self,
+        stage: Stage,
+        defines: &BTreeMap<String, String>,
+    ) -> Result<shaderc::CompileOptions> {
+        let mut options =
+            shaderc::CompileOptions::new().ok_or("Unable to create shader options")?;
+        options.set_target_env(
+            shaderc::TargetEnv::Vulkan,
+            shaderc::EnvVersion::Vulkan1_3 as _,
+        );
+        options.set_target_spirv(shaderc::SpirvVersion::V1_6);
+        options.set_source_language(shaderc::SourceLanguage::GLSL);
+        options.set_optimization_level(shaderc::OptimizationLevel::Performance);
+        options.set_generate_debug_info();
+        options.set_warnings_as_errors();
+        options.add_macro_definition(stage.glsl_macro(), None);
+        for (name, value) in defines {
+            options.add_macro_definition(name, Some(value));
+        }
+        options.set_include_callback(|source, include_type, requesting_source, _| {
+            self.resolve(source, include_type, requesting_source)
+                .map_or_else(
+                    || {
+                        Err(format!(
+                            "Unable to resolve source {source} from {requesting_source}"
+                        ))
+                    },
+                    |resolved_name| {
+                        Ok(shaderc::ResolvedInclude {
+                            content: self.sources[&resolved_name].clone(),
+                            resolved_name,
+                        })
+                    },
+                )
+        });
+        Ok(options)
+    }
+
+    // relative includes are looked up next to the including file first, then from the shaders
+    // directory like standard ones
+    fn resolve(
+        &self,
+        source: &str,
+        include_type: shaderc::IncludeType,
+        requesting_source: &str,
+    ) -> Option<String> {
+        let relative = match include_type {
+            shaderc::IncludeType::Relative => requesting_source
+                .rsplit_once('/')
+                .and_then(|(dir, _)| normalize(&format!("{dir}/{source}"))),
+            shaderc::IncludeType::Standard => None,
+        };
+        relative
+            .into_iter()
+            .chain(normalize(source))
+            .find(|name| self.sources.contains_key(name))
+    }
+
+    // every file the shader includes, from the #include lines of each file, conditional ones too
+    pub fn includes(&self, name: &str) -> BTreeSet<String> {
+        let mut includes = BTreeSet::new();
+        let mut pending = vec![name.to_owned()];
+        while let Some(file) = pending.pop() {
+            for line in self.sources[&file].lines() {
+                let Some(include) = line.trim_start().strip_prefix("#include") else {
+                    continue;
+                };
+                let include = include.trim();
+                let (source, include_type) = if let Some(source) = include
+                    .strip_prefix('"')
+                    .and_then(|rest| rest.strip_suffix('"'))
+                {
+                    (source, shaderc::IncludeType::Relative)
+                } else if let Some(source) = include
+                    .strip_prefix('<')
+                    .and_then(|rest| rest.strip_suffix('>'))
+                {
+                    (source, shaderc::IncludeType::Standard)
+                } else {
+                    continue;
+                };
+                if let Some(resolved) = self
+                    .resolve(source, include_type, &file)
+                    .filter(|resolved| !includes.contains(resolved))
+                {
+                    includes.insert(resolved.clone());
+                    pending.push(resolved);
+                }
+            }
+        }
+        includes
+    }
+
+    pub fn manifest(&self) -> Result<Manifest> {
+        let Some(manifest) = self.sources.get(VARIANTS_MANIFEST) else {
+            return Ok(Manifest::default());
+        };
+        let manifest: Manifest = toml::from_str(manifest)?;
+        for (shader, variants) in &manifest.variants {
+            if !self.sources.contains_key(shader) || Self::shader_kind(shader)?.is_none() {
+                return Err(format!(
+                    "{VARIANTS_MANIFEST} has variants of {shader}, which isn't a shader"
+                )
+                .into());
+            }
+            for variant in variants.keys() {
+                let is_snake_case = variant.starts_with(|c: char| c.is_ascii_lowercase())
+                    && variant
+                        .chars()
+                        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+                if !is_snake_case || variant == "default" {
+                    return Err(format!(
+                        "Variant {variant} of {shader} should be in snake case and not be named default"
+                    )
+                    .into());
+                }
+            }
+        }
+        Ok(manifest)
+    }
+
+    pub fn shader_kind(file: impl AsRef<Path>) -> Result<Option<(shaderc::ShaderKind, Stage)>> {
+        // the headers generated for the other languages sit next to the shaders
+        if file
+            .as_ref()
+            .extension()
+            .is_none_or(|extension| extension != "glsl")
+        {
+            return Ok(None);
+        }
+        let file_stem = file.as_ref().file_stem().ok_or(
+            "Unable to read file stem, shader files should ideally have the .glsl extension",
+        )?;
+        let extension = Path::new(file_stem)
+            .extension()
+            .ok_or("Unable to read file kind, files should generally be named <name>.<kind>.glsl")?
+            .to_str();
+
+        Ok(match extension {
+            Some("vert") => Some((shaderc::ShaderKind::Vertex, Stage::Vertex)),
+            Some("frag") => Some((shaderc::ShaderKind::Fragment, Stage::Fragment)),
+            Some("rgen") => Some((shaderc::ShaderKind::RayGeneration, Stage::RayGeneration)),
+            Some("rmiss") => Some((shaderc::ShaderKind::Miss, Stage::Miss)),
+            Some("rchit") => Some((shaderc::ShaderKind::ClosestHit, Stage::ClosestHit)),
+            Some("rahit") => Some((shaderc::ShaderKind::AnyHit, Stage::AnyHit)),
+            Some("rint") => Some((shaderc::ShaderKind::Intersection, Stage::Intersection)),
+            Some("rcall") => Some((shaderc::ShaderKind::Callable, Stage::Callable)),
+            Some("comp") => Some((shaderc::ShaderKind::Compute, Stage::Compute)),
+            Some("task") => Some((shaderc::ShaderKind::Task, Stage::Task)),
+            Some("mesh") => Some((shaderc::ShaderKind::Mesh, Stage::Mesh)),
+            _ => None,
+        })
+    }
+}
+
+// a/./b/../c -> a/c, none if it leaves the shaders directory
+fn normalize(path: &str) -> Option<String> {
+    let mut parts = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            _ => parts.push(part),
+        }
+    }
+    Some(parts.join("/"))
+}
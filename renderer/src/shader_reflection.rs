@@ -0,0 +1,616 @@
+// checks the spir-v of a shader against the layouts of the pipelines using it, shared by build.rs
+// and hot-reload
+
+use std::{collections::HashMap, iter};
+
+use shared::{
+    descriptors::{Resource, Stage},
+    pipelines::{self, PipelineLayout},
+};
+
+mod spirv {
+    pub const MAGIC: u32 = 0x0723_0203;
+
+    pub mod op {
+        pub const NAME: u32 = 5;
+        pub const ENTRY_POINT: u32 = 15;
+        pub const TYPE_INT: u32 = 21;
+        pub const TYPE_FLOAT: u32 = 22;
+        pub const TYPE_VECTOR: u32 = 23;
+        pub const TYPE_MATRIX: u32 = 24;
+        pub const TYPE_IMAGE: u32 = 25;
+        pub const TYPE_SAMPLER: u32 = 26;
+        pub const TYPE_SAMPLED_IMAGE: u32 = 27;
+        pub const TYPE_ARRAY: u32 = 28;
+        pub const TYPE_RUNTIME_ARRAY: u32 = 29;
+        pub const TYPE_STRUCT: u32 = 30;
+        pub const TYPE_POINTER: u32 = 32;
+        pub const CONSTANT: u32 = 43;
+        pub const VARIABLE: u32 = 59;
+        pub const DECORATE: u32 = 71;
+        pub const MEMBER_DECORATE: u32 = 72;
+        pub const TYPE_ACCELERATION_STRUCTURE: u32 = 5341;
+    }
+
+    pub mod decoration {
+        pub const BLOCK: u32 = 2;
+        pub const ARRAY_STRIDE: u32 = 6;
+        pub const MATRIX_STRIDE: u32 = 7;
+        pub const BINDING: u32 = 33;
+        pub const DESCRIPTOR_SET: u32 = 34;
+        pub const OFFSET: u32 = 35;
+    }
+
+    pub mod storage_class {
+        pub const UNIFORM_CONSTANT: u32 = 0;
+        pub const UNIFORM: u32 = 2;
+        pub const PUSH_CONSTANT: u32 = 9;
+        pub const STORAGE_BUFFER: u32 = 12;
+    }
+}
+
+enum Type {
+    Scalar { size: u32 },
+    Vector { component: u32, count: u32 },
+    Matrix { column: u32, count: u32 },
+    // 1 if it's sampled, 2 if it's a storage image
+    Image { sampled: u32 },
+    Sampler,
+    SampledImage,
+    Array { element: u32, length: u32 },
+    RuntimeArray { element: u32 },
+    Struct { members: Vec<u32> },
+    Pointer { pointee: u32 },
+    AccelerationStructure,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Count {
+    Single,
+    Array(u32),
+    Runtime,
+}
+
+#[derive(Default)]
+struct Module {
+    stage: Option<Stage>,
+    // the global variables the entry point uses, since spir-v 1.4
+    interface: Vec<u32>,
+    names: HashMap<u32, String>,
+    types: HashMap<u32, Type>,
+    constants: HashMap<u32, u32>,
+    // id -> (type, storage class)
+    variables: HashMap<u32, (u32, u32)>,
+    decorations: HashMap<(u32, u32), u32>,
+    member_decorations: HashMap<(u32, u32, u32), u32>,
+}
+
+// every mismatch between the shader and the layouts listing it, none if it matches all of them
+pub fn check(name: &str, spirv: &[u8]) -> Vec<String> {
+    let layouts = pipelines::ALL
+        .iter()
+        .filter(|layout| layout.shaders.contains(&name))
+        .collect::<Vec<_>>();
+    if layouts.is_empty() {
+        return Vec::new();
+    }
+
+    let module = match Module::parse(spirv) {
+        Ok(module) => module,
+        Err(err) => return vec![format!("{name}: {err}")],
+    };
+    let Some(stage) = module.stage else {
+        return vec![format!("{name}: no entry point of a known stage")];
+    };
+
+    let mut errors = Vec::new();
+    for layout in layouts {
+        for &variable in &module.interface {
+            let mismatch = module.check_variable(variable, stage, layout);
+            if let Some(mismatch) = mismatch {
+                errors.push(format!(
+                    "{} layout, {name}: {} {mismatch}",
+                    layout.name,
+                    module.variable_name(variable)
+                ));
+            }
+        }
+    }
+    errors
+}
+
+impl Module {
+    fn parse(spirv: &[u8]) -> Result<Self, String> {
+        if !spirv.len().is_multiple_of(4) {
+            return Err("spir-v isn't made of whole words".to_owned());
+        }
+        let words = spirv
+            .chunks_exact(4)
+            .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+            .collect::<Vec<_>>();
+        if words.len() < 5 || words[0] != spirv::MAGIC {
+            return Err("not spir-v".to_owned());
+        }
+
+        let mut module = Self::default();
+        let mut idx = 5;
+        while idx < words.len() {
+            let count = (words[idx] >> 16) as usize;
+            let opcode = words[idx] & 0xffff;
+            if count == 0 || idx + count > words.len() {
+                return Err("truncated spir-v".to_owned());
+            }
+            module.read(opcode, &words[idx + 1..idx + count]);
+            idx += count;
+        }
+        Ok(module)
+    }
+
+    fn read(&mut self, opcode: u32, operands: &[u32]) {
+        let operand = |idx: usize| operands.get(idx).copied().unwrap_or_default();
+        let rest = |idx: usize| operands.get(idx..).unwrap_or_default();
+        let ty = match opcode {
+            spirv::op::NAME => {
+                self.names.insert(operand(0), string(rest(1)).0);
+                return;
+            }
+            spirv::op::ENTRY_POINT => {
+                self.stage = stage(operand(0));
+                let (_, name_len) = string(rest(2));
+                self.interface.extend(rest(2 + name_len));
+                return;
+            }
+            spirv::op::CONSTANT => {
+                self.constants.insert(operand(1), operand(2));
+                return;
+            }
+            spirv::op::VARIABLE => {
+                self.variables.insert(operand(1), (operand(0), operand(2)));
+                return;
+            }
+            spirv::op::DECORATE => {
+                self.decorations
+                    .insert((operand(0), operand(1)), operand(2));
+                return;
+            }
+            spirv::op::MEMBER_DECORATE => {
+                self.member_decorations
+                    .insert((operand(0), operand(1), operand(2)), operand(3));
+                return;
+            }
+            spirv::op::TYPE_INT | spirv::op::TYPE_FLOAT => Type::Scalar {
+                size: operand(1) / 8,
+            },
+            spirv::op::TYPE_VECTOR => Type::Vector {
+                component: operand(1),
+                count: operand(2),
+            },
+            spirv::op::TYPE_MATRIX => Type::Matrix {
+                column: operand(1),
+                count: operand(2),
+            },
+            spirv::op::TYPE_IMAGE => Type::Image {
+                sampled: operand(6),
+            },
+            spirv::op::TYPE_SAMPLER => Type::Sampler,
+            spirv::op::TYPE_SAMPLED_IMAGE => Type::SampledImage,
+            spirv::op::TYPE_ARRAY => Type::Array {
+                element: operand(1),
+                length: operand(2),
+            },
+            spirv::op::TYPE_RUNTIME_ARRAY => Type::RuntimeArray {
+                element: operand(1),
+            },
+            spirv::op::TYPE_STRUCT => Type::Struct {
+                members: rest(1).to_vec(),
+            },
+            spirv::op::TYPE_POINTER => Type::Pointer {
+                pointee: operand(2),
+            },
+            spirv::op::TYPE_ACCELERATION_STRUCTURE => Type::AccelerationStructure,
+            _ => return,
+        };
+        self.types.insert(operand(0), ty);
+    }
+
+    // blocks without an instance name go by the name of their type
+    fn variable_name(&self, variable: u32) -> String {
+        let pointee =
+            self.variables
+                .get(&variable)
+                .and_then(|&(ty, _)| match self.types.get(&ty) {
+                    Some(&Type::Pointer { pointee }) => Some(pointee),
+                    _ => None,
+                });
+        iter::once(variable)
+            .chain(pointee)
+            .find_map(|id| self.names.get(&id).filter(|name| !name.is_empty()))
+            .cloned()
+            .unwrap_or_else(|| format!("%{variable}"))
+    }
+
+    // a description of the mismatch, if there is one
+    fn check_variable(
+        &self,
+        variable: u32,
+        stage: Stage,
+        layout: &PipelineLayout,
+    ) -> Option<String> {
+        let &(ty, storage_class) = self.variables.get(&variable)?;
+        let Some(&Type::Pointer { pointee }) = self.types.get(&ty) else {
+            return None;
+        };
+
+        if storage_class == spirv::storage_class::PUSH_CONSTANT {
+            let Some(push_constants) = layout.push_constants else {
+                return Some("reads push constants but the layout has none".to_owned());
+            };
+            if !push_constants.stages.contains(&stage) {
+                return Some(format!(
+                    "reads push constants but the layout doesn't make them visible to {stage:?}"
+                ));
+            }
+            // the block ends with its last member, the rust struct can have padding after it
+            let size = self.size(pointee);
+            return (size > push_constants.size).then(|| {
+                format!(
+                    "reads {size} bytes of push constants but the layout only has {}",
+                    push_constants.size
+                )
+            });
+        }
+
+        if ![
+            spirv::storage_class::UNIFORM_CONSTANT,
+            spirv::storage_class::UNIFORM,
+            spirv::storage_class::STORAGE_BUFFER,
+        ]
+        .contains(&storage_class)
+        {
+            return None;
+        }
+        let set = self
+            .decorations
+            .get(&(variable, spirv::decoration::DESCRIPTOR_SET))
+            .copied()
+            .unwrap_or_default();
+        let binding = *self
+            .decorations
+            .get(&(variable, spirv::decoration::BINDING))?;
+
+        let Some(expected) = layout
+            .descriptor_sets
+            .iter()
+            .find(|descriptor_set| descriptor_set.set == set)
+            .and_then(|descriptor_set| {
+                descriptor_set
+                    .bindings
+                    .iter()
+                    .find(|expected| expected.binding == binding)
+            })
+        else {
+            return Some(format!(
+                "is bound to set {set} binding {binding}, which the layout doesn't have"
+            ));
+        };
+
+        let (descriptor_type, count) = self.descriptor(pointee, storage_class);
+        let expected_type = descriptor_type_name(expected.resource);
+        if descriptor_type != expected_type {
+            return Some(format!(
+                "is a {descriptor_type} but the layout has {} as a {expected_type}",
+                expected.name
+            ));
+        }
+        let count_matches = match expected.resource {
+            Resource::Textures { max_count } => {
+                matches!(count, Count::Runtime)
+                    || matches!(count, Count::Array(length) if length <= max_count)
+            }
+            _ => count == Count::Single,
+        };
+        if !count_matches {
+            return Some(format!(
+                "has a different number of descriptors than {} in the layout",
+                expected.name
+            ));
+        }
+        (!expected.stages.contains(&stage)).then(|| {
+            format!(
+                "is used by {stage:?} but the layout doesn't make {} visible to it",
+                expected.name
+            )
+        })
+    }
+
+    fn descriptor(&self, ty: u32, storage_class: u32) -> (&'static str, Count) {
+        let (ty, count) = match self.types.get(&ty) {
+            Some(&Type::Array { element, length }) => (
+                element,
+                Count::Array(self.constants.get(&length).copied().unwrap_or_default()),
+            ),
+            Some(&Type::RuntimeArray { element }) => (element, Count::Runtime),
+            _ => (ty, Count::Single),
+        };
+        let descriptor_type = match self.types.get(&ty) {
+            Some(Type::Struct { .. })
+                if storage_class == spirv::storage_class::UNIFORM
+                    && self
+                        .decorations
+                        .contains_key(&(ty, spirv::decoration::BLOCK)) =>
+            {
+                "uniform buffer"
+            }
+            Some(Type::Struct { .. }) => "storage buffer",
+            Some(Type::AccelerationStructure) => "acceleration structure",
+            Some(Type::Image { sampled: 2 }) => "storage image",
+            Some(Type::Image { .. }) => "sampled image",
+            Some(Type::SampledImage) => "combined image sampler",
+            Some(Type::Sampler) => "sampler",
+            _ => "unknown resource",
+        };
+        (descriptor_type, count)
+    }
+
+    // up to the end of the last member, the strides are only known from the decorations of the
+    // structs and arrays holding them
+    fn size(&self, ty: u32) -> u32 {
+        match self.types.get(&ty) {
+            Some(&Type::Scalar { size }) => size,
+            Some(&Type::Vector { component, count }) => self.size(component) * count,
+            Some(&Type::Matrix { column, count }) => self.size(column) * count,
+            Some(&Type::Array { element, length }) => {
+                let length = self.constants.get(&length).copied().unwrap_or_default();
+                let stride = self
+                    .decorations
+                    .get(&(ty, spirv::decoration::ARRAY_STRIDE))
+                    .copied()
+                    .unwrap_or_else(|| self.size(element));
+                stride * length
+            }
+            Some(Type::Struct { members }) => (0..)
+                .zip(members)
+                .map(|(member, &member_ty)| {
+                    let decoration =
+                        |decoration| self.member_decorations.get(&(ty, member, decoration));
+                    let offset = decoration(spirv::decoration::OFFSET)
+                        .copied()
+                        .unwrap_or_default();
+                    let size = match (
+                        self.types.get(&member_ty),
+                        decoration(spirv::decoration::MATRIX_STRIDE),
+                    ) {
+                        (Some(&Type::Matrix { count, .. }), Some(&stride)) => stride * count,
+                        _ => self.size(member_ty),
+                    };
+                    offset + size
+                })
+                .max()
+                .unwrap_or_default(),
+            _ => 0,
+        }
+    }
+}
+
+// the execution models of the stages
+const fn stage(execution_model: u32) -> Option<Stage> {
+    Some(match execution_model {
+        0 => Stage::Vertex,
+        4 => Stage::Fragment,
+        5 => Stage::Compute,
+        5313 => Stage::RayGeneration,
+        5314 => Stage::Intersection,
+        5315 => Stage::AnyHit,
+        5316 => Stage::ClosestHit,
+        5317 => Stage::Miss,
+        5318 => Stage::Callable,
+        5364 => Stage::Task,
+        5365 => Stage::Mesh,
+        _ => return None,
+    })
+}
+
+const fn descriptor_type_name(resource: Resource) -> &'static str {
+    match resource {
+        Resource::UniformBuffer { .. } => "uniform buffer",
+        Resource::AccelerationStructure => "acceleration structure",
+        Resource::StorageImage { .. } => "storage image",
+        Resource::Texture | Resource::Textures { .. } => "combined image sampler",
+    }
+}
+
+// a nul terminated string packed into words, and the number of words it takes
+fn string(words: &[u32]) -> (String, usize) {
+    let mut bytes = Vec::new();
+    for (idx, word) in words.iter().enumerate() {
+        for byte in word.to_le_bytes() {
+            if byte == 0 {
+                return (String::from_utf8_lossy(&bytes).into_owned(), idx + 1);
+            }
+            bytes.push(byte);
+        }
+    }
+    (String::from_utf8_lossy(&bytes).into_owned(), words.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::{conf, descriptors};
+
+    use super::*;
+
+    const LAYOUT: PipelineLayout = PipelineLayout {
+        name: "Test",
+        shaders: &[],
+        descriptor_sets: &[descriptors::SCENE],
+        push_constants: Some(pipelines::PushConstants {
+            size: 80,
+            stages: &[Stage::Vertex],
+        }),
+    };
+
+    const MAIN: [u32; 2] = [u32::from_le_bytes(*b"main"), 0];
+    const NAME: [u32; 3] = [
+        u32::from_le_bytes(*b"Cons"),
+        u32::from_le_bytes(*b"tant"),
+        u32::from_le_bytes(*b"s\0\0\0"),
+    ];
+
+    fn instruction(opcode: u32, operands: &[u32]) -> Vec<u32> {
+        let count = u32::try_from(operands.len() + 1).unwrap();
+        iter::once(count << 16 | opcode)
+            .chain(operands.iter().copied())
+            .collect()
+    }
+
+    fn bytes(words: &[u32]) -> Vec<u8> {
+        words.iter().flat_map(|word| word.to_le_bytes()).collect()
+    }
+
+    // a vertex shader reading a push constant block { mat4; uint; }, a uniform block { mat4; }
+    // and declaring a float[3] with a std140 stride
+    fn module() -> Vec<u8> {
+        let mut words = vec![spirv::MAGIC, 0x0001_0600, 0, 20, 0];
+        let instructions = [
+            (
+                spirv::op::ENTRY_POINT,
+                [&[0, 1], &MAIN[..], &[10, 11]].concat(),
+            ),
+            (spirv::op::NAME, [&[3], &NAME[..]].concat()),
+            (spirv::op::TYPE_FLOAT, vec![4, 32]),
+            (spirv::op::TYPE_VECTOR, vec![5, 4, 4]),
+            (spirv::op::TYPE_MATRIX, vec![6, 5, 4]),
+            (spirv::op::TYPE_INT, vec![7, 32, 0]),
+            (spirv::op::TYPE_STRUCT, vec![3, 6, 7]),
+            (
+                spirv::op::MEMBER_DECORATE,
+                vec![3, 0, spirv::decoration::OFFSET, 0],
+            ),
+            (
+                spirv::op::MEMBER_DECORATE,
+                vec![3, 0, spirv::decoration::MATRIX_STRIDE, 16],
+            ),
+            (
+                spirv::op::MEMBER_DECORATE,
+                vec![3, 1, spirv::decoration::OFFSET, 64],
+            ),
+            (
+                spirv::op::TYPE_POINTER,
+                vec![8, spirv::storage_class::PUSH_CONSTANT, 3],
+            ),
+            (
+                spirv::op::VARIABLE,
+                vec![8, 10, spirv::storage_class::PUSH_CONSTANT],
+            ),
+            (spirv::op::TYPE_STRUCT, vec![12, 6]),
+            (spirv::op::DECORATE, vec![12, spirv::decoration::BLOCK]),
+            (
+                spirv::op::MEMBER_DECORATE,
+                vec![12, 0, spirv::decoration::OFFSET, 0],
+            ),
+            (
+                spirv::op::MEMBER_DECORATE,
+                vec![12, 0, spirv::decoration::MATRIX_STRIDE, 16],
+            ),
+            (
+                spirv::op::TYPE_POINTER,
+                vec![13, spirv::storage_class::UNIFORM, 12],
+            ),
+            (
+                spirv::op::VARIABLE,
+                vec![13, 11, spirv::storage_class::UNIFORM],
+            ),
+            (
+                spirv::op::DECORATE,
+                vec![11, spirv::decoration::DESCRIPTOR_SET, 0],
+            ),
+            (
+                spirv::op::DECORATE,
+                vec![11, spirv::decoration::BINDING, conf::UNIFORMS_BINDING],
+            ),
+            (spirv::op::CONSTANT, vec![7, 14, 3]),
+            (spirv::op::TYPE_ARRAY, vec![15, 4, 14]),
+            (
+                spirv::op::DECORATE,
+                vec![15, spirv::decoration::ARRAY_STRIDE, 16],
+            ),
+        ];
+        for (opcode, operands) in instructions {
+            words.extend(instruction(opcode, &operands));
+        }
+        bytes(&words)
+    }
+
+    #[test]
+    fn parse() {
+        let module = Module::parse(&module()).unwrap();
+        assert_eq!(module.stage, Some(Stage::Vertex));
+        assert_eq!(module.interface, [10, 11]);
+        assert_eq!(module.variable_name(10), "Constants");
+        assert_eq!(module.variable_name(11), "%11");
+    }
+
+    #[test]
+    fn sizes() {
+        let module = Module::parse(&module()).unwrap();
+        assert_eq!(module.size(3), 68);
+        assert_eq!(module.size(12), 64);
+        assert_eq!(module.size(15), 48);
+    }
+
+    #[test]
+    fn push_constants() {
+        let module = Module::parse(&module()).unwrap();
+        // the rust struct is padded to 80 bytes after the uint
+        assert_eq!(module.check_variable(10, Stage::Vertex, &LAYOUT), None);
+
+        let smaller = PipelineLayout {
+            push_constants: Some(pipelines::PushConstants {
+                size: 64,
+                stages: &[Stage::Vertex],
+            }),
+            ..LAYOUT
+        };
+        assert_eq!(
+            module.check_variable(10, Stage::Vertex, &smaller).unwrap(),
+            "reads 68 bytes of push constants but the layout only has 64"
+        );
+        assert!(module
+            .check_variable(10, Stage::Fragment, &LAYOUT)
+            .unwrap()
+            .contains("doesn't make them visible to Fragment"));
+    }
+
+    #[test]
+    fn descriptors() {
+        let module = Module::parse(&module()).unwrap();
+        assert!(
+            module.descriptor(12, spirv::storage_class::UNIFORM)
+                == ("uniform buffer", Count::Single)
+        );
+        assert_eq!(module.check_variable(11, Stage::Vertex, &LAYOUT), None);
+        assert!(module
+            .check_variable(11, Stage::Fragment, &LAYOUT)
+            .unwrap()
+            .contains("doesn't make uniforms visible"));
+    }
+
+    #[test]
+    fn malformed() {
+        let mut spirv = module();
+        assert!(Module::parse(&spirv[..spirv.len() - 2]).is_err());
+        assert!(Module::parse(&spirv[..spirv.len() - 4]).is_err());
+        spirv[0] = 0;
+        assert!(Module::parse(&spirv).is_err());
+    }
+
+    // only shaders that pipelines list are checked
+    #[test]
+    fn listed_shaders() {
+        assert!(check("unlisted.vert.glsl", &[0; 2]).is_empty());
+        assert_eq!(
+            check("rasterizer.vert.glsl", &[0; 2]),
+            ["rasterizer.vert.glsl: spir-v isn't made of whole words"]
+        );
+    }
+}
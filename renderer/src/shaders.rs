@@ -0,0 +1,30 @@
+use std::collections::HashMap;
+
+// an enum for each shader, with a variant for each of its entries in variants.toml
+include!(concat!(env!("OUT_DIR"), "/shaders.rs"));
+
+// a variant of a shader as built, by its path from the shaders directory
+#[derive(Clone, Copy, Debug)]
+pub struct ShaderFile {
+    pub source: &'static str,
+    pub variant: &'static str,
+    pub path: &'static str,
+}
+
+// where the pipelines load the shaders built to a path from, hot-reload points them to the last
+// spir-v that compiled
+#[derive(Default)]
+pub struct Paths {
+    reloaded: HashMap<&'static str, String>,
+}
+
+impl Paths {
+    pub fn get(&self, path: &'static str) -> &str {
+        self.reloaded.get(path).map_or(path, String::as_str)
+    }
+
+    #[cfg(feature = "hot-reload")]
+    pub(crate) fn reload(&mut self, path: &'static str, spirv: String) {
+        self.reloaded.insert(path, spirv);
+    }
+}
@@ -16,6 +16,7 @@ use super::{
 pub struct World {
     pub indices: Buffer,
     pub vertices: Buffer,
+    colors: Buffer,
     primitives: Buffer,
     materials: Buffer,
     pub scene_desc: Buffer,
@@ -23,6 +24,7 @@ pub struct World {
     pub textures: Vec<Texture<{ Format::Color }>>,
     pub info: SceneInfo,
     pub accel: AccelerationStructures,
+    default_materials: Vec<u32>,
 }
 
 pub struct SceneInfo {
@@ -41,6 +43,7 @@ impl World {
         ));
 
         let (vertices, indices) = Self::init_vertex_index_buffer(ctx, &mut scope, &scene.data);
+        let colors = Self::init_colors_buffer(ctx, &mut scope, &scene.data);
         let primitives = Self::init_primitives_buffer(ctx, &mut scope, &scene.info);
         let materials = Self::init_materials_buffer(ctx, &mut scope, &scene.data);
 
@@ -49,6 +52,8 @@ impl World {
             indices_address: indices.get_device_address(ctx),
             materials_address: materials.get_device_address(ctx),
             primitives_address: primitives.get_device_address(ctx),
+            colors_address: colors.get_device_address(ctx),
+            ..Default::default()
         };
         let scene_desc = Self::init_scene_desc_buffer(ctx, &mut scope, &device_info);
 
@@ -61,6 +66,11 @@ impl World {
         ));
 
         let scene::Scene { info, data } = scene;
+        let default_materials = info
+            .primitive_infos
+            .iter()
+            .map(|primitive| primitive.material)
+            .collect();
         let (images, textures) = Self::init_textures(ctx, &mut scope, &info, data);
 
         scope.finish(ctx);
@@ -75,6 +85,7 @@ impl World {
         Self {
             indices,
             vertices,
+            colors,
             primitives,
             materials,
             scene_desc,
@@ -82,9 +93,32 @@ impl World {
             textures,
             info,
             accel,
+            default_materials,
         }
     }
 
+    // the primitives buffer must not be in use
+    pub fn set_material_variant(&mut self, ctx: &Context, variant: Option<usize>) {
+        firestorm::profile_method!(set_material_variant);
+
+        self.info
+            .host
+            .apply_variant(&self.default_materials, variant);
+
+        let mut scope = Scope::new(Commands::begin_on_queue(
+            ctx,
+            "World - Material Variant".to_owned(),
+            ctx.queues.transfer(),
+        ));
+        self.primitives.update_with_staged_data(
+            ctx,
+            &mut scope,
+            "Primitives".to_owned(),
+            bytemuck::cast_slice(&self.info.host.primitive_infos),
+        );
+        scope.finish(ctx);
+    }
+
     fn init_vertex_index_buffer(
         ctx: &Context,
         scope: &mut Scope,
@@ -131,6 +165,30 @@ impl World {
         (vertices, indices)
     }
 
+    fn init_colors_buffer(ctx: &Context, scope: &mut Scope, scene: &scene::Data) -> Buffer {
+        firestorm::profile_method!(init_colors_buffer);
+
+        let create_info = vk::BufferCreateInfo::default().usage(
+            vk::BufferUsageFlags::STORAGE_BUFFER | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+        );
+
+        // buffers can't be empty, so use a placeholder when no primitive has vertex colors
+        let colors = if scene.colors.is_empty() {
+            std::slice::from_ref(&glam::Vec4::ONE)
+        } else {
+            scene.colors.as_slice()
+        };
+
+        Buffer::create_with_staged_data(
+            ctx,
+            scope,
+            "Colors".to_owned(),
+            create_info,
+            bytemuck::cast_slice(colors),
+            memory::Priority::Medium,
+        )
+    }
+
     fn init_primitives_buffer(ctx: &Context, scope: &mut Scope, scene: &scene::Info) -> Buffer {
         firestorm::profile_method!(init_primitives_buffer);
 
@@ -252,6 +310,7 @@ impl Destroy<Context> for World {
         self.scene_desc.destroy_with(ctx);
         self.primitives.destroy_with(ctx);
         self.materials.destroy_with(ctx);
+        self.colors.destroy_with(ctx);
         self.vertices.destroy_with(ctx);
         self.indices.destroy_with(ctx);
     }
@@ -0,0 +1,379 @@
+use std::{
+    collections::BTreeMap,
+    hash::{DefaultHasher, Hasher},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    description::Description,
+    gltf::Gltf,
+    io,
+    io::{FileLoader, Result},
+    options::ImportOptions,
+};
+
+// inputs whose dependencies and options hash to the recorded values are skipped
+#[derive(Default, Deserialize, Serialize)]
+pub struct Manifest {
+    entries: BTreeMap<PathBuf, Entry>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+struct Entry {
+    options: u64,
+    dependencies: Vec<(PathBuf, u64)>,
+}
+
+#[derive(Default, Serialize)]
+pub struct Summary {
+    pub processed: Vec<PathBuf>,
+    pub skipped: Vec<PathBuf>,
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+enum Outcome {
+    Processed(Entry),
+    Skipped,
+    Failed(String),
+}
+
+impl Manifest {
+    // a missing manifest is an empty one, an unreadable or corrupt one is left to the caller
+    pub fn load(file: impl AsRef<Path>) -> Result<Self> {
+        match std::fs::read_to_string(file) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map_err(|error| format!("Invalid preprocess cache manifest: {error}").into()),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(format!("Unable to read preprocess cache manifest: {error}").into()),
+        }
+    }
+
+    pub fn save(&self, file: impl AsRef<Path>) {
+        let contents = serde_json::to_string_pretty(self).expect("Failed to serialize manifest");
+        std::fs::write(file, contents).expect("Unable to write preprocess cache manifest");
+    }
+}
+
+// preprocesses every scene file under the given paths in parallel, updating the manifest
+pub fn run(inputs: &[PathBuf], options: &ImportOptions, manifest: &mut Manifest) -> Summary {
+    firestorm::profile_fn!(batch_run);
+
+    let mut summary = Summary::default();
+    let mut files = Vec::new();
+    for input in inputs {
+        collect_files(input, &mut files, &mut summary.failed);
+    }
+    files.sort();
+    files.dedup();
+
+    let options_hash = hash_options(options);
+    let conflicts = output_conflicts(&files);
+
+    let outcomes = files
+        .par_iter()
+        .map(|file| {
+            // written concurrently, neither output could be trusted
+            if let Some(others) = conflicts.get(file.as_path()) {
+                return Outcome::Failed(format!(
+                    "Writes the same output as {}",
+                    others
+                        .iter()
+                        .map(|other| other.display().to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ));
+            }
+
+            let cached = manifest.entries.get(file);
+            if cached.is_some_and(|entry| entry.is_fresh(file, options_hash)) {
+                return Outcome::Skipped;
+            }
+
+            // a broken input shouldn't abort the whole batch
+            process(file, options, options_hash).map_or_else(
+                |error| Outcome::Failed(error.to_string()),
+                Outcome::Processed,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    for (file, outcome) in files.into_iter().zip(outcomes) {
+        match outcome {
+            Outcome::Processed(entry) => {
+                manifest.entries.insert(file.clone(), entry);
+                summary.processed.push(file);
+            }
+            Outcome::Skipped => summary.skipped.push(file),
+            Outcome::Failed(message) => {
+                manifest.entries.remove(&file);
+                summary.failed.push((file, message));
+            }
+        }
+    }
+    summary
+}
+
+// the inputs that share their output path with others, like a.gltf and a.glb, with those others
+fn output_conflicts(files: &[PathBuf]) -> BTreeMap<&Path, Vec<&Path>> {
+    let mut outputs = BTreeMap::<_, Vec<_>>::new();
+    for file in files {
+        outputs
+            .entry(io::output_path(file))
+            .or_default()
+            .push(file.as_path());
+    }
+
+    let mut conflicts = BTreeMap::new();
+    for sharing in outputs.into_values().filter(|sharing| sharing.len() > 1) {
+        for &file in &sharing {
+            let others = sharing
+                .iter()
+                .copied()
+                .filter(|&other| other != file)
+                .collect();
+            conflicts.insert(file, others);
+        }
+    }
+    conflicts
+}
+
+fn process(file: &Path, options: &ImportOptions, options_hash: u64) -> Result<Entry> {
+    // hashed before importing so that changes made in the meantime are picked up next time
+    let dependencies = io::dependencies(file)?
+        .into_iter()
+        .map(|dependency| {
+            let hash = hash_file(&dependency)
+                .ok_or_else(|| format!("Unable to read dependency {}", dependency.display()))?;
+            Ok((dependency, hash))
+        })
+        .collect::<Result<_>>()?;
+
+    let scene = io::import_with_options(file, options)?;
+
+    let report = scene.validate();
+    if report.has_errors() {
+        return Err(report.to_string().into());
+    }
+
+    io::save(scene, file)?;
+
+    Ok(Entry {
+        options: options_hash,
+        dependencies,
+    })
+}
+
+impl Entry {
+    fn is_fresh(&self, file: &Path, options_hash: u64) -> bool {
+        self.options == options_hash
+            && io::output_path(file).exists()
+            && self
+                .dependencies
+                .iter()
+                .all(|(dependency, hash)| hash_file(dependency) == Some(*hash))
+    }
+}
+
+// directories that can't be read fail like broken inputs, without the files of the others
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>, failed: &mut Vec<(PathBuf, String)>) {
+    if path.is_dir() {
+        let entries = std::fs::read_dir(path).and_then(|entries| {
+            entries
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<std::io::Result<Vec<_>>>()
+        });
+        let mut entries = match entries {
+            Ok(entries) => entries,
+            Err(error) => {
+                failed.push((
+                    path.to_owned(),
+                    format!("Unable to read input directory: {error}"),
+                ));
+                return;
+            }
+        };
+        entries.sort();
+        for entry in entries {
+            collect_files(&entry, files, failed);
+        }
+    } else if Gltf::can_load(path) || Description::can_load(path) {
+        files.push(path.to_owned());
+    }
+}
+
+// not stable across toolchains, which only costs a rebuild of the cache
+fn hash_file(file: &Path) -> Option<u64> {
+    let contents = std::fs::read(file).ok()?;
+    let mut hasher = DefaultHasher::new();
+    hasher.write(&contents);
+    Some(hasher.finish())
+}
+
+fn hash_options(options: &ImportOptions) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    hasher.write(
+        serde_json::to_string(options)
+            .expect("Failed to serialize import options")
+            .as_bytes(),
+    );
+    // outputs of other versions of the preprocessor may not be compatible
+    hasher.write(env!("CARGO_PKG_VERSION").as_bytes());
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate, testing::Fixture};
+
+    fn run_in(fixture: &Fixture, options: &ImportOptions, manifest: &mut Manifest) -> Summary {
+        run(&[fixture.dir().to_path_buf()], options, manifest)
+    }
+
+    fn names(files: &[PathBuf]) -> Vec<&str> {
+        files
+            .iter()
+            .map(|file| file.file_name().unwrap().to_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn changed_inputs_are_reprocessed() {
+        let fixture = Fixture::new("batch-cache");
+        fixture.export("cube.gltf", &generate::cube());
+        let description = "[[sources]]\nfile = \"cube.gltf\"\n";
+        fixture.write("scene.toml", description);
+        let options = ImportOptions::default();
+        let mut manifest = Manifest::default();
+
+        let summary = run_in(&fixture, &options, &mut manifest);
+        assert_eq!(names(&summary.processed), ["cube.gltf", "scene.toml"]);
+        assert!(summary.failed.is_empty());
+        assert!(fixture.path("scene.tsnasset").exists());
+
+        let summary = run_in(&fixture, &options, &mut manifest);
+        assert!(summary.processed.is_empty());
+        assert_eq!(names(&summary.skipped), ["cube.gltf", "scene.toml"]);
+
+        // the description itself
+        fixture.write("scene.toml", &format!("{description}visible = true\n"));
+        let summary = run_in(&fixture, &options, &mut manifest);
+        assert_eq!(names(&summary.processed), ["scene.toml"]);
+        assert_eq!(names(&summary.skipped), ["cube.gltf"]);
+
+        // a dependency of the description
+        fixture.export("cube.gltf", &generate::sphere());
+        let summary = run_in(&fixture, &options, &mut manifest);
+        assert_eq!(names(&summary.processed), ["cube.gltf", "scene.toml"]);
+
+        // the options
+        let options = ImportOptions {
+            fit: Some(2.),
+            ..ImportOptions::default()
+        };
+        let summary = run_in(&fixture, &options, &mut manifest);
+        assert_eq!(names(&summary.processed), ["cube.gltf", "scene.toml"]);
+
+        // a missing output
+        std::fs::remove_file(fixture.path("cube.tsnasset")).unwrap();
+        let summary = run_in(&fixture, &options, &mut manifest);
+        assert_eq!(names(&summary.processed), ["cube.gltf"]);
+        assert_eq!(names(&summary.skipped), ["scene.toml"]);
+    }
+
+    #[test]
+    fn conflicting_outputs_fail() {
+        let fixture = Fixture::new("batch-conflicts");
+        fixture.export("cube.gltf", &generate::cube());
+        fixture.export("cube.glb", &generate::cube());
+        fixture.export("plane.gltf", &generate::plane());
+
+        let summary = run_in(
+            &fixture,
+            &ImportOptions::default(),
+            &mut Manifest::default(),
+        );
+        assert_eq!(names(&summary.processed), ["plane.gltf"]);
+        let failed = summary
+            .failed
+            .iter()
+            .map(|(file, message)| (file.clone(), message.clone()))
+            .collect::<BTreeMap<_, _>>();
+        assert_eq!(failed.len(), 2);
+        for (file, other) in [("cube.glb", "cube.gltf"), ("cube.gltf", "cube.glb")] {
+            assert_eq!(
+                failed[&fixture.path(file)],
+                format!(
+                    "Writes the same output as {}",
+                    fixture.path(other).display()
+                )
+            );
+        }
+        assert!(!fixture.path("cube.tsnasset").exists());
+    }
+
+    #[test]
+    fn broken_inputs_fail_alone() {
+        let fixture = Fixture::new("batch-broken");
+        fixture.write("broken.gltf", "not a gltf");
+        fixture.export("cube.gltf", &generate::cube());
+        // fails in one of the sources imported in parallel
+        let description = "[[sources]]\nfile = \"cube.gltf\"\nhide = [\"Missing\"]\n";
+        fixture.write("scene.toml", description);
+        let mut manifest = Manifest::default();
+
+        let summary = run_in(&fixture, &ImportOptions::default(), &mut manifest);
+        assert_eq!(names(&summary.processed), ["cube.gltf"]);
+        let failed = summary.failed.into_iter().collect::<BTreeMap<_, _>>();
+        assert_eq!(failed.len(), 2);
+        assert!(failed[&fixture.path("broken.gltf")].starts_with("Couldn't read gltf headers"));
+        assert_eq!(
+            failed[&fixture.path("scene.toml")],
+            "No node named \"Missing\" found"
+        );
+        assert!(!manifest.entries.contains_key(&fixture.path("broken.gltf")));
+    }
+
+    #[test]
+    fn unwritable_outputs_fail_alone() {
+        let fixture = Fixture::new("batch-unwritable");
+        fixture.export("cube.gltf", &generate::cube());
+        fixture.export("plane.gltf", &generate::plane());
+        // in the way of the output
+        std::fs::create_dir(fixture.path("cube.tsnasset")).unwrap();
+        let mut manifest = Manifest::default();
+
+        let summary = run_in(&fixture, &ImportOptions::default(), &mut manifest);
+        assert_eq!(names(&summary.processed), ["plane.gltf"]);
+        assert_eq!(summary.failed.len(), 1);
+        assert_eq!(summary.failed[0].0, fixture.path("cube.gltf"));
+        assert!(summary.failed[0].1.starts_with("Unable to open"));
+        assert!(!manifest.entries.contains_key(&fixture.path("cube.gltf")));
+    }
+
+    #[test]
+    fn corrupt_manifests_fail_to_load() {
+        let fixture = Fixture::new("batch-manifest");
+        let file = fixture.path("manifest.json");
+        assert!(Manifest::load(&file).unwrap().entries.is_empty());
+
+        let mut manifest = Manifest::default();
+        fixture.export("cube.gltf", &generate::cube());
+        run_in(&fixture, &ImportOptions::default(), &mut manifest);
+        manifest.save(&file);
+        assert_eq!(Manifest::load(&file).unwrap().entries.len(), 1);
+
+        // truncated while being written
+        let contents = std::fs::read_to_string(&file).unwrap();
+        std::fs::write(&file, &contents[..contents.len() / 2]).unwrap();
+        assert!(Manifest::load(&file)
+            .err()
+            .unwrap()
+            .to_string()
+            .starts_with("Invalid preprocess cache manifest"));
+    }
+}
@@ -1,16 +1,191 @@
-use std::{env, path::Path};
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
 
-use scene::io::FileLoader;
+use scene::{
+    batch,
+    options::{ImportOptions, SceneSelector, Units, UpAxis},
+};
+
+mod conf {
+    pub const CACHE_MANIFEST: &str = "preprocess_cache.json";
+}
+
+struct Args {
+    inputs: Vec<PathBuf>,
+    batch: bool,
+    cache: PathBuf,
+    summary: Option<PathBuf>,
+    export: Option<PathBuf>,
+    generate: Option<String>,
+    options: ImportOptions,
+}
 
 fn main() {
-    let filename = env::args().nth(1).expect("No asset filename provided");
-    let filepath = Path::new(&filename);
+    let args = Args::parse();
 
-    let scene = if scene::gltf::Gltf::can_load(filepath) {
-        scene::gltf::Gltf::load(filepath)
-    } else {
-        panic!("No loader found");
+    if args.batch {
+        run_batch(&args);
+        return;
+    }
+    if let Some(name) = &args.generate {
+        run_generate(&args, name);
+        return;
+    }
+
+    let [filepath] = args.inputs.as_slice() else {
+        panic!("Expected a single asset filename, use --batch for several");
     };
 
-    scene::io::save(&scene, filepath);
+    let scene = scene::io::import_with_options(filepath, &args.options)
+        .unwrap_or_else(|error| panic!("Failed to import {}: {error}", filepath.display()));
+
+    let report = scene.validate();
+    if !report.diagnostics.is_empty() {
+        eprintln!("{report}");
+    }
+    assert!(!report.has_errors(), "Scene failed validation");
+
+    save(&args, scene, filepath);
+}
+
+fn run_generate(args: &Args, name: &str) {
+    let mut scene = scene::generate::generate(name).unwrap_or_else(|| {
+        panic!(
+            "Unknown generator {name}, expected one of {}",
+            scene::generate::GENERATORS.join(", ")
+        )
+    });
+    args.options
+        .apply(&mut scene)
+        .unwrap_or_else(|error| panic!("{error}"));
+
+    // named after the generator unless an output filename is given
+    let filepath = match args.inputs.as_slice() {
+        [] => PathBuf::from(name),
+        [filepath] => filepath.clone(),
+        _ => panic!("Expected at most one output filename with --generate"),
+    };
+
+    let report = scene.validate();
+    assert!(
+        !report.has_errors(),
+        "Generated scene failed validation\n{report}"
+    );
+
+    save(args, scene, &filepath);
+}
+
+fn save(args: &Args, scene: scene::Scene, filepath: &Path) {
+    if let Some(export) = &args.export {
+        scene::io::export(&scene, export);
+    }
+    scene::io::save(scene, filepath)
+        .unwrap_or_else(|error| panic!("Failed to save {}: {error}", filepath.display()));
+    println!(
+        "Asset processed and saved to {}",
+        scene::io::output_path(filepath).display()
+    );
+}
+
+fn run_batch(args: &Args) {
+    // only costs a rebuild of every asset
+    let mut manifest = batch::Manifest::load(&args.cache).unwrap_or_else(|error| {
+        eprintln!("Warning: {error}, preprocessing every asset again");
+        batch::Manifest::default()
+    });
+    let summary = batch::run(&args.inputs, &args.options, &mut manifest);
+    manifest.save(&args.cache);
+
+    println!("\nProcessed {}", summary.processed.len());
+    for file in &summary.processed {
+        println!("  {}", file.display());
+    }
+    println!("Skipped {} (unchanged)", summary.skipped.len());
+    println!("Failed {}", summary.failed.len());
+    for (file, message) in &summary.failed {
+        println!("  {}: {message}", file.display());
+    }
+
+    if let Some(summary_file) = &args.summary {
+        let contents = serde_json::to_string_pretty(&summary).expect("Failed to serialize summary");
+        std::fs::write(summary_file, contents).expect("Unable to write summary");
+    }
+
+    assert!(
+        summary.failed.is_empty(),
+        "Some assets failed to preprocess"
+    );
+}
+
+impl Args {
+    // preprocess <file> [--export FILE.gltf|glb] [options]
+    // preprocess --generate sphere|box|plane|cornell|materials|furnace [output] [--export FILE]
+    // preprocess --batch <file or directory>... [--cache FILE] [--summary FILE] [options]
+    //
+    // options: [--scene INDEX|NAME] [--include PATTERN]... [--exclude PATTERN]...
+    //          [--up-axis y|z] [--units m|cm|mm|in|ft] [--fit SIZE] [--recenter]
+    fn parse() -> Self {
+        let mut inputs = Vec::new();
+        let mut batch = false;
+        let mut cache = PathBuf::from(conf::CACHE_MANIFEST);
+        let mut summary = None;
+        let mut export = None;
+        let mut generate = None;
+        let mut options = ImportOptions::default();
+
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .unwrap_or_else(|| panic!("{arg} requires a value"))
+            };
+            match arg.as_str() {
+                "--batch" => batch = true,
+                "--cache" => cache = value().into(),
+                "--summary" => summary = Some(value().into()),
+                "--export" => export = Some(value().into()),
+                "--generate" => generate = Some(value()),
+                "--scene" => options.scene = Some(SceneSelector::parse(&value())),
+                "--include" => options.include.push(value()),
+                "--exclude" => options.exclude.push(value()),
+                "--up-axis" => {
+                    let axis = value();
+                    options.up_axis =
+                        UpAxis::parse(&axis).unwrap_or_else(|| panic!("Unknown up axis {axis}"));
+                }
+                "--units" => {
+                    let units = value();
+                    options.units =
+                        Units::parse(&units).unwrap_or_else(|| panic!("Unknown units {units}"));
+                }
+                "--fit" => {
+                    let size = value();
+                    options.fit = Some(
+                        size.parse()
+                            .unwrap_or_else(|_| panic!("Invalid size {size}")),
+                    );
+                }
+                "--recenter" => options.recenter = true,
+                _ if arg.starts_with("--") => panic!("Unknown argument {arg}"),
+                _ => inputs.push(arg.into()),
+            }
+        }
+
+        assert!(
+            !inputs.is_empty() || generate.is_some(),
+            "No asset filename provided"
+        );
+
+        Self {
+            inputs,
+            batch,
+            cache,
+            summary,
+            export,
+            generate,
+            options,
+        }
+    }
 }
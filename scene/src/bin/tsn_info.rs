@@ -0,0 +1,111 @@
+use std::env;
+
+use scene::stats::Stats;
+
+fn main() {
+    let mut filename = None;
+    let mut json = false;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--json" => json = true,
+            _ => filename = Some(arg),
+        }
+    }
+    let filename = filename.expect("No asset filename provided");
+
+    let scene = scene::io::load(&filename);
+    let stats = Stats::compute(&scene);
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&stats).expect("Failed to serialize stats")
+        );
+    } else {
+        print_summary(&filename, &stats);
+    }
+}
+
+fn print_summary(filename: &str, stats: &Stats) {
+    println!("{filename}");
+
+    println!("\nTotals");
+    println!("  vertices             {:>12}", stats.vertices);
+    println!("  indices              {:>12}", stats.indices);
+    println!("  triangles            {:>12}", stats.triangles);
+    println!("  primitives           {:>12}", stats.primitives);
+    println!("  instances            {:>12}", stats.instances);
+    println!("  nodes                {:>12}", stats.nodes);
+    println!("  instanced triangles  {:>12}", stats.instanced_triangles);
+    if stats.invalid_instances > 0 {
+        println!(
+            "  invalid instances    {:>12}  (missing primitives, not counted)",
+            stats.invalid_instances
+        );
+    }
+    println!("  materials            {:>12}", stats.materials);
+    println!("  textures             {:>12}", stats.textures);
+    println!("  images               {:>12}", stats.images);
+    println!("  variants             {:>12}", stats.variants);
+
+    let bbox = &stats.bounding_box;
+    println!("\nBounding box");
+    println!("  min   {:?}", bbox.min.to_array());
+    println!("  max   {:?}", bbox.max.to_array());
+    println!("  size  {:?}", bbox.size().to_array());
+
+    println!("\nMaterials");
+    println!(
+        "  {:>8}  {:>10}  {:>8}  {:>9}  {:>18}",
+        "material", "primitives", "color", "emittance", "metallic_roughness"
+    );
+    let texture = |texture: Option<u32>| texture.map_or_else(|| "-".to_owned(), |t| t.to_string());
+    for usage in &stats.material_usage {
+        println!(
+            "  {:>8}  {:>10}  {:>8}  {:>9}  {:>18}",
+            usage.material,
+            usage.primitives,
+            texture(usage.color_texture),
+            texture(usage.emittance_texture),
+            texture(usage.metallic_roughness_texture),
+        );
+    }
+
+    let memory = &stats.gpu_memory;
+    println!("\nEstimated GPU memory");
+    println!("  vertices    {:>12}", format_bytes(memory.vertices));
+    println!("  colors      {:>12}", format_bytes(memory.colors));
+    println!("  indices     {:>12}", format_bytes(memory.indices));
+    println!("  primitives  {:>12}", format_bytes(memory.primitives));
+    println!("  materials   {:>12}", format_bytes(memory.materials));
+    println!("  scene desc  {:>12}", format_bytes(memory.scene_desc));
+    println!("  images      {:>12}", format_bytes(memory.images));
+    println!("  blases      {:>12}  (rough)", format_bytes(memory.blases));
+    println!("  tlas        {:>12}  (rough)", format_bytes(memory.tlas));
+    println!(
+        "  scratch     {:>12}  (rough)",
+        format_bytes(memory.scratch)
+    );
+    println!("  total       {:>12}", format_bytes(memory.total));
+    println!(
+        "  (rough: the driver picks the acceleration structure sizes, these are typical ones)"
+    );
+    for image in &memory.unreadable_images {
+        println!("  (unable to read image {})", image.display());
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024. && unit < UNITS.len() - 1 {
+        value /= 1024.;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.2} {}", UNITS[unit])
+    }
+}
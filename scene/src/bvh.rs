@@ -0,0 +1,682 @@
+use crate::{BoundingBox, Scene};
+
+mod conf {
+    pub const BINS: usize = 16;
+    // leaves this small are kept whenever splitting them doesn't pay off
+    pub const MAX_LEAF_SIZE: usize = 4;
+    // cost of visiting a node relative to intersecting a triangle
+    pub const TRAVERSAL_COST: f32 = 1.;
+}
+
+// bounding volume hierarchy over the world space triangles of every instance of a scene
+pub struct Bvh {
+    nodes: Vec<Node>,
+    triangles: Vec<Triangle>,
+    // (primitive, material) of each instance
+    instances: Vec<(usize, u32)>,
+}
+
+pub struct Ray {
+    pub origin: glam::Vec3,
+    pub direction: glam::Vec3,
+    // in units of `direction`, hits are only reported in (0, max_distance)
+    pub max_distance: f32,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Hit {
+    pub distance: f32,
+    pub position: glam::Vec3,
+    pub instance: usize,
+    pub primitive: usize,
+    // index of the triangle within the primitive
+    pub triangle: usize,
+    // weights of the second and third vertices of the triangle
+    pub barycentrics: glam::Vec2,
+    pub material: u32,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Overlap {
+    pub instance: usize,
+    pub primitive: usize,
+    pub triangle: usize,
+    pub material: u32,
+}
+
+// inner nodes are followed by their first child and store the index of the second one in
+// `start`, leaves store their triangles range
+#[derive(Clone, Copy, Default)]
+struct Node {
+    bounding_box: BoundingBox,
+    start: u32,
+    count: u32,
+}
+
+struct Triangle {
+    vertices: [glam::Vec3; 3],
+    instance: u32,
+    triangle: u32,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Bin {
+    bounding_box: BoundingBox,
+    count: usize,
+}
+
+impl Bvh {
+    pub fn new(scene: &Scene) -> Self {
+        firestorm::profile_method!(new);
+
+        let mut triangles = Vec::new();
+        for (idx, instance) in scene.info.instances.iter().enumerate() {
+            let primitive = &scene.info.primitive_infos[instance.primitive_index];
+            let size = &scene.info.primitive_sizes[instance.primitive_index];
+            let vertices = &scene.data.vertices[primitive.vertices_offset as usize..];
+            let indices = &scene.data.indices[primitive.indices_offset as usize..]
+                [..size.indices_size as usize];
+
+            triangles.extend(
+                indices
+                    .chunks_exact(3)
+                    .enumerate()
+                    .map(|(triangle, indices)| Triangle {
+                        vertices: [indices[0], indices[1], indices[2]].map(|index| {
+                            instance
+                                .transform
+                                .transform_point3(vertices[index as usize].position.truncate())
+                        }),
+                        instance: idx as u32,
+                        triangle: triangle as u32,
+                    }),
+            );
+        }
+
+        let instances = scene
+            .info
+            .instances
+            .iter()
+            .map(|instance| {
+                let material = scene.info.primitive_infos[instance.primitive_index].material;
+                (instance.primitive_index, material)
+            })
+            .collect();
+
+        let mut bvh = Self {
+            nodes: Vec::new(),
+            triangles,
+            instances,
+        };
+        bvh.nodes.push(Node::default());
+        bvh.subdivide(0, 0, bvh.triangles.len());
+        bvh
+    }
+
+    pub fn bounding_box(&self) -> BoundingBox {
+        self.nodes[0].bounding_box
+    }
+
+    pub fn closest_hit(&self, ray: &Ray) -> Option<Hit> {
+        // every reported hit is closer than the previous ones
+        let mut closest = None;
+        self.traverse(ray, |idx, hit| {
+            closest = Some((idx, hit));
+            false
+        });
+        closest.map(|(idx, hit)| self.hit(idx, ray, hit))
+    }
+
+    // the first hit found, which is cheaper than the closest one, e.g. for occlusion tests
+    pub fn any_hit(&self, ray: &Ray) -> Option<Hit> {
+        let mut found = None;
+        self.traverse(ray, |idx, hit| {
+            found = Some((idx, hit));
+            true
+        });
+        found.map(|(idx, hit)| self.hit(idx, ray, hit))
+    }
+
+    // every triangle overlapping the box
+    pub fn overlapping(&self, bounding_box: &BoundingBox) -> Vec<Overlap> {
+        let mut overlaps = Vec::new();
+        if bounding_box.is_empty() || self.triangles.is_empty() {
+            return overlaps;
+        }
+
+        let mut stack = vec![0];
+        while let Some(idx) = stack.pop() {
+            let node = &self.nodes[idx];
+            if !node.bounding_box.overlaps(bounding_box) {
+                continue;
+            }
+            if node.is_leaf() {
+                for triangle in &self.triangles[node.range()] {
+                    if triangle.overlaps(bounding_box) {
+                        let (primitive, material) = self.instances[triangle.instance as usize];
+                        overlaps.push(Overlap {
+                            instance: triangle.instance as usize,
+                            primitive,
+                            triangle: triangle.triangle as usize,
+                            material,
+                        });
+                    }
+                }
+            } else {
+                stack.extend([idx + 1, node.start as usize]);
+            }
+        }
+        overlaps
+    }
+
+    // calls `on_hit` for every intersected triangle closer than the ones before it, until it
+    // returns true
+    fn traverse(&self, ray: &Ray, mut on_hit: impl FnMut(usize, glam::Vec3) -> bool) {
+        if self.triangles.is_empty() {
+            return;
+        }
+
+        let inv_direction = ray.direction.recip();
+        let mut max_distance = ray.max_distance;
+        let mut stack = vec![0];
+        while let Some(idx) = stack.pop() {
+            let node = &self.nodes[idx];
+            if node
+                .bounding_box
+                .intersect(ray.origin, inv_direction, max_distance)
+                .is_none()
+            {
+                continue;
+            }
+
+            if node.is_leaf() {
+                for triangle in node.range() {
+                    let Some(hit) = intersect(&self.triangles[triangle], ray, max_distance) else {
+                        continue;
+                    };
+                    max_distance = hit.x;
+                    if on_hit(triangle, hit) {
+                        return;
+                    }
+                }
+                continue;
+            }
+
+            // the nearest child is visited first, so that farther nodes are more often culled
+            let children = [idx + 1, node.start as usize].map(|child| {
+                let distance = self.nodes[child].bounding_box.intersect(
+                    ray.origin,
+                    inv_direction,
+                    max_distance,
+                );
+                (child, distance)
+            });
+            let [near, far] = if children[1].1 < children[0].1 {
+                [children[1], children[0]]
+            } else {
+                children
+            };
+            for (child, distance) in [far, near] {
+                if distance.is_some() {
+                    stack.push(child);
+                }
+            }
+        }
+    }
+
+    // `hit` is the distance followed by the barycentrics
+    fn hit(&self, idx: usize, ray: &Ray, hit: glam::Vec3) -> Hit {
+        let triangle = &self.triangles[idx];
+        let (primitive, material) = self.instances[triangle.instance as usize];
+        Hit {
+            distance: hit.x,
+            position: ray.origin + ray.direction * hit.x,
+            instance: triangle.instance as usize,
+            primitive,
+            triangle: triangle.triangle as usize,
+            barycentrics: glam::Vec2::new(hit.y, hit.z),
+            material,
+        }
+    }
+
+    // binned surface area heuristic
+    fn subdivide(&mut self, idx: usize, start: usize, end: usize) {
+        let triangles = &mut self.triangles[start..end];
+        let bounding_box = triangles
+            .iter()
+            .fold(BoundingBox::default(), |bbox, triangle| {
+                bbox.union(triangle.bounding_box())
+            });
+        let centroids = triangles
+            .iter()
+            .fold(BoundingBox::default(), |bbox, triangle| {
+                let centroid = triangle.centroid();
+                BoundingBox::new(bbox.min.min(centroid), bbox.max.max(centroid))
+            });
+        self.nodes[idx] = Node {
+            bounding_box,
+            start: start as u32,
+            count: triangles.len() as u32,
+        };
+
+        let leaf_cost = triangles.len() as f32 * bounding_box.half_area();
+        let Some((axis, split, split_cost)) = best_split(triangles, &centroids) else {
+            return;
+        };
+        let split_cost = conf::TRAVERSAL_COST.mul_add(bounding_box.half_area(), split_cost);
+        if triangles.len() <= conf::MAX_LEAF_SIZE && split_cost >= leaf_cost {
+            return;
+        }
+
+        let mut middle = 0;
+        for current in 0..triangles.len() {
+            if bin_index(triangles[current].centroid(), &centroids, axis) < split {
+                triangles.swap(middle, current);
+                middle += 1;
+            }
+        }
+        let middle = start + middle;
+
+        let first = self.nodes.len();
+        self.nodes.push(Node::default());
+        self.subdivide(first, start, middle);
+        let second = self.nodes.len();
+        self.nodes.push(Node::default());
+        self.subdivide(second, middle, end);
+
+        self.nodes[idx].start = second as u32;
+        self.nodes[idx].count = 0;
+    }
+}
+
+// returns the axis, the first bin of the second child and the cost of the split
+fn best_split(triangles: &[Triangle], centroids: &BoundingBox) -> Option<(usize, usize, f32)> {
+    let mut best: Option<(usize, usize, f32)> = None;
+    for axis in 0..3 {
+        if centroids.size()[axis] <= 0. {
+            continue;
+        }
+
+        let mut bins = [Bin::default(); conf::BINS];
+        for triangle in triangles {
+            let bin = &mut bins[bin_index(triangle.centroid(), centroids, axis)];
+            bin.bounding_box = bin.bounding_box.union(triangle.bounding_box());
+            bin.count += 1;
+        }
+
+        // costs of the second children, from the right
+        let mut right_costs = [0.; conf::BINS];
+        let mut right = Bin::default();
+        for split in (1..conf::BINS).rev() {
+            right.bounding_box = right.bounding_box.union(bins[split].bounding_box);
+            right.count += bins[split].count;
+            right_costs[split] = right.count as f32 * right.bounding_box.half_area();
+        }
+
+        let mut left = Bin::default();
+        for split in 1..conf::BINS {
+            left.bounding_box = left.bounding_box.union(bins[split - 1].bounding_box);
+            left.count += bins[split - 1].count;
+            if left.count == 0 || left.count == triangles.len() {
+                continue;
+            }
+            let cost =
+                (left.count as f32).mul_add(left.bounding_box.half_area(), right_costs[split]);
+            if best.is_none_or(|(_, _, best_cost)| cost < best_cost) {
+                best = Some((axis, split, cost));
+            }
+        }
+    }
+    best
+}
+
+fn bin_index(centroid: glam::Vec3, centroids: &BoundingBox, axis: usize) -> usize {
+    let relative = (centroid[axis] - centroids.min[axis]) / centroids.size()[axis];
+    // the centroid is within the bounds, so only rounding errors could make it negative
+    usize::try_from((relative * conf::BINS as f32) as i32).map_or(0, |bin| bin.min(conf::BINS - 1))
+}
+
+// Möller-Trumbore, returns the distance and the barycentrics of the second and third vertices
+fn intersect(triangle: &Triangle, ray: &Ray, max_distance: f32) -> Option<glam::Vec3> {
+    let [v0, v1, v2] = triangle.vertices;
+    let edge1 = v1 - v0;
+    let edge2 = v2 - v0;
+    let pvec = ray.direction.cross(edge2);
+    let determinant = edge1.dot(pvec);
+    // parallel or degenerate, both faces are hit
+    if determinant.abs() < f32::EPSILON * edge1.length() * edge2.length() {
+        return None;
+    }
+    let inv_determinant = determinant.recip();
+
+    let tvec = ray.origin - v0;
+    let u = tvec.dot(pvec) * inv_determinant;
+    if !(0. ..=1.).contains(&u) {
+        return None;
+    }
+    let qvec = tvec.cross(edge1);
+    let v = ray.direction.dot(qvec) * inv_determinant;
+    if v < 0. || u + v > 1. {
+        return None;
+    }
+    let distance = edge2.dot(qvec) * inv_determinant;
+    (distance > 0. && distance < max_distance).then_some(glam::Vec3::new(distance, u, v))
+}
+
+impl Ray {
+    pub const fn new(origin: glam::Vec3, direction: glam::Vec3) -> Self {
+        Self {
+            origin,
+            direction,
+            max_distance: f32::INFINITY,
+        }
+    }
+}
+
+impl Node {
+    const fn is_leaf(&self) -> bool {
+        self.count > 0
+    }
+
+    const fn range(&self) -> std::ops::Range<usize> {
+        self.start as usize..(self.start + self.count) as usize
+    }
+}
+
+impl Triangle {
+    fn bounding_box(&self) -> BoundingBox {
+        let [a, b, c] = self.vertices;
+        BoundingBox::new(a.min(b).min(c), a.max(b).max(c))
+    }
+
+    fn centroid(&self) -> glam::Vec3 {
+        (self.vertices[0] + self.vertices[1] + self.vertices[2]) / 3.
+    }
+
+    // separating axis test, against the box and triangle normals and their edges cross products
+    fn overlaps(&self, bounding_box: &BoundingBox) -> bool {
+        let center = bounding_box.center();
+        let half_size = bounding_box.size() / 2.;
+        let vertices = self.vertices.map(|vertex| vertex - center);
+        let edges = [
+            vertices[1] - vertices[0],
+            vertices[2] - vertices[1],
+            vertices[0] - vertices[2],
+        ];
+
+        let box_axes = [glam::Vec3::X, glam::Vec3::Y, glam::Vec3::Z];
+        let edge_axes = box_axes
+            .iter()
+            .flat_map(|axis| edges.map(|edge| axis.cross(edge)));
+        box_axes
+            .into_iter()
+            .chain([edges[0].cross(edges[1])])
+            .chain(edge_axes)
+            .all(|axis| {
+                let projections = vertices.map(|vertex| vertex.dot(axis));
+                let radius = half_size.dot(axis.abs());
+                let min = projections[0].min(projections[1]).min(projections[2]);
+                let max = projections[0].max(projections[1]).max(projections[2]);
+                min <= radius && max >= -radius
+            })
+    }
+}
+
+impl BoundingBox {
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.min.cmple(other.max).all() && other.min.cmple(self.max).all()
+    }
+
+    // distance to the entry point of the ray, or to its origin when inside
+    pub fn intersect(
+        &self,
+        origin: glam::Vec3,
+        inv_direction: glam::Vec3,
+        max_distance: f32,
+    ) -> Option<f32> {
+        let t0 = (self.min - origin) * inv_direction;
+        let t1 = (self.max - origin) * inv_direction;
+        let near = t0.min(t1).max_element().max(0.);
+        let far = t0.max(t1).min_element().min(max_distance);
+        (near <= far).then_some(near)
+    }
+
+    fn half_area(&self) -> f32 {
+        if self.is_empty() {
+            return 0.;
+        }
+        let size = self.size();
+        size.x
+            .mul_add(size.y, size.y.mul_add(size.z, size.z * size.x))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::rng::Rng;
+
+    use super::*;
+    use crate::generate;
+
+    const RAYS: u32 = 1000;
+    const BOXES: u32 = 200;
+
+    fn scenes() -> Vec<(&'static str, Bvh)> {
+        ["cornell", "materials", "sphere"]
+            .into_iter()
+            .map(|name| (name, Bvh::new(&generate::generate(name).unwrap())))
+            .collect()
+    }
+
+    fn point(rng: &mut Rng, bounding_box: &BoundingBox) -> glam::Vec3 {
+        let r = glam::Vec3::new(rng.float(), rng.float(), rng.float());
+        bounding_box.min + r * bounding_box.size()
+    }
+
+    fn direction(rng: &mut Rng) -> glam::Vec3 {
+        let r = rng.vec2();
+        let z = 2.0f32.mul_add(r.x, -1.);
+        let (sin, cos) = (r.y * std::f32::consts::TAU).sin_cos();
+        glam::Vec3::new(cos, sin, 0.) * z.mul_add(-z, 1.).sqrt() + glam::Vec3::Z * z
+    }
+
+    // random rays from around the scene, and axis aligned ones from points on its box
+    fn rays(bvh: &Bvh, seed: u32) -> Vec<Ray> {
+        let mut rng = Rng::new(glam::UVec2::new(seed, 0), 0);
+        let bounding_box = bvh.bounding_box();
+        let around = BoundingBox::new(
+            bounding_box.min - bounding_box.size() * 0.5,
+            bounding_box.max + bounding_box.size() * 0.5,
+        );
+        let axes = [
+            glam::Vec3::X,
+            glam::Vec3::NEG_X,
+            glam::Vec3::Y,
+            glam::Vec3::NEG_Y,
+            glam::Vec3::Z,
+            glam::Vec3::NEG_Z,
+        ];
+        (0..RAYS)
+            .map(|idx| {
+                if idx % 2 == 0 {
+                    Ray::new(point(&mut rng, &around), direction(&mut rng))
+                } else {
+                    // on the planes of the walls and boxes half of the time
+                    let mut origin = point(&mut rng, &bounding_box);
+                    let plane = idx as usize / 2 % 3;
+                    if idx % 4 == 1 {
+                        origin[plane] = bounding_box.min[plane];
+                    }
+                    Ray::new(origin, axes[idx as usize % 6])
+                }
+            })
+            .collect()
+    }
+
+    fn brute_force_hits(bvh: &Bvh, ray: &Ray) -> Vec<(usize, glam::Vec3)> {
+        bvh.triangles
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, triangle)| Some((idx, intersect(triangle, ray, ray.max_distance)?)))
+            .collect()
+    }
+
+    fn brute_force_closest(bvh: &Bvh, ray: &Ray) -> Option<f32> {
+        brute_force_hits(bvh, ray)
+            .into_iter()
+            .map(|(_, hit)| hit.x)
+            .min_by(f32::total_cmp)
+    }
+
+    #[allow(clippy::float_cmp)]
+    fn is_hit(bvh: &Bvh, ray: &Ray, hit: &Hit) -> bool {
+        brute_force_hits(bvh, ray).into_iter().any(|(idx, found)| {
+            let triangle = &bvh.triangles[idx];
+            triangle.instance as usize == hit.instance
+                && triangle.triangle as usize == hit.triangle
+                && found.x == hit.distance
+        })
+    }
+
+    #[test]
+    fn closest_hit_matches_brute_force() {
+        for (seed, (name, bvh)) in scenes().into_iter().enumerate() {
+            for ray in rays(&bvh, seed as u32) {
+                let hit = bvh.closest_hit(&ray);
+                let expected = brute_force_closest(&bvh, &ray);
+                assert_eq!(
+                    hit.map(|hit| hit.distance),
+                    expected,
+                    "{name}, ray from {} towards {}",
+                    ray.origin,
+                    ray.direction
+                );
+                if let Some(hit) = hit {
+                    assert!(is_hit(&bvh, &ray, &hit), "{name}, {hit:?} isn't a hit");
+                    assert!(hit
+                        .position
+                        .abs_diff_eq(ray.origin + ray.direction * hit.distance, 1e-5));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn any_hit_matches_brute_force() {
+        for (seed, (name, bvh)) in scenes().into_iter().enumerate() {
+            for mut ray in rays(&bvh, seed as u32) {
+                // short rays miss more often
+                ray.max_distance = bvh.bounding_box().size().length() * 0.25;
+                let hit = bvh.any_hit(&ray);
+                assert_eq!(
+                    hit.is_some(),
+                    brute_force_closest(&bvh, &ray).is_some(),
+                    "{name}, ray from {} towards {}",
+                    ray.origin,
+                    ray.direction
+                );
+                if let Some(hit) = hit {
+                    assert!(hit.distance < ray.max_distance);
+                    assert!(is_hit(&bvh, &ray, &hit), "{name}, {hit:?} isn't a hit");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn overlapping_matches_brute_force() {
+        for (seed, (name, bvh)) in scenes().into_iter().enumerate() {
+            let mut rng = Rng::new(glam::UVec2::new(seed as u32, 1), 0);
+            let bounding_box = bvh.bounding_box();
+            for _ in 0..BOXES {
+                let center = point(&mut rng, &bounding_box);
+                let half_size = bounding_box.size() * 0.2 * rng.float();
+                let query = BoundingBox::new(center - half_size, center + half_size);
+
+                let mut overlaps = bvh
+                    .overlapping(&query)
+                    .into_iter()
+                    .map(|overlap| (overlap.instance, overlap.triangle))
+                    .collect::<Vec<_>>();
+                overlaps.sort_unstable();
+                let mut expected = bvh
+                    .triangles
+                    .iter()
+                    .filter(|triangle| triangle.overlaps(&query))
+                    .map(|triangle| (triangle.instance as usize, triangle.triangle as usize))
+                    .collect::<Vec<_>>();
+                expected.sort_unstable();
+                assert_eq!(
+                    overlaps, expected,
+                    "{name}, box {} {}",
+                    query.min, query.max
+                );
+
+                // the separating axes are stricter than the bounding boxes
+                assert!(bvh
+                    .triangles
+                    .iter()
+                    .filter(|triangle| triangle.overlaps(&query))
+                    .all(|triangle| triangle.bounding_box().overlaps(&query)));
+            }
+
+            assert_eq!(bvh.overlapping(&bounding_box).len(), bvh.triangles.len());
+        }
+    }
+
+    #[test]
+    fn rays_along_axes() {
+        let bvh = Bvh::new(&generate::cornell_box());
+
+        // from the middle of the floor, the light is above
+        let hit = bvh
+            .closest_hit(&Ray::new(glam::Vec3::ZERO, glam::Vec3::Y))
+            .unwrap();
+        assert!((hit.distance - 1.999).abs() < 1e-5, "{hit:?}");
+        assert_eq!(hit.instance, 5);
+
+        // from the floor beside the light, towards the ceiling
+        let hit = bvh
+            .closest_hit(&Ray::new(glam::Vec3::new(0., 0., 0.8), glam::Vec3::Y))
+            .unwrap();
+        assert!((hit.distance - 2.).abs() < 1e-5, "{hit:?}");
+        assert_eq!(hit.instance, 1);
+
+        // rotated walls aren't exactly where the rays start, callers move them by the minimum
+        // distance like the path tracers do
+        let start = |origin: glam::Vec3, direction: glam::Vec3| {
+            Ray::new(origin + direction * shared::conf::T_MIN, direction)
+        };
+
+        // from the right wall across the box to the left one
+        let hit = bvh
+            .closest_hit(&start(glam::Vec3::new(1., 1., 0.9), glam::Vec3::NEG_X))
+            .unwrap();
+        assert!((hit.distance - 2.).abs() < 1e-3, "{hit:?}");
+        assert_eq!(hit.instance, 3);
+
+        // out of the box through the walls the rays start on
+        for (origin, direction) in [
+            (glam::Vec3::new(0., 0., 0.8), glam::Vec3::NEG_Y),
+            (glam::Vec3::new(1., 1., 0.9), glam::Vec3::X),
+        ] {
+            assert!(bvh.closest_hit(&start(origin, direction)).is_none());
+            assert!(bvh.any_hit(&start(origin, direction)).is_none());
+        }
+
+        // open towards +z
+        let ray = Ray::new(glam::Vec3::new(0.5, 1.5, 0.5), glam::Vec3::Z);
+        assert!(bvh.closest_hit(&ray).is_none());
+
+        let ray = Ray {
+            max_distance: 1.5,
+            ..Ray::new(glam::Vec3::new(0., 1.5, 0.5), glam::Vec3::NEG_Z)
+        };
+        assert!(bvh.any_hit(&ray).is_none());
+        let ray = Ray {
+            max_distance: 1.6,
+            ..ray
+        };
+        assert_eq!(bvh.any_hit(&ray).unwrap().instance, 2);
+    }
+}
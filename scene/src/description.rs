@@ -0,0 +1,533 @@
+use std::path::{Path, PathBuf};
+
+use rayon::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    io,
+    io::{FileLoader, Result},
+    options::ImportOptions,
+    Camera, Environment, Material, RenderSettings, Scene,
+};
+
+pub struct Description;
+
+// scene description file, e.g.
+//
+// [environment]
+// color = [1.0, 1.0, 1.0]
+//
+// [[cameras]]
+// position = [0.0, 1.0, 5.0]
+// target = [0.0, 1.0, 0.0]
+//
+// [[sources]]
+// file = "models/chair.gltf"
+// import = { up_axis = "z", units = "cm" }
+// transform = { translation = [1.0, 0.0, 0.0], rotation = [0.0, 90.0, 0.0], scale = 0.5 }
+// materials = [{ index = 0, color = [0.8, 0.1, 0.1] }]
+// nodes = [{ name = "Cushion", transform = { translation = [0.0, 0.1, 0.0] } }]
+// hide = ["Blanket"]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct File {
+    // defaults to the environment of the first nested description among the sources
+    environment: Option<Environment>,
+    #[serde(default)]
+    cameras: Vec<Camera>,
+    #[serde(default)]
+    render: RenderSettings,
+    #[serde(default)]
+    sources: Vec<Source>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Source {
+    file: PathBuf,
+    #[serde(default)]
+    import: ImportOptions,
+    #[serde(default)]
+    transform: Transform,
+    #[serde(default)]
+    materials: Vec<MaterialOverride>,
+    #[serde(default)]
+    nodes: Vec<NodeOverride>,
+    #[serde(default)]
+    hide: Vec<String>,
+    #[serde(default = "Source::default_visible")]
+    visible: bool,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Transform {
+    #[serde(default)]
+    translation: glam::Vec3,
+    // euler angles in degrees, applied in XYZ order
+    #[serde(default)]
+    rotation: glam::Vec3,
+    #[serde(default)]
+    scale: Scale,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Scale {
+    Uniform(f32),
+    NonUniform(glam::Vec3),
+}
+
+// replaces the local transform of every node of the source with the given name
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NodeOverride {
+    name: String,
+    transform: Transform,
+}
+
+// applies to every material of the source when `index` is omitted
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MaterialOverride {
+    index: Option<usize>,
+    color: Option<glam::Vec3>,
+    emittance: Option<glam::Vec3>,
+    metallic: Option<f32>,
+    roughness: Option<f32>,
+}
+
+impl FileLoader for Description {
+    const SUPPORTED_EXTENSIONS: &'static [&'static str] = &["toml"];
+
+    fn load(filename: impl AsRef<Path>, options: &ImportOptions) -> Result<Scene> {
+        firestorm::profile_method!(load);
+
+        if options.scene.is_some() {
+            return Err(
+                "Scene selection only applies to gltf files, use the import options of \
+                        the sources"
+                    .into(),
+            );
+        }
+
+        let filename = filename.as_ref();
+        let filedir = filename.parent().unwrap_or_else(|| Path::new("./"));
+
+        File::check_cycles(filename, &mut Vec::new())?;
+        let description = File::read(filename)?;
+
+        let mut scene = Scene::default();
+
+        // imported in parallel, then merged in order
+        let sources = description
+            .sources
+            .into_par_iter()
+            .filter(|source| source.visible)
+            .map(|source| {
+                let file = filedir.join(&source.file);
+                let mut source_scene = io::import_with_options(&file, &source.import)?;
+
+                for material_override in &source.materials {
+                    material_override.apply(&mut source_scene.data.materials)?;
+                }
+                for node_override in &source.nodes {
+                    let count = source_scene
+                        .set_transform(&node_override.name, node_override.transform.matrix());
+                    if count == 0 {
+                        return Err(format!("No node named {:?} found", node_override.name).into());
+                    }
+                }
+                for name in &source.hide {
+                    if source_scene.hide(name) == 0 {
+                        return Err(format!("No node named {name:?} found").into());
+                    }
+                }
+
+                // only descriptions have an environment, gltf files always get the default one
+                let environment =
+                    Self::can_load(&file).then_some((file, source_scene.info.environment));
+                Ok((source_scene, source.transform.matrix(), environment))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut environment = description.environment;
+        for (source_scene, transform, source_environment) in sources {
+            scene.merge(source_scene, transform);
+
+            if let Some((file, source_environment)) = source_environment {
+                match environment {
+                    None => environment = Some(source_environment),
+                    Some(environment) if environment.color != source_environment.color => {
+                        scene.info.import_warnings.push(format!(
+                            "the environment of {} is discarded in {}",
+                            file.display(),
+                            filename.display()
+                        ));
+                    }
+                    Some(_) => (),
+                }
+            }
+        }
+
+        // cameras listed in the description take precedence over those of the sources
+        scene.info.cameras.splice(0..0, description.cameras);
+        scene.info.environment = environment.unwrap_or_default();
+        scene.info.render_settings = description.render;
+
+        Ok(scene)
+    }
+
+    fn dependencies(filename: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+        let filename = filename.as_ref();
+        let filedir = filename.parent().unwrap_or_else(|| Path::new("./"));
+
+        File::check_cycles(filename, &mut Vec::new())?;
+        let description = File::read(filename)?;
+        let mut dependencies = vec![filename.to_owned()];
+        for source in description.sources.iter().filter(|source| source.visible) {
+            dependencies.extend(io::dependencies(filedir.join(&source.file))?);
+        }
+        Ok(dependencies)
+    }
+}
+
+impl File {
+    fn read(filename: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(filename)
+            .map_err(|error| format!("Couldn't open scene description file: {error}"))?;
+        toml::from_str(&contents)
+            .map_err(|error| format!("Couldn't parse scene description: {error}").into())
+    }
+
+    // `visiting` holds the descriptions being read, which include the given one through
+    // their sources, so finding the given one among them means it includes itself
+    fn check_cycles(filename: &Path, visiting: &mut Vec<PathBuf>) -> Result<()> {
+        let path = filename
+            .canonicalize()
+            .map_err(|error| format!("Couldn't open scene description file: {error}"))?;
+        if let Some(start) = visiting.iter().position(|visited| *visited == path) {
+            let cycle = visiting[start..]
+                .iter()
+                .chain([&path])
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>();
+            return Err(format!(
+                "Scene descriptions include each other: {}",
+                cycle.join(" -> ")
+            )
+            .into());
+        }
+
+        let filedir = filename.parent().unwrap_or_else(|| Path::new("./"));
+        visiting.push(path);
+        for source in Self::read(filename)?.sources {
+            let file = filedir.join(&source.file);
+            if Description::can_load(&file) {
+                Self::check_cycles(&file, visiting)?;
+            }
+        }
+        visiting.pop();
+        Ok(())
+    }
+}
+
+impl Source {
+    const fn default_visible() -> bool {
+        true
+    }
+}
+
+impl Transform {
+    fn matrix(&self) -> glam::Mat4 {
+        let rotation = glam::Quat::from_euler(
+            glam::EulerRot::XYZ,
+            self.rotation.x.to_radians(),
+            self.rotation.y.to_radians(),
+            self.rotation.z.to_radians(),
+        );
+        glam::Mat4::from_scale_rotation_translation(self.scale.vector(), rotation, self.translation)
+    }
+}
+
+impl Scale {
+    const fn vector(&self) -> glam::Vec3 {
+        match *self {
+            Self::Uniform(scale) => glam::Vec3::splat(scale),
+            Self::NonUniform(scale) => scale,
+        }
+    }
+}
+
+impl Default for Scale {
+    fn default() -> Self {
+        Self::Uniform(1.)
+    }
+}
+
+impl MaterialOverride {
+    fn apply(&self, materials: &mut [Material]) -> Result<()> {
+        let materials = match self.index {
+            Some(index) => {
+                let count = materials.len();
+                std::slice::from_mut(materials.get_mut(index).ok_or_else(|| {
+                    format!("Material override index {index} out of range ({count} materials)")
+                })?)
+            }
+            None => materials,
+        };
+
+        for material in materials {
+            if let Some(color) = self.color {
+                material.color = color;
+            }
+            if let Some(emittance) = self.emittance {
+                material.emittance = emittance;
+            }
+            if let Some(metallic) = self.metallic {
+                material.metallic = metallic;
+            }
+            if let Some(roughness) = self.roughness {
+                material.roughness = roughness;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate, testing::Fixture, validate::Severity};
+
+    // the given files next to gltf ones exported from generated scenes
+    fn inputs(name: &str, files: &[(&str, &str)]) -> Fixture {
+        let fixture = Fixture::new(&format!("description-{name}"));
+        fixture.export("cornell.gltf", &generate::cornell_box());
+        fixture.export("cube.gltf", &generate::cube());
+        for (file, contents) in files {
+            fixture.write(file, contents);
+        }
+        fixture
+    }
+
+    fn load(fixture: &Fixture, file: &str) -> Result<Scene> {
+        Description::load(fixture.path(file), &ImportOptions::default())
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn sources_are_overridden() {
+        let fixture = inputs(
+            "overrides",
+            &[(
+                "scene.toml",
+                r#"
+                [[sources]]
+                file = "cornell.gltf"
+                materials = [{ index = 1, color = [0.0, 0.0, 1.0] }]
+                nodes = [{ name = "Light", transform = { translation = [0.0, 1.5, 0.0] } }]
+                hide = ["Tall block"]
+
+                [[sources]]
+                file = "cornell.gltf"
+                visible = false
+
+                [[sources]]
+                file = "cube.gltf"
+                transform = { translation = [5.0, 0.0, 0.0], scale = 2.0 }
+                materials = [{ color = [1.0, 0.0, 0.0] }]
+                "#,
+            )],
+        );
+        let scene = load(&fixture, "scene.toml").unwrap();
+        let cornell = generate::cornell_box();
+        let cube = generate::cube();
+
+        // the invisible source isn't merged
+        assert_eq!(
+            scene.info.nodes.len(),
+            cornell.info.nodes.len() + cube.info.nodes.len()
+        );
+        assert_eq!(
+            scene.data.materials.len(),
+            cornell.data.materials.len() + cube.data.materials.len()
+        );
+
+        assert_eq!(
+            scene.data.materials[0].color,
+            cornell.data.materials[0].color
+        );
+        assert_eq!(scene.data.materials[1].color, glam::Vec3::Z);
+        for material in &scene.data.materials[cornell.data.materials.len()..] {
+            assert_eq!(material.color, glam::Vec3::X);
+        }
+
+        let instances_of = |name: &str| {
+            let node = scene.find_nodes(name).next().unwrap();
+            scene
+                .info
+                .instances
+                .iter()
+                .filter(move |instance| instance.node == Some(node))
+        };
+        assert_eq!(instances_of("Tall block").count(), 0);
+        assert_eq!(
+            scene.info.instances.len(),
+            cornell.info.instances.len() - 1 + cube.info.instances.len()
+        );
+        let light = instances_of("Light").next().unwrap();
+        assert!(light
+            .transform
+            .abs_diff_eq(glam::Mat4::from_translation(glam::Vec3::Y * 1.5), 1e-5));
+
+        // the cube spans [-0.5, 0.5] before being scaled and moved
+        let bounding_box = scene.info.bounding_box;
+        assert!(bounding_box
+            .min
+            .abs_diff_eq(glam::Vec3::new(-1., -1., -1.), 1e-5));
+        assert!(bounding_box
+            .max
+            .abs_diff_eq(glam::Vec3::new(6., 2., 1.), 1e-5));
+    }
+
+    #[test]
+    fn environment_of_nested_descriptions() {
+        let fixture = inputs(
+            "environment",
+            &[
+                (
+                    "nested.toml",
+                    r#"
+                    environment = { color = [0.5, 0.5, 0.5] }
+                    [[sources]]
+                    file = "cube.gltf"
+                    "#,
+                ),
+                (
+                    "inherits.toml",
+                    r#"
+                    [[sources]]
+                    file = "cornell.gltf"
+                    [[sources]]
+                    file = "nested.toml"
+                    "#,
+                ),
+                (
+                    "overrides.toml",
+                    r#"
+                    environment = { color = [0.0, 0.0, 0.0] }
+                    [[sources]]
+                    file = "nested.toml"
+                    "#,
+                ),
+            ],
+        );
+        assert_eq!(
+            load(&fixture, "inherits.toml")
+                .unwrap()
+                .info
+                .environment
+                .color,
+            glam::Vec3::splat(0.5)
+        );
+        assert_eq!(
+            load(&fixture, "overrides.toml")
+                .unwrap()
+                .info
+                .environment
+                .color,
+            glam::Vec3::ZERO
+        );
+    }
+
+    // the conflict is reported by validation rather than printed while loading
+    #[test]
+    fn discarded_environments_are_reported() {
+        let fixture = inputs(
+            "discarded-environment",
+            &[
+                (
+                    "nested.toml",
+                    r#"
+                    environment = { color = [0.5, 0.5, 0.5] }
+                    [[sources]]
+                    file = "cube.gltf"
+                    "#,
+                ),
+                (
+                    "overrides.toml",
+                    r#"
+                    environment = { color = [0.0, 0.0, 0.0] }
+                    [[sources]]
+                    file = "nested.toml"
+                    "#,
+                ),
+            ],
+        );
+        let report = load(&fixture, "overrides.toml").unwrap().validate();
+        let warnings = report
+            .diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Warning)
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            warnings,
+            [format!(
+                "the environment of {} is discarded in {}",
+                fixture.path("nested.toml").display(),
+                fixture.path("overrides.toml").display()
+            )]
+        );
+        assert!(load(&fixture, "nested.toml")
+            .unwrap()
+            .info
+            .import_warnings
+            .is_empty());
+    }
+
+    #[test]
+    fn cycles_are_rejected() {
+        let fixture = inputs(
+            "cycles",
+            &[
+                (
+                    "a.toml",
+                    r#"
+                    [[sources]]
+                    file = "cube.gltf"
+                    [[sources]]
+                    file = "b.toml"
+                    "#,
+                ),
+                (
+                    "b.toml",
+                    r#"
+                    [[sources]]
+                    file = "a.toml"
+                    "#,
+                ),
+                (
+                    "self.toml",
+                    r#"
+                    [[sources]]
+                    file = "./self.toml"
+                    "#,
+                ),
+            ],
+        );
+        for file in ["a.toml", "self.toml"] {
+            let message = load(&fixture, file)
+                .err()
+                .unwrap_or_else(|| panic!("{file} was loaded"))
+                .to_string();
+            assert!(
+                message.starts_with("Scene descriptions include each other"),
+                "{file} failed with {message:?}"
+            );
+        }
+        assert!(Description::dependencies(fixture.path("b.toml")).is_err());
+    }
+}
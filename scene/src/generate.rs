@@ -0,0 +1,465 @@
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+use crate::{
+    BoundingBox, Camera, Instance, Material, Node, PrimitiveInfo, PrimitiveSize, Scene, Vertex,
+};
+
+const SPHERE_SEGMENTS: u32 = 64;
+const SPHERE_RINGS: u32 = 32;
+
+// names accepted by `generate`, e.g. by `preprocess --generate cornell`
+pub const GENERATORS: &[&str] = &["sphere", "box", "plane", "cornell", "materials", "furnace"];
+
+pub fn generate(name: &str) -> Option<Scene> {
+    firestorm::profile_fn!(generate);
+
+    let scene = match name {
+        "sphere" => sphere(),
+        "box" => cube(),
+        "plane" => plane(),
+        "cornell" => cornell_box(),
+        "materials" => material_grid(5, 5),
+        "furnace" => white_furnace(),
+        _ => return None,
+    };
+    Some(scene)
+}
+
+// indexed triangles, counter-clockwise when seen from the outside
+pub struct Mesh {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+}
+
+// assembles a scene one mesh at a time, each one becoming a root node with a single instance
+#[derive(Default)]
+pub struct Builder {
+    scene: Scene,
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn material(&mut self, material: Material) -> u32 {
+        self.scene.data.materials.push(material);
+        self.scene.data.materials.len() as u32 - 1
+    }
+
+    // returns the index of the new node
+    pub fn add(&mut self, name: &str, mesh: &Mesh, material: u32, transform: glam::Mat4) -> usize {
+        let scene = &mut self.scene;
+
+        let primitive_index = scene.info.primitive_infos.len();
+        scene.info.primitive_infos.push(PrimitiveInfo {
+            indices_offset: scene.data.indices.len() as u32,
+            vertices_offset: scene.data.vertices.len() as u32,
+            material,
+            colors_offset: PrimitiveInfo::no_colors(),
+        });
+        scene.info.primitive_sizes.push(PrimitiveSize {
+            indices_size: mesh.indices.len() as u32,
+            vertices_size: mesh.vertices.len() as u32,
+        });
+        scene.data.indices.extend(&mesh.indices);
+        scene.data.vertices.extend(&mesh.vertices);
+
+        let node = scene.info.nodes.len();
+        scene.info.nodes.push(Node {
+            name: name.to_owned(),
+            transform,
+            parent: None,
+            primitives: primitive_index..primitive_index + 1,
+        });
+        scene.info.instances.push(Instance {
+            primitive_index,
+            transform,
+            node: Some(node),
+            local_transform: glam::Mat4::IDENTITY,
+        });
+
+        scene.info.bounding_box = scene
+            .info
+            .bounding_box
+            .union(mesh.bounding_box().transform(transform));
+        node
+    }
+
+    pub fn camera(&mut self, name: &str, position: glam::Vec3, target: glam::Vec3, fov: f32) {
+        self.scene.info.cameras.push(Camera {
+            name: name.to_owned(),
+            position,
+            target,
+            fov,
+        });
+    }
+
+    pub const fn environment(&mut self, color: glam::Vec3) {
+        self.scene.info.environment.color = color;
+    }
+
+    pub fn build(self) -> Scene {
+        self.scene
+    }
+}
+
+impl Mesh {
+    // uv sphere centered on the origin
+    pub fn sphere(radius: f32, segments: u32, rings: u32) -> Self {
+        assert!(segments >= 3 && rings >= 2, "Sphere is too coarse");
+
+        let mut vertices = Vec::new();
+        for ring in 0..=rings {
+            let v = ring as f32 / rings as f32;
+            let (sin_theta, cos_theta) = (v * PI).sin_cos();
+            for segment in 0..=segments {
+                let u = segment as f32 / segments as f32;
+                let (sin_phi, cos_phi) = (u * TAU).sin_cos();
+                let normal = glam::Vec3::new(sin_theta * cos_phi, cos_theta, -sin_theta * sin_phi);
+                vertices.push(vertex(normal * radius, normal, glam::Vec2::new(u, v)));
+            }
+        }
+
+        let mut indices = Vec::new();
+        let row = segments + 1;
+        for ring in 0..rings {
+            for segment in 0..segments {
+                let top_left = ring * row + segment;
+                let bottom_left = top_left + row;
+                // the triangles touching the poles would be degenerate
+                if ring != 0 {
+                    indices.extend([top_left, bottom_left, top_left + 1]);
+                }
+                if ring != rings - 1 {
+                    indices.extend([top_left + 1, bottom_left, bottom_left + 1]);
+                }
+            }
+        }
+
+        Self { vertices, indices }
+    }
+
+    // axis aligned box centered on the origin, with flat faces
+    pub fn cube(size: glam::Vec3) -> Self {
+        let mut mesh = Self {
+            vertices: Vec::new(),
+            indices: Vec::new(),
+        };
+        for axis in 0..3 {
+            for sign in [1., -1.] {
+                let mut normal = glam::Vec3::ZERO;
+                normal[axis] = sign;
+                let mut tangent = glam::Vec3::ZERO;
+                tangent[(axis + 1) % 3] = 1.;
+                let bitangent = normal.cross(tangent);
+                mesh.push_quad(normal, tangent, bitangent, size / 2.);
+            }
+        }
+        mesh
+    }
+
+    // rectangle in the xz plane centered on the origin, facing +y
+    pub fn plane(size: glam::Vec2) -> Self {
+        let mut mesh = Self {
+            vertices: Vec::new(),
+            indices: Vec::new(),
+        };
+        mesh.push_quad(
+            glam::Vec3::Y,
+            glam::Vec3::X,
+            glam::Vec3::NEG_Z,
+            glam::Vec3::new(size.x, 0., size.y) / 2.,
+        );
+        mesh
+    }
+
+    pub fn bounding_box(&self) -> BoundingBox {
+        self.vertices
+            .iter()
+            .fold(BoundingBox::default(), |bbox, vertex| {
+                let position = vertex.position.truncate();
+                BoundingBox::new(bbox.min.min(position), bbox.max.max(position))
+            })
+    }
+
+    // face of a box with the given half extents, `tangent` and `bitangent` spanning it
+    // counter-clockwise around `normal`
+    fn push_quad(
+        &mut self,
+        normal: glam::Vec3,
+        tangent: glam::Vec3,
+        bitangent: glam::Vec3,
+        half_size: glam::Vec3,
+    ) {
+        let start = self.vertices.len() as u32;
+        for (x, y) in [(-1., -1.), (1., -1.), (1., 1.), (-1., 1.)] {
+            let position = (normal + tangent * x + bitangent * y) * half_size;
+            let tex_coords = glam::Vec2::new(x + 1., 1. - y) / 2.;
+            self.vertices.push(vertex(position, normal, tex_coords));
+        }
+        self.indices
+            .extend([0, 1, 2, 0, 2, 3].map(|index| start + index));
+    }
+}
+
+pub const fn diffuse(color: glam::Vec3) -> Material {
+    Material {
+        color,
+        color_texture: -1,
+        emittance: glam::Vec3::ZERO,
+        emittance_texture: -1,
+        metallic: 0.,
+        roughness: 1.,
+        metallic_roughness_texture: -1,
+    }
+}
+
+pub const fn emissive(emittance: glam::Vec3) -> Material {
+    Material {
+        emittance,
+        ..diffuse(glam::Vec3::ZERO)
+    }
+}
+
+pub fn sphere() -> Scene {
+    let mut builder = Builder::new();
+    let material = builder.material(diffuse(glam::Vec3::splat(0.8)));
+    builder.add(
+        "Sphere",
+        &Mesh::sphere(1., SPHERE_SEGMENTS, SPHERE_RINGS),
+        material,
+        glam::Mat4::IDENTITY,
+    );
+    builder.camera("Front", glam::Vec3::new(0., 0., 4.), glam::Vec3::ZERO, 45.);
+    builder.build()
+}
+
+pub fn cube() -> Scene {
+    let mut builder = Builder::new();
+    let material = builder.material(diffuse(glam::Vec3::splat(0.8)));
+    builder.add(
+        "Box",
+        &Mesh::cube(glam::Vec3::ONE),
+        material,
+        glam::Mat4::IDENTITY,
+    );
+    builder.camera(
+        "Front",
+        glam::Vec3::new(1.5, 1.5, 3.),
+        glam::Vec3::ZERO,
+        45.,
+    );
+    builder.build()
+}
+
+pub fn plane() -> Scene {
+    let mut builder = Builder::new();
+    let material = builder.material(diffuse(glam::Vec3::splat(0.8)));
+    builder.add(
+        "Plane",
+        &Mesh::plane(glam::Vec2::splat(2.)),
+        material,
+        glam::Mat4::IDENTITY,
+    );
+    builder.camera("Above", glam::Vec3::new(0., 2., 3.), glam::Vec3::ZERO, 45.);
+    builder.build()
+}
+
+// open towards +z, 2 units wide, deep and high, with the floor at y = 0
+pub fn cornell_box() -> Scene {
+    let mut builder = Builder::new();
+    let white = builder.material(diffuse(glam::Vec3::new(0.73, 0.73, 0.73)));
+    let red = builder.material(diffuse(glam::Vec3::new(0.65, 0.05, 0.05)));
+    let green = builder.material(diffuse(glam::Vec3::new(0.12, 0.45, 0.15)));
+    let light = builder.material(emissive(glam::Vec3::new(17., 12., 4.)));
+
+    let wall = Mesh::plane(glam::Vec2::splat(2.));
+    let walls = [
+        ("Floor", white, glam::Mat4::IDENTITY),
+        (
+            "Ceiling",
+            white,
+            glam::Mat4::from_translation(glam::Vec3::new(0., 2., 0.))
+                * glam::Mat4::from_rotation_x(PI),
+        ),
+        (
+            "Back",
+            white,
+            glam::Mat4::from_translation(glam::Vec3::new(0., 1., -1.))
+                * glam::Mat4::from_rotation_x(FRAC_PI_2),
+        ),
+        (
+            "Left",
+            red,
+            glam::Mat4::from_translation(glam::Vec3::new(-1., 1., 0.))
+                * glam::Mat4::from_rotation_z(-FRAC_PI_2),
+        ),
+        (
+            "Right",
+            green,
+            glam::Mat4::from_translation(glam::Vec3::new(1., 1., 0.))
+                * glam::Mat4::from_rotation_z(FRAC_PI_2),
+        ),
+    ];
+    for (name, material, transform) in walls {
+        builder.add(name, &wall, material, transform);
+    }
+
+    // slightly below the ceiling so that the two don't overlap
+    builder.add(
+        "Light",
+        &Mesh::plane(glam::Vec2::splat(0.5)),
+        light,
+        glam::Mat4::from_translation(glam::Vec3::new(0., 1.999, 0.))
+            * glam::Mat4::from_rotation_x(PI),
+    );
+
+    builder.add(
+        "Tall block",
+        &Mesh::cube(glam::Vec3::new(0.6, 1.2, 0.6)),
+        white,
+        glam::Mat4::from_translation(glam::Vec3::new(-0.35, 0.6, -0.3))
+            * glam::Mat4::from_rotation_y(0.3),
+    );
+    builder.add(
+        "Short block",
+        &Mesh::cube(glam::Vec3::splat(0.6)),
+        white,
+        glam::Mat4::from_translation(glam::Vec3::new(0.35, 0.3, 0.35))
+            * glam::Mat4::from_rotation_y(-0.3),
+    );
+
+    builder.camera(
+        "Front",
+        glam::Vec3::new(0., 1., 3.9),
+        glam::Vec3::new(0., 1., 0.),
+        40.,
+    );
+    builder.environment(glam::Vec3::ZERO);
+    builder.build()
+}
+
+// spheres in the xy plane, metallic increasing along x and roughness along y
+pub fn material_grid(columns: u32, rows: u32) -> Scene {
+    assert!(columns >= 2 && rows >= 2, "Material grid is too small");
+
+    let mut builder = Builder::new();
+    let sphere = Mesh::sphere(0.4, SPHERE_SEGMENTS, SPHERE_RINGS);
+    let color = glam::Vec3::new(0.9, 0.6, 0.3);
+    for row in 0..rows {
+        for column in 0..columns {
+            let metallic = column as f32 / (columns - 1) as f32;
+            let roughness = row as f32 / (rows - 1) as f32;
+            let material = builder.material(Material {
+                metallic,
+                roughness,
+                ..diffuse(color)
+            });
+            let position = glam::Vec3::new(
+                column as f32 - (columns - 1) as f32 / 2.,
+                row as f32 - (rows - 1) as f32 / 2.,
+                0.,
+            );
+            builder.add(
+                &format!("Sphere m{metallic:.2} r{roughness:.2}"),
+                &sphere,
+                material,
+                glam::Mat4::from_translation(position),
+            );
+        }
+    }
+
+    let distance = columns.max(rows) as f32 * 1.6;
+    builder.camera("Front", glam::Vec3::Z * distance, glam::Vec3::ZERO, 45.);
+    builder.build()
+}
+
+// a white diffuse sphere under a uniform white environment should be invisible, any visible
+// difference is energy lost or gained by the renderer
+pub fn white_furnace() -> Scene {
+    let mut builder = Builder::new();
+    let material = builder.material(diffuse(glam::Vec3::ONE));
+    builder.add(
+        "Sphere",
+        &Mesh::sphere(1., SPHERE_SEGMENTS, SPHERE_RINGS),
+        material,
+        glam::Mat4::IDENTITY,
+    );
+    builder.camera("Front", glam::Vec3::new(0., 0., 4.), glam::Vec3::ZERO, 45.);
+    builder.environment(glam::Vec3::ONE);
+    builder.build()
+}
+
+fn vertex(position: glam::Vec3, normal: glam::Vec3, tex_coords: glam::Vec2) -> Vertex {
+    Vertex {
+        position: position.extend(1.),
+        normal: normal.extend(1.),
+        tex_coords: tex_coords.extend(0.).extend(0.),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_bounding_box(
+        name: &str,
+        bounding_box: BoundingBox,
+        min: glam::Vec3,
+        max: glam::Vec3,
+    ) {
+        assert!(
+            bounding_box.min.abs_diff_eq(min, 1e-4) && bounding_box.max.abs_diff_eq(max, 1e-4),
+            "{name} is bounded by {:?} {:?}, expected {min:?} {max:?}",
+            bounding_box.min,
+            bounding_box.max
+        );
+    }
+
+    #[test]
+    fn generators_are_valid() {
+        for name in GENERATORS {
+            let scene = generate(name).unwrap_or_else(|| panic!("{name} isn't generated"));
+            let report = scene.validate();
+            assert!(!report.has_errors(), "{name} is invalid:\n{report}");
+            assert!(!scene.info.cameras.is_empty(), "{name} has no camera");
+        }
+        assert!(generate("teapot").is_none());
+    }
+
+    #[test]
+    fn generators_bounding_boxes() {
+        let expected = [
+            ("sphere", glam::Vec3::splat(-1.), glam::Vec3::splat(1.)),
+            ("box", glam::Vec3::splat(-0.5), glam::Vec3::splat(0.5)),
+            (
+                "plane",
+                glam::Vec3::new(-1., 0., -1.),
+                glam::Vec3::new(1., 0., 1.),
+            ),
+            (
+                "cornell",
+                glam::Vec3::new(-1., 0., -1.),
+                glam::Vec3::new(1., 2., 1.),
+            ),
+            (
+                "materials",
+                glam::Vec3::new(-2.4, -2.4, -0.4),
+                glam::Vec3::new(2.4, 2.4, 0.4),
+            ),
+            ("furnace", glam::Vec3::splat(-1.), glam::Vec3::splat(1.)),
+        ];
+        assert_eq!(expected.len(), GENERATORS.len());
+        for (name, min, max) in expected {
+            let scene = generate(name).unwrap();
+            assert_bounding_box(name, scene.info.bounding_box, min, max);
+        }
+    }
+
+    #[test]
+    fn environments() {
+        assert_eq!(cornell_box().info.environment.color, glam::Vec3::ZERO);
+        assert_eq!(white_furnace().info.environment.color, glam::Vec3::ONE);
+    }
+}
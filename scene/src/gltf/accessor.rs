@@ -0,0 +1,149 @@
+use gltf::{
+    accessor::{sparse::IndexType, DataType},
+    buffer,
+    buffer::View,
+    Accessor,
+};
+
+use crate::io::Result;
+
+// Reads an accessor as floats regardless of its component type, so that quantized
+// (KHR_mesh_quantization) attributes are dequantized and sparse substitutions applied
+pub fn read_f32<const N: usize>(
+    accessor: &Accessor,
+    buffers: &[buffer::Data],
+) -> Result<Vec<[f32; N]>> {
+    if accessor.dimensions().multiplicity() != N {
+        return Err(format!("Unexpected dimensions for accessor #{}", accessor.index()).into());
+    }
+    check_bounds(accessor, buffers)?;
+
+    let data_type = accessor.data_type();
+    let normalized = accessor.normalized();
+    let component_size = data_type.size();
+    let element_size = component_size * N;
+
+    let read_element = |bytes: &[u8]| -> [f32; N] {
+        std::array::from_fn(|i| read_component(&bytes[i * component_size..], data_type, normalized))
+    };
+
+    let mut elements = accessor.view().map_or_else(
+        || vec![[0.; N]; accessor.count()],
+        |view| {
+            let stride = view.stride().unwrap_or(element_size);
+            let data = &buffers[view.buffer().index()][view.offset() + accessor.offset()..];
+            (0..accessor.count())
+                .map(|i| read_element(&data[i * stride..]))
+                .collect()
+        },
+    );
+
+    if let Some(sparse) = accessor.sparse() {
+        let indices = sparse.indices();
+        let index_data =
+            &buffers[indices.view().buffer().index()][indices.view().offset() + indices.offset()..];
+        let values = sparse.values();
+        let value_data =
+            &buffers[values.view().buffer().index()][values.view().offset() + values.offset()..];
+
+        for i in 0..sparse.count() {
+            let index = match indices.index_type() {
+                IndexType::U8 => usize::from(index_data[i]),
+                IndexType::U16 => usize::from(u16::from_le_bytes([
+                    index_data[i * 2],
+                    index_data[i * 2 + 1],
+                ])),
+                IndexType::U32 => u32::from_le_bytes([
+                    index_data[i * 4],
+                    index_data[i * 4 + 1],
+                    index_data[i * 4 + 2],
+                    index_data[i * 4 + 3],
+                ]) as usize,
+            };
+            *elements.get_mut(index).ok_or_else(|| {
+                format!(
+                    "Sparse index {index} out of range of accessor #{}",
+                    accessor.index()
+                )
+            })? = read_element(&value_data[i * element_size..]);
+        }
+    }
+
+    Ok(elements)
+}
+
+// the elements of the accessor, and of its sparse substitutions, are within their buffer views,
+// and the views within their buffers
+pub fn check_bounds(accessor: &Accessor, buffers: &[buffer::Data]) -> Result<()> {
+    let in_bounds = |view: &View, offset: usize, count: usize, stride: usize, size: usize| {
+        let end = match count {
+            0 => offset,
+            _ => offset + (count - 1) * stride + size,
+        };
+        end <= view.length()
+            && buffers
+                .get(view.buffer().index())
+                .is_some_and(|buffer| view.offset() + view.length() <= buffer.len())
+    };
+
+    let size = accessor.size();
+    let count = accessor.count();
+    let mut checks = Vec::new();
+    if let Some(view) = accessor.view() {
+        let stride = view.stride().unwrap_or(size);
+        checks.push(in_bounds(&view, accessor.offset(), count, stride, size));
+    }
+    if let Some(sparse) = accessor.sparse() {
+        let (indices, values) = (sparse.indices(), sparse.values());
+        let index_size = match indices.index_type() {
+            IndexType::U8 => 1,
+            IndexType::U16 => 2,
+            IndexType::U32 => 4,
+        };
+        checks.push(in_bounds(
+            &indices.view(),
+            indices.offset(),
+            sparse.count(),
+            index_size,
+            index_size,
+        ));
+        checks.push(in_bounds(
+            &values.view(),
+            values.offset(),
+            sparse.count(),
+            size,
+            size,
+        ));
+    }
+
+    if checks.into_iter().all(|check| check) {
+        Ok(())
+    } else {
+        Err(format!("Accessor #{} out of range of its buffers", accessor.index()).into())
+    }
+}
+
+pub fn read_component(bytes: &[u8], data_type: DataType, normalized: bool) -> f32 {
+    let (value, max) = match data_type {
+        DataType::F32 => return f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        DataType::U32 => {
+            return u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32;
+        }
+        DataType::I8 => (f32::from(bytes[0] as i8), f32::from(i8::MAX)),
+        DataType::U8 => (f32::from(bytes[0]), f32::from(u8::MAX)),
+        DataType::I16 => (
+            f32::from(i16::from_le_bytes([bytes[0], bytes[1]])),
+            f32::from(i16::MAX),
+        ),
+        DataType::U16 => (
+            f32::from(u16::from_le_bytes([bytes[0], bytes[1]])),
+            f32::from(u16::MAX),
+        ),
+    };
+
+    if normalized {
+        (value / max).max(-1.)
+    } else {
+        value
+    }
+}
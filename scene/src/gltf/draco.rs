@@ -0,0 +1,305 @@
+// Decoding of KHR_draco_mesh_compression primitives, following
+// https://github.com/KhronosGroup/glTF/tree/main/extensions/2.0/Khronos/KHR_draco_mesh_compression
+
+use std::collections::{HashMap, HashSet};
+
+use draco_oxide_core::{
+    attribute::{Attribute, ComponentDataType},
+    types::PointIdx,
+};
+use gltf::{accessor::DataType, mesh::Semantic, Accessor};
+use serde::Deserialize;
+
+use super::accessor;
+use crate::io::Result;
+
+pub const EXTENSION_NAME: &str = "KHR_draco_mesh_compression";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompressedPrimitive {
+    pub buffer_view: usize,
+    // semantic -> unique id of the attribute in the Draco stream
+    pub attributes: HashMap<String, usize>,
+}
+
+// the triangles of a Draco mesh and its attributes by semantic, which replace the data of the
+// primitive's accessors
+pub struct DecodedMesh {
+    pub indices: Vec<u32>,
+    attributes: HashMap<String, Attribute>,
+}
+
+// the accessors of compressed primitives, which describe the decoded data rather than point to
+// any of their own
+pub fn compressed_accessors(json: &gltf::json::Root) -> HashSet<usize> {
+    json.meshes
+        .iter()
+        .flat_map(|mesh| &mesh.primitives)
+        .filter(|primitive| {
+            primitive
+                .extensions
+                .as_ref()
+                .is_some_and(|extensions| extensions.others.contains_key(EXTENSION_NAME))
+        })
+        .flat_map(|primitive| primitive.attributes.values().chain(&primitive.indices))
+        .map(gltf::json::Index::value)
+        .collect()
+}
+
+impl CompressedPrimitive {
+    pub fn decode(&self, data: &[u8]) -> Result<DecodedMesh> {
+        let mesh = draco_oxide_decoder::decode_mesh(data)
+            .map_err(|error| format!("Malformed Draco compressed mesh: {error}"))?;
+
+        let indices = mesh
+            .faces
+            .iter()
+            .flatten()
+            .map(|&point| usize::from(point) as u32)
+            .collect();
+
+        let mut by_id = mesh
+            .attributes
+            .into_iter()
+            .map(|attribute| (attribute.get_id().as_usize(), attribute))
+            .collect::<HashMap<_, _>>();
+        let attributes = self
+            .attributes
+            .iter()
+            .map(|(semantic, id)| {
+                let attribute = by_id
+                    .remove(id)
+                    .ok_or_else(|| format!("No Draco attribute #{id} found for {semantic}"))?;
+                Ok((semantic.clone(), attribute))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(DecodedMesh {
+            indices,
+            attributes,
+        })
+    }
+}
+
+impl DecodedMesh {
+    // reads a compressed attribute as floats like `accessor::read_f32`, the accessor only
+    // describing it
+    pub fn read_f32<const N: usize>(
+        &self,
+        semantic: &Semantic,
+        accessor: &Accessor,
+    ) -> Option<Result<Vec<[f32; N]>>> {
+        let attribute = self.attributes.get(&semantic.to_string())?;
+        Some(read_attribute(attribute, accessor))
+    }
+}
+
+fn read_attribute<const N: usize>(
+    attribute: &Attribute,
+    accessor: &Accessor,
+) -> Result<Vec<[f32; N]>> {
+    if attribute.get_num_components() != N || accessor.dimensions().multiplicity() != N {
+        return Err(format!("Unexpected dimensions for accessor #{}", accessor.index()).into());
+    }
+    if attribute.len() != accessor.count() {
+        return Err(format!(
+            "Draco attribute count does not match the count of accessor #{}",
+            accessor.index()
+        )
+        .into());
+    }
+
+    // values are dequantized by the decoder, integer ones are normalized as the accessor says
+    let data_type = match attribute.get_component_type() {
+        ComponentDataType::F32 => DataType::F32,
+        ComponentDataType::U32 => DataType::U32,
+        ComponentDataType::I16 => DataType::I16,
+        ComponentDataType::U16 => DataType::U16,
+        ComponentDataType::I8 => DataType::I8,
+        ComponentDataType::U8 => DataType::U8,
+        component_type => {
+            return Err(format!("Unsupported Draco component type {component_type:?}").into());
+        }
+    };
+    let normalized = accessor.normalized();
+    let component_size = data_type.size();
+    let data = attribute.get_data_as_bytes();
+
+    Ok((0..attribute.len())
+        .map(|point| {
+            let value = usize::from(attribute.get_unique_val_idx(PointIdx::from(point)));
+            let element = &data[value * component_size * N..];
+            std::array::from_fn(|i| {
+                accessor::read_component(&element[i * component_size..], data_type, normalized)
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use draco_oxide_core::types::{NdVector, Vector};
+
+    use crate::{gltf::Gltf, io::FileLoader, options::ImportOptions, testing::TempDir};
+
+    // a unit quad with texture coordinates equal to its positions and normalized rgba8 colors,
+    // encoded by draco-oxide with its default settings, which reorders the points
+    const QUAD_DATA: [u8; 179] = [
+        0x44, 0x52, 0x41, 0x43, 0x4f, 0x02, 0x02, 0x01, 0x01, 0x00, 0x00, 0x02, 0x04, 0x02, 0x02,
+        0x02, 0x00, 0x00, 0xff, 0x01, 0x11, 0xff, 0x01, 0x11, 0xff, 0x01, 0x11, 0x01, 0x01, 0x01,
+        0x03, 0x07, 0x01, 0x40, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0xff, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x01, 0x00, 0x09, 0x03, 0x00, 0x00, 0x02, 0x01, 0x03,
+        0x09, 0x02, 0x00, 0x01, 0x02, 0x01, 0x02, 0x02, 0x04, 0x00, 0x02, 0x01, 0x01, 0x01, 0x01,
+        0x01, 0x02, 0x03, 0x01, 0x30, 0xad, 0x0a, 0x55, 0x05, 0x04, 0xec, 0x98, 0xc5, 0x85, 0x00,
+        0x00, 0x00, 0x00, 0xff, 0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x3f, 0x0b, 0x01, 0x01, 0x01, 0x01, 0x02, 0x03,
+        0x01, 0x28, 0x01, 0x10, 0x01, 0x08, 0x04, 0x00, 0x3c, 0x16, 0x81, 0x00, 0x00, 0x00, 0x00,
+        0xff, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80,
+        0x3f, 0x0a, 0x00, 0x01, 0x01, 0x01, 0x02, 0x03, 0x01, 0x18, 0x01, 0x18, 0x01, 0x10, 0x06,
+        0x00, 0x8e, 0x34, 0x54, 0x56, 0x80, 0x00, 0x00, 0x00, 0x00, 0xff, 0x00, 0x00, 0x00,
+    ];
+    // the corners of the quad with their colors, and its two counter-clockwise triangles
+    const QUAD_CORNERS: [([f32; 3], [u8; 4]); 4] = [
+        ([0., 0., 0.], [255, 0, 0, 255]),
+        ([1., 0., 0.], [0, 255, 0, 255]),
+        ([1., 1., 0.], [0, 0, 255, 255]),
+        ([0., 1., 0.], [255, 255, 255, 0]),
+    ];
+    const QUAD_TRIANGLES: [[usize; 3]; 2] = [[0, 1, 2], [0, 2, 3]];
+
+    // the triangles as corner indices, each rotated to start at its smallest index, sorted,
+    // the corners decode exactly at the ends of the quantization range
+    #[allow(clippy::float_cmp)]
+    fn corner_triangles(indices: &[u32], positions: &[[f32; 3]]) -> Vec<[usize; 3]> {
+        let mut triangles = indices
+            .chunks_exact(3)
+            .map(|triangle| {
+                let mut corners = [0, 1, 2].map(|i| {
+                    QUAD_CORNERS
+                        .iter()
+                        .position(|(corner, _)| *corner == positions[triangle[i] as usize])
+                        .unwrap()
+                });
+                let first = (0..3).min_by_key(|&i| corners[i]).unwrap();
+                corners.rotate_left(first);
+                corners
+            })
+            .collect::<Vec<_>>();
+        triangles.sort_unstable();
+        triangles
+    }
+
+    #[test]
+    fn decode() {
+        let compressed: CompressedPrimitive = serde_json::from_value(serde_json::json!({
+            "bufferView": 0,
+            "attributes": { "POSITION": 0, "TEXCOORD_0": 1, "COLOR_0": 2 },
+        }))
+        .unwrap();
+        let decoded = compressed.decode(&QUAD_DATA).unwrap();
+
+        let positions = decoded.attributes["POSITION"].len();
+        assert_eq!(positions, QUAD_CORNERS.len());
+        let positions = (0..positions)
+            .map(|point| {
+                let position: NdVector<3, f32> =
+                    decoded.attributes["POSITION"].get(PointIdx::from(point));
+                [0, 1, 2].map(|i| *position.get(i))
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            corner_triangles(&decoded.indices, &positions),
+            QUAD_TRIANGLES
+        );
+
+        assert_eq!(
+            decoded.attributes["COLOR_0"].get_component_type(),
+            ComponentDataType::U8
+        );
+
+        // attributes the stream doesn't have and truncated streams
+        let missing: CompressedPrimitive = serde_json::from_value(serde_json::json!({
+            "bufferView": 0,
+            "attributes": { "POSITION": 0, "NORMAL": 3 },
+        }))
+        .unwrap();
+        assert_eq!(
+            missing.decode(&QUAD_DATA).err().unwrap().to_string(),
+            "No Draco attribute #3 found for NORMAL"
+        );
+        assert!(compressed
+            .decode(&QUAD_DATA[..QUAD_DATA.len() - 8])
+            .is_err());
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn load() {
+        let dir = TempDir::new("draco");
+        std::fs::write(dir.join("quad.bin"), QUAD_DATA).unwrap();
+        // the accessors have no buffer views, their data is in the compressed one
+        let gltf = serde_json::json!({
+            "asset": { "version": "2.0" },
+            "extensionsUsed": [EXTENSION_NAME],
+            "extensionsRequired": [EXTENSION_NAME],
+            "buffers": [{ "uri": "quad.bin", "byteLength": QUAD_DATA.len() }],
+            "bufferViews": [{ "buffer": 0, "byteLength": QUAD_DATA.len() }],
+            "accessors": [
+                { "componentType": 5125, "count": 6, "type": "SCALAR" },
+                {
+                    "componentType": 5126, "count": 4, "type": "VEC3",
+                    "min": [0., 0., 0.], "max": [1., 1., 0.],
+                },
+                { "componentType": 5126, "count": 4, "type": "VEC2" },
+                { "componentType": 5121, "normalized": true, "count": 4, "type": "VEC4" },
+            ],
+            "meshes": [{
+                "primitives": [{
+                    "indices": 0,
+                    "attributes": { "POSITION": 1, "TEXCOORD_0": 2, "COLOR_0": 3 },
+                    "extensions": {
+                        EXTENSION_NAME: {
+                            "bufferView": 0,
+                            "attributes": { "POSITION": 0, "TEXCOORD_0": 1, "COLOR_0": 2 },
+                        },
+                    },
+                }],
+            }],
+            "nodes": [{ "mesh": 0 }],
+            "scenes": [{ "nodes": [0] }],
+        });
+        std::fs::write(dir.join("quad.gltf"), gltf.to_string()).unwrap();
+
+        let scene = Gltf::load(dir.join("quad.gltf"), &ImportOptions::default()).unwrap();
+
+        let positions = scene
+            .data
+            .vertices
+            .iter()
+            .map(|vertex| vertex.position.truncate().to_array())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            corner_triangles(&scene.data.indices, &positions),
+            QUAD_TRIANGLES
+        );
+
+        let colors = &scene.data.colors;
+        assert_eq!(colors.len(), positions.len());
+        for ((vertex, position), color) in scene.data.vertices.iter().zip(&positions).zip(colors) {
+            let (_, corner_color) = QUAD_CORNERS
+                .iter()
+                .find(|(corner, _)| corner == position)
+                .unwrap();
+            assert_eq!(
+                *color,
+                glam::Vec4::from(corner_color.map(|component| f32::from(component) / 255.))
+            );
+            assert_eq!(
+                vertex.tex_coords.truncate().truncate().to_array(),
+                position[..2]
+            );
+        }
+    }
+}
@@ -0,0 +1,609 @@
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    ops::Range,
+    path::{Component, Path},
+};
+
+use glam::Vec4Swizzles;
+use serde_json::{json, Value};
+
+use crate::{Instance, Material, Scene};
+
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+const VARIANTS_EXTENSION_NAME: &str = "KHR_materials_variants";
+const EMISSIVE_STRENGTH_EXTENSION_NAME: &str = "KHR_materials_emissive_strength";
+
+// collects the contents of the single buffer along with its views and accessors
+#[derive(Default)]
+struct Buffer {
+    data: Vec<u8>,
+    views: Vec<Value>,
+    accessors: Vec<Value>,
+}
+
+// writes a `.glb`, or a `.gltf` with its buffer in a `.bin` file next to it
+pub fn save(scene: &Scene, filename: impl AsRef<Path>) {
+    firestorm::profile_fn!(gltf_save);
+
+    let filename = filename.as_ref();
+    let filedir = filename.parent().unwrap_or_else(|| Path::new("./"));
+    let is_binary = filename
+        .extension()
+        .is_some_and(|extension| extension == "glb");
+
+    let mut buffer = Buffer::default();
+    let primitives = (0..scene.info.primitive_infos.len())
+        .map(|primitive| export_primitive(scene, primitive, &mut buffer))
+        .collect::<Vec<_>>();
+
+    let mut meshes = Vec::new();
+    let (nodes, roots) = export_nodes(scene, |range: Range<usize>| {
+        meshes.push(json!({ "primitives": primitives[range].to_vec() }));
+        meshes.len() - 1
+    });
+
+    let mut root = json!({
+        "asset": { "version": "2.0", "generator": "tsunami" },
+        "scene": 0,
+        "scenes": [{ "nodes": roots }],
+        "nodes": nodes,
+        "meshes": meshes,
+        "materials": export_materials(scene),
+        "bufferViews": buffer.views,
+        "accessors": buffer.accessors,
+    });
+
+    if !scene.info.textures.is_empty() {
+        root["images"] = scene
+            .data
+            .images
+            .iter()
+            .map(|image| json!({ "uri": image_uri(&image.source, filedir) }))
+            .collect();
+        root["samplers"] = json!([{}]);
+        root["textures"] = scene
+            .info
+            .textures
+            .iter()
+            .map(|texture| json!({ "source": texture.image_index, "sampler": 0 }))
+            .collect();
+    }
+
+    let mut extensions_used = Vec::new();
+    if scene
+        .data
+        .materials
+        .iter()
+        .any(|material| emissive_strength(material).is_some())
+    {
+        extensions_used.push(EMISSIVE_STRENGTH_EXTENSION_NAME);
+    }
+    if !scene.info.variants.is_empty() {
+        extensions_used.push(VARIANTS_EXTENSION_NAME);
+        root["extensions"] = json!({
+            VARIANTS_EXTENSION_NAME: {
+                "variants": scene
+                    .info
+                    .variants
+                    .iter()
+                    .map(|variant| json!({ "name": variant.name }))
+                    .collect::<Vec<_>>()
+            }
+        });
+    }
+    if !extensions_used.is_empty() {
+        root["extensionsUsed"] = extensions_used.into();
+    }
+
+    if is_binary {
+        root["buffers"] = json!([{ "byteLength": buffer.data.len() }]);
+        let glb = gltf::binary::Glb {
+            header: gltf::binary::Header {
+                magic: *b"glTF",
+                version: 2,
+                // computed when writing
+                length: 0,
+            },
+            json: Cow::Owned(serde_json::to_vec(&root).expect("Failed to serialize gltf")),
+            bin: Some(Cow::Owned(buffer.data)),
+        };
+        let file = std::fs::File::create(filename).expect("Unable to open file for writing");
+        glb.to_writer(std::io::BufWriter::new(file))
+            .expect("Failed to write glb file");
+    } else {
+        let bin_filename = filename.with_extension("bin");
+        let bin_uri = bin_filename
+            .file_name()
+            .expect("Invalid gltf filename")
+            .to_string_lossy();
+        root["buffers"] = json!([{ "byteLength": buffer.data.len(), "uri": bin_uri }]);
+        std::fs::write(&bin_filename, &buffer.data).expect("Unable to write gltf buffer");
+        std::fs::write(
+            filename,
+            serde_json::to_string_pretty(&root).expect("Failed to serialize gltf"),
+        )
+        .expect("Unable to write gltf file");
+    }
+
+    println!("Scene exported to {}", filename.display());
+}
+
+// relative to the exported file where possible, with forward slashes and percent-encoded names
+fn image_uri(source: &Path, filedir: &Path) -> String {
+    crate::io::relative_path(source, filedir)
+        .components()
+        .map(|component| match component {
+            Component::Normal(name) => urlencoding::encode(&name.to_string_lossy()).into_owned(),
+            // joined into a leading slash
+            Component::RootDir => String::new(),
+            _ => component.as_os_str().to_string_lossy().into_owned(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn export_primitive(scene: &Scene, primitive: usize, buffer: &mut Buffer) -> Value {
+    let info = &scene.info.primitive_infos[primitive];
+    let size = &scene.info.primitive_sizes[primitive];
+
+    let vertices_start = info.vertices_offset as usize;
+    let vertices =
+        &scene.data.vertices[vertices_start..vertices_start + size.vertices_size as usize];
+    let indices_start = info.indices_offset as usize;
+    let indices = &scene.data.indices[indices_start..indices_start + size.indices_size as usize];
+
+    let (min, max) = vertices.iter().fold(
+        (glam::Vec3::INFINITY, glam::Vec3::NEG_INFINITY),
+        |(min, max), vertex| {
+            let position = vertex.position.truncate();
+            (min.min(position), max.max(position))
+        },
+    );
+    let position = buffer.push_floats(
+        vertices
+            .iter()
+            .flat_map(|vertex| vertex.position.truncate().to_array()),
+        "VEC3",
+        Some((min.to_array(), max.to_array())),
+    );
+    let normal = buffer.push_floats(
+        vertices
+            .iter()
+            .flat_map(|vertex| vertex.normal.truncate().to_array()),
+        "VEC3",
+        None,
+    );
+    let tex_coords0 = buffer.push_floats(
+        vertices
+            .iter()
+            .flat_map(|vertex| vertex.tex_coords.xy().to_array()),
+        "VEC2",
+        None,
+    );
+    let tex_coords1 = buffer.push_floats(
+        vertices
+            .iter()
+            .flat_map(|vertex| vertex.tex_coords.zw().to_array()),
+        "VEC2",
+        None,
+    );
+    let indices = buffer.push_indices(indices);
+
+    let mut attributes = json!({
+        "POSITION": position,
+        "NORMAL": normal,
+        "TEXCOORD_0": tex_coords0,
+        "TEXCOORD_1": tex_coords1,
+    });
+    if let Ok(colors_start) = usize::try_from(info.colors_offset) {
+        let colors = &scene.data.colors[colors_start..colors_start + vertices.len()];
+        attributes["COLOR_0"] = buffer
+            .push_floats(colors.iter().flat_map(glam::Vec4::to_array), "VEC4", None)
+            .into();
+    }
+
+    let mut primitive_json = json!({
+        "attributes": attributes,
+        "indices": indices,
+        "material": info.material,
+    });
+
+    let mappings = scene
+        .info
+        .variants
+        .iter()
+        .enumerate()
+        .flat_map(|(idx, variant)| {
+            variant
+                .materials
+                .iter()
+                .filter(|&&(mapped, _)| mapped == primitive)
+                .map(move |&(_, material)| json!({ "material": material, "variants": [idx] }))
+        })
+        .collect::<Vec<_>>();
+    if !mappings.is_empty() {
+        primitive_json["extensions"] = json!({ VARIANTS_EXTENSION_NAME: { "mappings": mappings } });
+    }
+
+    primitive_json
+}
+
+// returns the nodes and the indices of the root nodes, calling `add_mesh` for every set of
+// primitives that needs a mesh
+fn export_nodes(
+    scene: &Scene,
+    mut add_mesh: impl FnMut(Range<usize>) -> usize,
+) -> (Vec<Value>, Vec<usize>) {
+    let scene_nodes = &scene.info.nodes;
+
+    let mut node_instances = vec![Vec::new(); scene_nodes.len()];
+    let mut free_instances = Vec::new();
+    for instance in &scene.info.instances {
+        match instance.node {
+            Some(node) => node_instances[node].push(instance),
+            None => free_instances.push(instance),
+        }
+    }
+
+    let mut meshes = HashMap::new();
+    let mut mesh_for = |range: Range<usize>| {
+        *meshes
+            .entry(range.clone())
+            .or_insert_with(|| add_mesh(range))
+    };
+
+    let mut nodes = scene_nodes
+        .iter()
+        .map(|node| {
+            let mut node_json = json!({ "name": node.name });
+            set_matrix(&mut node_json, node.transform);
+            node_json
+        })
+        .collect::<Vec<_>>();
+    let mut children = vec![Vec::new(); scene_nodes.len()];
+    let mut roots = Vec::new();
+
+    for (idx, node) in scene_nodes.iter().enumerate() {
+        match node.parent {
+            Some(parent) => children[parent].push(idx),
+            None => roots.push(idx),
+        }
+
+        let instances = &node_instances[idx];
+        // nodes that weren't filtered or instanced can reference their mesh directly
+        let is_whole = instances.len() == node.primitives.len()
+            && instances
+                .iter()
+                .zip(node.primitives.clone())
+                .all(|(instance, primitive)| {
+                    instance.primitive_index == primitive
+                        && instance.local_transform == glam::Mat4::IDENTITY
+                });
+        if is_whole {
+            if !node.primitives.is_empty() {
+                nodes[idx]["mesh"] = mesh_for(node.primitives.clone()).into();
+            }
+        } else {
+            for instance in instances {
+                children[idx].push(nodes.len());
+                nodes.push(instance_node(
+                    instance,
+                    instance.local_transform,
+                    &mut mesh_for,
+                ));
+            }
+        }
+    }
+
+    for instance in free_instances {
+        roots.push(nodes.len());
+        nodes.push(instance_node(instance, instance.transform, &mut mesh_for));
+    }
+
+    for (node, children) in nodes.iter_mut().zip(children) {
+        if !children.is_empty() {
+            node["children"] = children.into();
+        }
+    }
+
+    (nodes, roots)
+}
+
+fn instance_node(
+    instance: &Instance,
+    transform: glam::Mat4,
+    mesh_for: &mut impl FnMut(Range<usize>) -> usize,
+) -> Value {
+    let primitive = instance.primitive_index;
+    let mut node = json!({ "mesh": mesh_for(primitive..primitive + 1) });
+    set_matrix(&mut node, transform);
+    node
+}
+
+fn set_matrix(node: &mut Value, transform: glam::Mat4) {
+    if transform != glam::Mat4::IDENTITY {
+        node["matrix"] = transform.to_cols_array().to_vec().into();
+    }
+}
+
+fn export_materials(scene: &Scene) -> Vec<Value> {
+    let texture = |texture: i32| {
+        u32::try_from(texture)
+            .ok()
+            .map(|index| json!({ "index": index }))
+    };
+
+    scene
+        .data
+        .materials
+        .iter()
+        .map(|material| {
+            let mut pbr = json!({
+                "baseColorFactor": material.color.extend(1.).to_array(),
+                "metallicFactor": material.metallic,
+                "roughnessFactor": material.roughness,
+            });
+            if let Some(texture) = texture(material.color_texture) {
+                pbr["baseColorTexture"] = texture;
+            }
+            if let Some(texture) = texture(material.metallic_roughness_texture) {
+                pbr["metallicRoughnessTexture"] = texture;
+            }
+
+            let strength = emissive_strength(material);
+            let mut material_json = json!({
+                "pbrMetallicRoughness": pbr,
+                "emissiveFactor": (material.emittance / strength.unwrap_or(1.)).to_array(),
+            });
+            if let Some(strength) = strength {
+                material_json["extensions"] = json!({
+                    EMISSIVE_STRENGTH_EXTENSION_NAME: { "emissiveStrength": strength }
+                });
+            }
+            if let Some(texture) = texture(material.emittance_texture) {
+                material_json["emissiveTexture"] = texture;
+            }
+            material_json
+        })
+        .collect()
+}
+
+// emissive factors are limited to [0, 1], brighter emittances are scaled by their largest component
+fn emissive_strength(material: &Material) -> Option<f32> {
+    let strength = material.emittance.max_element();
+    (strength > 1.).then_some(strength)
+}
+
+impl Buffer {
+    fn push_floats(
+        &mut self,
+        values: impl Iterator<Item = f32>,
+        type_: &str,
+        bounds: Option<([f32; 3], [f32; 3])>,
+    ) -> usize {
+        let components = match type_ {
+            "VEC2" => 2,
+            "VEC3" => 3,
+            _ => 4,
+        };
+        let data = values.flat_map(f32::to_le_bytes).collect::<Vec<_>>();
+        let count = data.len() / (components * 4);
+
+        let mut accessor = json!({
+            "bufferView": self.push_view(&data, ARRAY_BUFFER),
+            "componentType": FLOAT,
+            "count": count,
+            "type": type_,
+        });
+        if let Some((min, max)) = bounds {
+            accessor["min"] = min.to_vec().into();
+            accessor["max"] = max.to_vec().into();
+        }
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    fn push_indices(&mut self, indices: &[u32]) -> usize {
+        let data = indices
+            .iter()
+            .flat_map(|index| index.to_le_bytes())
+            .collect::<Vec<_>>();
+        let view = self.push_view(&data, ELEMENT_ARRAY_BUFFER);
+        self.accessors.push(json!({
+            "bufferView": view,
+            "componentType": UNSIGNED_INT,
+            "count": indices.len(),
+            "type": "SCALAR",
+        }));
+        self.accessors.len() - 1
+    }
+
+    fn push_view(&mut self, data: &[u8], target: u32) -> usize {
+        // all components are 4 bytes
+        let offset = self.data.len();
+        self.data.extend_from_slice(data);
+        self.views.push(json!({
+            "buffer": 0,
+            "byteOffset": offset,
+            "byteLength": data.len(),
+            "target": target,
+        }));
+        self.views.len() - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        generate, gltf::Gltf, io::FileLoader, options::ImportOptions, testing::TempDir, Image,
+        Node, TextureInfo, Variant,
+    };
+
+    // the cornell box with a texture in a sibling directory, vertex colors on the floor, a copy
+    // of the short block sharing its mesh as a child node and a variant
+    fn textured_scene(dir: &TempDir) -> Scene {
+        let mut scene = generate::cornell_box();
+
+        let source = dir.join("textures/white wall.png");
+        std::fs::create_dir_all(source.parent().unwrap()).unwrap();
+        image::RgbaImage::new(2, 2).save(&source).unwrap();
+        scene.data.images.push(Image { source });
+        scene.info.textures.push(TextureInfo { image_index: 0 });
+        scene.data.materials[0].color_texture = 0;
+
+        let floor_vertices = scene.info.primitive_sizes[0].vertices_size;
+        scene.data.colors = (0..floor_vertices)
+            .map(|vertex| glam::Vec4::new(vertex as f32 / 4., 0.5, 1., 0.75))
+            .collect();
+        scene.info.primitive_infos[0].colors_offset = 0;
+
+        let parent = scene.info.nodes.len() - 1;
+        let transform = glam::Mat4::from_translation(glam::Vec3::new(0., 0.6, 0.));
+        scene.info.nodes.push(Node {
+            name: "Short block copy".to_owned(),
+            transform,
+            parent: Some(parent),
+            primitives: scene.info.nodes[parent].primitives.clone(),
+        });
+        let parent_transform = scene.info.nodes[parent].transform;
+        scene.info.instances.push(Instance {
+            primitive_index: scene.info.nodes[parent].primitives.start,
+            transform: parent_transform * transform,
+            node: Some(scene.info.nodes.len() - 1),
+            local_transform: glam::Mat4::IDENTITY,
+        });
+
+        scene.info.variants.push(Variant {
+            name: "Red floor".to_owned(),
+            materials: vec![(0, 1)],
+        });
+        scene
+    }
+
+    // everything but the emittances is written as is, and image sources are resolved again
+    #[allow(clippy::float_cmp)]
+    fn round_trip(extension: &str) {
+        let dir = TempDir::new("export");
+        let scene = textured_scene(&dir);
+
+        let filename = dir.join(format!("scenes/cornell.{extension}"));
+        std::fs::create_dir_all(filename.parent().unwrap()).unwrap();
+        save(&scene, &filename);
+        let imported = Gltf::load(&filename, &ImportOptions::default()).unwrap();
+
+        assert_eq!(imported.data.indices, scene.data.indices);
+
+        assert_eq!(imported.data.vertices.len(), scene.data.vertices.len());
+        for (imported, vertex) in imported.data.vertices.iter().zip(&scene.data.vertices) {
+            assert_eq!(imported.position.truncate(), vertex.position.truncate());
+            assert_eq!(imported.normal.truncate(), vertex.normal.truncate());
+            assert_eq!(imported.tex_coords, vertex.tex_coords);
+        }
+        assert_eq!(imported.data.colors, scene.data.colors);
+        for (imported, info) in imported
+            .info
+            .primitive_infos
+            .iter()
+            .zip(&scene.info.primitive_infos)
+        {
+            assert_eq!(imported.colors_offset, info.colors_offset);
+            assert_eq!(imported.material, info.material);
+        }
+
+        assert_eq!(imported.data.materials.len(), scene.data.materials.len());
+        for (imported, material) in imported.data.materials.iter().zip(&scene.data.materials) {
+            assert_eq!(imported.color, material.color);
+            assert!(
+                imported.emittance.abs_diff_eq(material.emittance, 1e-5),
+                "emittance {} was exported as {}",
+                material.emittance,
+                imported.emittance
+            );
+            assert_eq!(imported.metallic, material.metallic);
+            assert_eq!(imported.roughness, material.roughness);
+            assert_eq!(imported.color_texture, material.color_texture);
+            assert_eq!(imported.emittance_texture, -1);
+            assert_eq!(imported.metallic_roughness_texture, -1);
+        }
+        assert_eq!(imported.info.textures.len(), 1);
+        assert_eq!(imported.info.textures[0].image_index, 0);
+        assert_eq!(imported.data.images.len(), 1);
+        assert_eq!(
+            imported.data.images[0].source.canonicalize().unwrap(),
+            scene.data.images[0].source.canonicalize().unwrap()
+        );
+
+        assert_eq!(imported.info.nodes.len(), scene.info.nodes.len());
+        for (imported, node) in imported.info.nodes.iter().zip(&scene.info.nodes) {
+            assert_eq!(imported.name, node.name);
+            assert_eq!(imported.transform, node.transform);
+            assert_eq!(imported.parent, node.parent);
+            assert_eq!(imported.primitives, node.primitives);
+        }
+        assert_eq!(imported.info.instances.len(), scene.info.instances.len());
+        for (imported, instance) in imported.info.instances.iter().zip(&scene.info.instances) {
+            assert_eq!(imported.primitive_index, instance.primitive_index);
+            assert_eq!(imported.node, instance.node);
+            assert!(
+                imported.transform.abs_diff_eq(instance.transform, 1e-6),
+                "transform {} was exported as {}",
+                instance.transform,
+                imported.transform
+            );
+        }
+
+        assert_eq!(imported.info.variants.len(), 1);
+        assert_eq!(imported.info.variants[0].name, "Red floor");
+        assert_eq!(imported.info.variants[0].materials, [(0, 1)]);
+    }
+
+    #[test]
+    fn image_uris() {
+        let dir = Path::new("/assets/scenes");
+        for (source, uri) in [
+            ("/assets/scenes/white.png", "white.png"),
+            ("/assets/scenes/textures/white.png", "textures/white.png"),
+            (
+                "/assets/textures/white wall.png",
+                "../textures/white%20wall.png",
+            ),
+            ("/assets/100%/white.png", "../100%25/white.png"),
+        ] {
+            assert_eq!(image_uri(Path::new(source), dir), uri);
+        }
+    }
+
+    #[test]
+    fn gltf_round_trip() {
+        round_trip("gltf");
+    }
+
+    #[test]
+    fn glb_round_trip() {
+        round_trip("glb");
+    }
+
+    #[test]
+    fn emissive_factors_are_normalized() {
+        let materials = export_materials(&generate::cornell_box());
+        let light = &materials[3];
+        let factor = light["emissiveFactor"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|value| value.as_f64().unwrap())
+            .collect::<Vec<_>>();
+        assert!(factor.iter().all(|value| (0. ..=1.).contains(value)));
+        assert_eq!(
+            light["extensions"][EMISSIVE_STRENGTH_EXTENSION_NAME]["emissiveStrength"],
+            17.
+        );
+        assert!(materials[0].get("extensions").is_none());
+    }
+}
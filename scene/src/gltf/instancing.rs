@@ -0,0 +1,239 @@
+// Per-instance transforms of EXT_mesh_gpu_instancing, following
+// https://github.com/KhronosGroup/glTF/tree/main/extensions/2.0/Vendor/EXT_mesh_gpu_instancing
+
+use gltf::buffer;
+use serde::Deserialize;
+
+use super::accessor;
+use crate::io::Result;
+
+pub const EXTENSION_NAME: &str = "EXT_mesh_gpu_instancing";
+
+#[derive(Deserialize)]
+struct Instancing {
+    attributes: Attributes,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+struct Attributes {
+    translation: Option<usize>,
+    rotation: Option<usize>,
+    scale: Option<usize>,
+}
+
+// returns the transforms of the instances relative to the node, if the node is instanced
+pub fn read_transforms(
+    node: &gltf::scene::Node<'_>,
+    document: &gltf::Document,
+    buffers: &[buffer::Data],
+) -> Result<Option<Vec<glam::Mat4>>> {
+    let Some(extension) = node.extension_value(EXTENSION_NAME) else {
+        return Ok(None);
+    };
+    let Attributes {
+        translation,
+        rotation,
+        scale,
+    } = Instancing::deserialize(extension)
+        .map_err(|error| format!("Invalid gpu instancing extension: {error}"))?
+        .attributes;
+
+    let accessor = |index: Option<usize>| {
+        index
+            .map(|index| {
+                document
+                    .accessors()
+                    .nth(index)
+                    .ok_or("Invalid gpu instancing accessor")
+            })
+            .transpose()
+    };
+    let translations = accessor(translation)?
+        .map(|accessor| accessor::read_f32::<3>(&accessor, buffers))
+        .transpose()?;
+    let rotations = accessor(rotation)?
+        .map(|accessor| accessor::read_f32::<4>(&accessor, buffers))
+        .transpose()?;
+    let scales = accessor(scale)?
+        .map(|accessor| accessor::read_f32::<3>(&accessor, buffers))
+        .transpose()?;
+
+    let counts = [
+        translations.as_ref().map(Vec::len),
+        rotations.as_ref().map(Vec::len),
+        scales.as_ref().map(Vec::len),
+    ];
+    let Some(count) = counts.into_iter().flatten().next() else {
+        return Ok(None);
+    };
+    if counts.into_iter().flatten().any(|other| other != count) {
+        return Err(format!(
+            "Gpu instancing attributes of node #{} have different counts",
+            node.index()
+        )
+        .into());
+    }
+
+    Ok(Some(
+        (0..count)
+            .map(|i| {
+                glam::Mat4::from_scale_rotation_translation(
+                    scales
+                        .as_ref()
+                        .map_or(glam::Vec3::ONE, |scales| scales[i].into()),
+                    rotations
+                        .as_ref()
+                        .map_or(glam::Quat::IDENTITY, |rotations| {
+                            glam::Quat::from_array(rotations[i]).normalize()
+                        }),
+                    translations
+                        .as_ref()
+                        .map_or(glam::Vec3::ZERO, |translations| translations[i].into()),
+                )
+            })
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    use crate::{gltf::Gltf, io::FileLoader, options::ImportOptions, testing::TempDir};
+
+    // a triangle, float translations, normalized short rotations, float scales and byte
+    // translations, two instances each, then the triangle's indices
+    fn buffer() -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let floats = [
+            [0., 0., 0.],
+            [1., 0., 0.],
+            [0., 1., 0.],
+            [1., 2., 3.],
+            [-1., 0., 0.],
+        ];
+        for value in floats.iter().flatten() {
+            bytes.extend(f32::to_le_bytes(*value));
+        }
+        // identity and a quarter turn around y
+        for value in [0, 0, 0, i16::MAX, 0, 23170, 0, 23170] {
+            bytes.extend(i16::to_le_bytes(value));
+        }
+        for value in [2., 2., 2., 1., 0.5, 1.] {
+            bytes.extend(f32::to_le_bytes(value));
+        }
+        for value in [3_i8, 0, 0, 0, -2, 0] {
+            bytes.extend(i8::to_le_bytes(value));
+        }
+        bytes.extend([0_u8, 1, 2]);
+        bytes
+    }
+
+    fn load(dir: &TempDir, nodes: &serde_json::Value) -> Result<crate::Scene> {
+        let buffer = buffer();
+        std::fs::write(dir.join("triangle.bin"), &buffer).unwrap();
+        let views = [(0, 36), (36, 24), (60, 16), (76, 24), (100, 6), (106, 3)].map(
+            |(start, length)| json!({ "buffer": 0, "byteOffset": start, "byteLength": length }),
+        );
+        let gltf = json!({
+            "asset": { "version": "2.0" },
+            "extensionsUsed": [EXTENSION_NAME],
+            "buffers": [{ "uri": "triangle.bin", "byteLength": buffer.len() }],
+            "bufferViews": views,
+            "accessors": [
+                {
+                    "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+                    "min": [0., 0., 0.], "max": [1., 1., 0.],
+                },
+                { "bufferView": 1, "componentType": 5126, "count": 2, "type": "VEC3" },
+                {
+                    "bufferView": 2, "componentType": 5122, "normalized": true, "count": 2,
+                    "type": "VEC4",
+                },
+                { "bufferView": 3, "componentType": 5126, "count": 2, "type": "VEC3" },
+                { "bufferView": 4, "componentType": 5120, "count": 2, "type": "VEC3" },
+                { "bufferView": 5, "componentType": 5121, "count": 3, "type": "SCALAR" },
+            ],
+            "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0 }, "indices": 5 }] }],
+            "nodes": nodes,
+            "scenes": [{ "nodes": [0] }],
+        });
+        std::fs::write(dir.join("triangle.gltf"), gltf.to_string()).unwrap();
+        Gltf::load(dir.join("triangle.gltf"), &ImportOptions::default())
+    }
+
+    fn instanced(attributes: &serde_json::Value) -> serde_json::Value {
+        json!({ EXTENSION_NAME: { "attributes": attributes } })
+    }
+
+    #[test]
+    fn instances_are_expanded() {
+        let dir = TempDir::new("instancing");
+        let nodes = json!([
+            {
+                "translation": [0., 0., 5.],
+                "children": [1],
+                "mesh": 0,
+                "extensions": instanced(&json!({
+                    "TRANSLATION": 1, "ROTATION": 2, "SCALE": 3,
+                })),
+            },
+            { "mesh": 0, "extensions": instanced(&json!({ "TRANSLATION": 4 })) },
+        ]);
+        let scene = load(&dir, &nodes).unwrap();
+
+        let node_transform = glam::Mat4::from_translation(glam::Vec3::new(0., 0., 5.));
+        let quarter_turn = glam::Quat::from_rotation_y(std::f32::consts::FRAC_PI_2);
+        let expected = [
+            glam::Mat4::from_scale_rotation_translation(
+                glam::Vec3::splat(2.),
+                glam::Quat::IDENTITY,
+                glam::Vec3::new(1., 2., 3.),
+            ),
+            glam::Mat4::from_scale_rotation_translation(
+                glam::Vec3::new(1., 0.5, 1.),
+                quarter_turn,
+                glam::Vec3::new(-1., 0., 0.),
+            ),
+            // only translated, by the unnormalized bytes
+            glam::Mat4::from_translation(glam::Vec3::new(3., 0., 0.)),
+            glam::Mat4::from_translation(glam::Vec3::new(0., -2., 0.)),
+        ];
+
+        assert_eq!(scene.info.instances.len(), expected.len());
+        for (idx, (instance, local_transform)) in
+            scene.info.instances.iter().zip(expected).enumerate()
+        {
+            assert_eq!(instance.primitive_index, 0);
+            assert_eq!(instance.node, Some(idx / 2));
+            assert!(
+                instance.local_transform.abs_diff_eq(local_transform, 1e-4),
+                "instance {idx} has the local transform {}",
+                instance.local_transform
+            );
+            assert!(
+                instance
+                    .transform
+                    .abs_diff_eq(node_transform * local_transform, 1e-4),
+                "instance {idx} has the transform {}",
+                instance.transform
+            );
+        }
+    }
+
+    #[test]
+    fn attribute_counts_must_match() {
+        let dir = TempDir::new("instancing-counts");
+        // three translations from the positions but two rotations
+        let nodes = json!([{
+            "mesh": 0,
+            "extensions": instanced(&json!({ "TRANSLATION": 0, "ROTATION": 2 })),
+        }]);
+        assert_eq!(
+            load(&dir, &nodes).err().unwrap().to_string(),
+            "Gpu instancing attributes of node #0 have different counts"
+        );
+    }
+}
@@ -0,0 +1,636 @@
+// Decoders for the bitstreams of EXT_meshopt_compression, following
+// https://github.com/KhronosGroup/glTF/tree/main/extensions/2.0/Vendor/EXT_meshopt_compression
+
+use serde::Deserialize;
+
+use crate::io::Result;
+
+pub const EXTENSION_NAME: &str = "EXT_meshopt_compression";
+
+const VERTEX_HEADER: u8 = 0xa0;
+const VERTEX_BLOCK_SIZE_BYTES: usize = 8192;
+const VERTEX_BLOCK_MAX_SIZE: usize = 256;
+const BYTE_GROUP_SIZE: usize = 16;
+const TAIL_MAX_SIZE: usize = 32;
+
+const INDEX_HEADER: u8 = 0xe0;
+const SEQUENCE_HEADER: u8 = 0xd0;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompressedView {
+    pub buffer: usize,
+    #[serde(default)]
+    pub byte_offset: usize,
+    pub byte_length: usize,
+    pub byte_stride: usize,
+    pub count: usize,
+    pub mode: Mode,
+    #[serde(default)]
+    pub filter: Filter,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Mode {
+    Attributes,
+    Triangles,
+    Indices,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Filter {
+    #[default]
+    None,
+    Octahedral,
+    Quaternion,
+    Exponential,
+}
+
+impl CompressedView {
+    pub fn decode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut decoded = match self.mode {
+            Mode::Attributes => decode_vertex_buffer(data, self.count, self.byte_stride),
+            Mode::Triangles => decode_index_buffer(data, self.count, self.byte_stride),
+            Mode::Indices => decode_index_sequence(data, self.count, self.byte_stride),
+        }
+        .ok_or("Malformed meshopt compressed buffer view")?;
+
+        let stride = self.byte_stride;
+        let filter = match self.filter {
+            Filter::None => None,
+            Filter::Octahedral => Some(("octahedral", matches!(stride, 4 | 8))),
+            Filter::Quaternion => Some(("quaternion", stride == 8)),
+            Filter::Exponential => Some(("exponential", stride.is_multiple_of(4))),
+        };
+        if let Some((name, false)) = filter {
+            return Err(format!("Invalid byte stride {stride} for the {name} filter").into());
+        }
+
+        match self.filter {
+            Filter::None => {}
+            Filter::Octahedral => decode_octahedral_filter(&mut decoded, stride),
+            Filter::Quaternion => decode_quaternion_filter(&mut decoded, stride),
+            Filter::Exponential => decode_exponential_filter(&mut decoded, stride),
+        }
+
+        Ok(decoded)
+    }
+}
+
+fn decode_vertex_buffer(data: &[u8], count: usize, stride: usize) -> Option<Vec<u8>> {
+    if stride == 0 || stride > 256 || !stride.is_multiple_of(4) {
+        return None;
+    }
+    if data.len() < 1 + stride || data[0] != VERTEX_HEADER {
+        return None;
+    }
+
+    // the tail holds the vertex that the deltas of the first block are relative to
+    let mut last_vertex = data[data.len() - stride..].to_vec();
+    let block_size =
+        ((VERTEX_BLOCK_SIZE_BYTES / stride) & !(BYTE_GROUP_SIZE - 1)).min(VERTEX_BLOCK_MAX_SIZE);
+
+    let mut decoded = vec![0; count * stride];
+    let mut bytes = [0; VERTEX_BLOCK_MAX_SIZE];
+    let mut position = 1;
+
+    for block_start in (0..count).step_by(block_size) {
+        let block_count = block_size.min(count - block_start);
+        let block_bytes = &mut bytes[..block_count.next_multiple_of(BYTE_GROUP_SIZE)];
+
+        // each byte of the vertex is stored as its own stream of deltas
+        for (k, last) in last_vertex.iter_mut().enumerate() {
+            position = decode_bytes(data, position, block_bytes)?;
+
+            for (i, &delta) in block_bytes[..block_count].iter().enumerate() {
+                *last = unzigzag8(delta).wrapping_add(*last);
+                decoded[(block_start + i) * stride + k] = *last;
+            }
+        }
+    }
+
+    (data.len() - position == stride.max(TAIL_MAX_SIZE)).then_some(decoded)
+}
+
+fn decode_bytes(data: &[u8], position: usize, bytes: &mut [u8]) -> Option<usize> {
+    // 2 bits of header per group of bytes
+    let header_size = (bytes.len() / BYTE_GROUP_SIZE).div_ceil(4);
+    let header = data.get(position..position + header_size)?;
+    let mut position = position + header_size;
+
+    for (group, group_bytes) in bytes.chunks_exact_mut(BYTE_GROUP_SIZE).enumerate() {
+        if data.len() - position < TAIL_MAX_SIZE {
+            return None;
+        }
+        let bits_log2 = (header[group / 4] >> ((group % 4) * 2)) & 3;
+        position = decode_bytes_group(data, position, group_bytes, bits_log2);
+    }
+
+    Some(position)
+}
+
+// relies on the tail of the stream for the bounds of the reads
+fn decode_bytes_group(data: &[u8], position: usize, bytes: &mut [u8], bits_log2: u8) -> usize {
+    match bits_log2 {
+        0 => {
+            bytes.fill(0);
+            position
+        }
+        3 => {
+            bytes.copy_from_slice(&data[position..position + BYTE_GROUP_SIZE]);
+            position + BYTE_GROUP_SIZE
+        }
+        _ => {
+            // packed 2 or 4 bit values, where all ones means the value follows as a whole byte
+            let bits = 1 << bits_log2;
+            let sentinel = (1 << bits) - 1;
+            let per_byte = 8 / bits;
+
+            let mut extra = position + BYTE_GROUP_SIZE / per_byte;
+            for (i, byte) in bytes.iter_mut().enumerate() {
+                let packed = data[position + i / per_byte];
+                let value = (packed >> (8 - bits * (i % per_byte + 1))) & sentinel;
+                if value == sentinel {
+                    *byte = data[extra];
+                    extra += 1;
+                } else {
+                    *byte = value;
+                }
+            }
+            extra
+        }
+    }
+}
+
+const fn unzigzag8(v: u8) -> u8 {
+    (v >> 1) ^ (v & 1).wrapping_neg()
+}
+
+fn decode_index_buffer(data: &[u8], count: usize, index_size: usize) -> Option<Vec<u8>> {
+    if !count.is_multiple_of(3) || !(index_size == 2 || index_size == 4) {
+        return None;
+    }
+    // header, 1 code byte per triangle and the codeaux table
+    if data.len() < 1 + count / 3 + 16 || data[0] & 0xf0 != INDEX_HEADER {
+        return None;
+    }
+    let version = data[0] & 0x0f;
+    if version > 1 {
+        return None;
+    }
+
+    let mut edge_fifo = Fifo::new([u32::MAX; 2]);
+    let mut vertex_fifo = Fifo::new(u32::MAX);
+
+    let mut next = 0_u32;
+    let mut last = 0_u32;
+
+    // version 1 encodes small deltas from the last free index in the code byte
+    let fec_limit = if version >= 1 { 13 } else { 15 };
+
+    let safe_end = data.len() - 16;
+    let codeaux_table = &data[safe_end..];
+    let codes = &data[1..=count / 3];
+    let mut position = 1 + count / 3;
+
+    let mut indices = Vec::with_capacity(count);
+    for &code in codes {
+        // each triangle reads at most 16 bytes, which the codeaux table pads for
+        if position > safe_end {
+            return None;
+        }
+
+        let [a, b, c] = if code < 0xf0 {
+            let fe = usize::from(code >> 4);
+            let [a, b] = edge_fifo.get(1 + fe);
+
+            let fec = code & 15;
+            let c = if fec < fec_limit {
+                let c = if fec == 0 {
+                    next
+                } else {
+                    vertex_fifo.get(1 + usize::from(fec))
+                };
+                next += u32::from(fec == 0);
+                vertex_fifo.push(c, fec == 0);
+                c
+            } else {
+                last = match fec {
+                    13 => last.wrapping_sub(1),
+                    14 => last.wrapping_add(1),
+                    _ => decode_index(data, &mut position, last)?,
+                };
+                vertex_fifo.push(last, true);
+                last
+            };
+
+            edge_fifo.push([c, b], true);
+            edge_fifo.push([a, c], true);
+            [a, b, c]
+        } else {
+            // `0xfe` and `0xff` store the codeaux byte inline and `0xff` also has a free first index
+            let codeaux = if code < 0xfe {
+                codeaux_table[usize::from(code & 15)]
+            } else {
+                let codeaux = data[position];
+                position += 1;
+                codeaux
+            };
+            let fea = if code == 0xff { 15 } else { 0 };
+            let feb = codeaux >> 4;
+            let fec = codeaux & 15;
+
+            let mut vertex = |fe: u8| match fe {
+                0 => {
+                    next += 1;
+                    next - 1
+                }
+                15 => 0,
+                _ => vertex_fifo.get(usize::from(fe)),
+            };
+            let mut a = vertex(fea);
+            let mut b = vertex(feb);
+            let mut c = vertex(fec);
+
+            for (fe, v) in [(fea, &mut a), (feb, &mut b), (fec, &mut c)] {
+                if fe == 15 {
+                    last = decode_index(data, &mut position, last)?;
+                    *v = last;
+                }
+            }
+
+            vertex_fifo.push(a, true);
+            vertex_fifo.push(b, feb == 0 || feb == 15);
+            vertex_fifo.push(c, fec == 0 || fec == 15);
+
+            edge_fifo.push([b, a], true);
+            edge_fifo.push([c, b], true);
+            edge_fifo.push([a, c], true);
+            [a, b, c]
+        };
+
+        indices.extend([a, b, c]);
+    }
+
+    (position == safe_end).then(|| write_indices(&indices, index_size))
+}
+
+// ring buffer of the 16 most recently pushed items
+struct Fifo<T> {
+    items: [T; 16],
+    offset: usize,
+}
+
+impl<T: Copy> Fifo<T> {
+    const fn new(fill: T) -> Self {
+        Self {
+            items: [fill; 16],
+            offset: 0,
+        }
+    }
+
+    // `advance` is false when the item only needs to be visible to the next lookup
+    fn push(&mut self, item: T, advance: bool) {
+        self.items[self.offset] = item;
+        self.offset = (self.offset + usize::from(advance)) & 15;
+    }
+
+    const fn get(&self, back: usize) -> T {
+        self.items[self.offset.wrapping_sub(back) & 15]
+    }
+}
+
+fn decode_index_sequence(data: &[u8], count: usize, index_size: usize) -> Option<Vec<u8>> {
+    if !(index_size == 2 || index_size == 4) {
+        return None;
+    }
+    // header, at least 1 byte per index and a 4 byte tail
+    if data.len() < 1 + count + 4 || data[0] & 0xf0 != SEQUENCE_HEADER || data[0] & 0x0f > 1 {
+        return None;
+    }
+
+    let safe_end = data.len() - 4;
+    let mut position = 1;
+    let mut last = [0_u32; 2];
+
+    let mut indices = Vec::with_capacity(count);
+    for _ in 0..count {
+        if position >= safe_end {
+            return None;
+        }
+        let v = decode_vbyte(data, &mut position)?;
+
+        // the lowest bit selects which of the two baselines the delta is relative to
+        let baseline = &mut last[(v & 1) as usize];
+        *baseline = baseline.wrapping_add(unzigzag32(v >> 1));
+        indices.push(*baseline);
+    }
+
+    (position == safe_end).then(|| write_indices(&indices, index_size))
+}
+
+fn decode_index(data: &[u8], position: &mut usize, last: u32) -> Option<u32> {
+    Some(last.wrapping_add(unzigzag32(decode_vbyte(data, position)?)))
+}
+
+fn decode_vbyte(data: &[u8], position: &mut usize) -> Option<u32> {
+    let mut result = 0;
+    // up to 5 groups of 7 bits
+    for shift in (0..35).step_by(7) {
+        let group = *data.get(*position)?;
+        *position += 1;
+        result |= u32::from(group & 127) << shift;
+        if group < 128 {
+            break;
+        }
+    }
+    Some(result)
+}
+
+const fn unzigzag32(v: u32) -> u32 {
+    (v >> 1) ^ (v & 1).wrapping_neg()
+}
+
+fn write_indices(indices: &[u32], index_size: usize) -> Vec<u8> {
+    if index_size == 2 {
+        indices
+            .iter()
+            .flat_map(|&index| (index as u16).to_le_bytes())
+            .collect()
+    } else {
+        indices
+            .iter()
+            .flat_map(|&index| index.to_le_bytes())
+            .collect()
+    }
+}
+
+fn decode_octahedral_filter(data: &mut [u8], stride: usize) {
+    match stride {
+        4 => {
+            for element in data.chunks_exact_mut(4) {
+                let xyz = decode_octahedral(
+                    [0, 1, 2].map(|i| f32::from(element[i] as i8)),
+                    f32::from(i8::MAX),
+                );
+                for (i, v) in xyz.into_iter().enumerate() {
+                    element[i] = (v as i8).to_le_bytes()[0];
+                }
+            }
+        }
+        8 => {
+            for element in data.chunks_exact_mut(8) {
+                let components = read_i16x4(element);
+                let xyz = decode_octahedral(
+                    [0, 1, 2].map(|i| f32::from(components[i])),
+                    f32::from(i16::MAX),
+                );
+                for (i, v) in xyz.into_iter().enumerate() {
+                    element[i * 2..i * 2 + 2].copy_from_slice(&(v as i16).to_le_bytes());
+                }
+            }
+        }
+        _ => panic!("Invalid byte stride {stride} for the octahedral filter"),
+    }
+}
+
+// reconstructs z and renormalizes to the signed integer range
+fn decode_octahedral([x, y, z]: [f32; 3], max: f32) -> [i32; 3] {
+    let z = z - x.abs() - y.abs();
+
+    // fixup for the lower hemisphere
+    let t = z.min(0.);
+    let x = if x >= 0. { x + t } else { x - t };
+    let y = if y >= 0. { y + t } else { y - t };
+
+    let scale = max / glam::Vec3::new(x, y, z).length();
+    [x, y, z].map(|v| round_to_int(v * scale))
+}
+
+fn decode_quaternion_filter(data: &mut [u8], stride: usize) {
+    assert_eq!(
+        stride, 8,
+        "Invalid byte stride {stride} for the quaternion filter"
+    );
+
+    let scale = std::f32::consts::FRAC_1_SQRT_2;
+    for element in data.chunks_exact_mut(8) {
+        let components = read_i16x4(element);
+
+        // the 4th component holds the index of the omitted (largest) component and the scale
+        let component_scale = scale / f32::from(components[3] | 3);
+        let [x, y, z] = [0, 1, 2].map(|i| f32::from(components[i]) * component_scale);
+        let w = (1. - glam::Vec3::new(x, y, z).length_squared())
+            .max(0.)
+            .sqrt();
+
+        let max_component = usize::from(element[6] & 3);
+        for (i, v) in [w, x, y, z].into_iter().enumerate() {
+            let offset = ((max_component + i) & 3) * 2;
+            element[offset..offset + 2]
+                .copy_from_slice(&(round_to_int(v * f32::from(i16::MAX)) as i16).to_le_bytes());
+        }
+    }
+}
+
+fn decode_exponential_filter(data: &mut [u8], stride: usize) {
+    assert_eq!(
+        stride % 4,
+        0,
+        "Invalid byte stride {stride} for the exponential filter"
+    );
+
+    for component in data.chunks_exact_mut(4) {
+        let v = i32::from_le_bytes(component.try_into().unwrap());
+
+        // 24 bit signed mantissa and 8 bit signed exponent
+        let mantissa = (v << 8) >> 8;
+        let exponent = v >> 24;
+        let value = f32::from_bits((exponent + 127).cast_unsigned() << 23) * mantissa as f32;
+
+        component.copy_from_slice(&value.to_le_bytes());
+    }
+}
+
+fn read_i16x4(element: &[u8]) -> [i16; 4] {
+    std::array::from_fn(|i| i16::from_le_bytes([element[i * 2], element[i * 2 + 1]]))
+}
+
+fn round_to_int(v: f32) -> i32 {
+    (v + 0.5_f32.copysign(v)) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // from meshoptimizer's tests, encoded with version 0
+    const INDEX_DATA: [u8; 27] = [
+        0xe0, 0xf0, 0x10, 0xfe, 0xff, 0xf0, 0x0c, 0xff, 0x02, 0x02, 0x02, 0x00, 0x76, 0x87, 0x56,
+        0x67, 0x78, 0xa9, 0x86, 0x65, 0x89, 0x68, 0x98, 0x01, 0x69, 0x00, 0x00,
+    ];
+    const INDICES: [u32; 12] = [0, 1, 2, 2, 1, 3, 4, 6, 5, 7, 8, 9];
+    const INDEX_SEQUENCE_DATA: [u8; 13] = [
+        0xd1, 0x00, 0x04, 0xcd, 0x01, 0x04, 0x07, 0x98, 0x1f, 0x00, 0x00, 0x00, 0x00,
+    ];
+    const INDEX_SEQUENCE: [u32; 6] = [0, 1, 51, 2, 49, 1000];
+
+    fn read_u32s(data: &[u8]) -> Vec<u32> {
+        data.chunks_exact(4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+            .collect()
+    }
+
+    fn read_i16s(data: &[u8]) -> Vec<i16> {
+        data.chunks_exact(2)
+            .map(|bytes| i16::from_le_bytes(bytes.try_into().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn attributes() {
+        // 4 vertices of 8 bytes, every byte with its own stream of zigzagged deltas
+        let mut data = vec![VERTEX_HEADER];
+        // 2 bit deltas 1, 1, -1, -1
+        data.extend([0x01, 0xa5, 0, 0, 0]);
+        // 4 bit deltas 2, 2, -2, -2
+        data.extend([0x02, 0x44, 0x33, 0, 0, 0, 0, 0, 0]);
+        // 2 bit deltas 3, 3, -3, -3, stored as whole bytes after the sentinels
+        data.extend([0x01, 0xff, 0, 0, 0, 6, 6, 5, 5]);
+        // raw deltas 4, 4, -4, -4
+        data.extend([0x03, 8, 8, 7, 7, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        // no deltas
+        data.extend([0x00; 4]);
+        // the tail ends with the vertex the first deltas are relative to
+        data.extend([0; 24]);
+        data.extend([10, 0, 0, 0, 9, 9, 9, 9]);
+
+        let decoded = decode_vertex_buffer(&data, 4, 8).unwrap();
+        assert_eq!(
+            decoded,
+            [
+                [11, 2, 3, 4, 9, 9, 9, 9],
+                [12, 4, 6, 8, 9, 9, 9, 9],
+                [11, 2, 3, 4, 9, 9, 9, 9],
+                [10, 0, 0, 0, 9, 9, 9, 9],
+            ]
+            .concat()
+        );
+
+        // truncated streams and invalid strides
+        assert!(decode_vertex_buffer(&data[..data.len() - 1], 4, 8).is_none());
+        assert!(decode_vertex_buffer(&data, 4, 6).is_none());
+    }
+
+    #[test]
+    fn triangles() {
+        let decoded = decode_index_buffer(&INDEX_DATA, INDICES.len(), 4).unwrap();
+        assert_eq!(read_u32s(&decoded), INDICES);
+
+        let decoded = decode_index_buffer(&INDEX_DATA, INDICES.len(), 2).unwrap();
+        assert_eq!(read_i16s(&decoded), INDICES.map(|index| index as i16));
+
+        assert!(
+            decode_index_buffer(&INDEX_DATA[..INDEX_DATA.len() - 1], INDICES.len(), 4).is_none()
+        );
+        assert!(decode_index_buffer(&INDEX_DATA, INDICES.len() - 1, 4).is_none());
+    }
+
+    #[test]
+    fn triangles_version_1() {
+        // a new triangle, then edges of the previous ones with an explicit index, the next index
+        // and the previous one, which version 0 would read from the fifo
+        let mut data = vec![INDEX_HEADER | 1, 0xf0, 0x0f, 0x0e, 0x0d, 0x0a];
+        data.extend([0; 16]);
+
+        let decoded = decode_index_buffer(&data, 12, 4).unwrap();
+        assert_eq!(read_u32s(&decoded), [0, 1, 2, 0, 2, 5, 0, 5, 6, 0, 6, 5]);
+
+        data[0] = INDEX_HEADER | 2;
+        assert!(decode_index_buffer(&data, 12, 4).is_none());
+    }
+
+    #[test]
+    fn index_sequence() {
+        let view: CompressedView = serde_json::from_value(serde_json::json!({
+            "buffer": 0,
+            "byteLength": INDEX_SEQUENCE_DATA.len(),
+            "byteStride": 4,
+            "count": INDEX_SEQUENCE.len(),
+            "mode": "INDICES",
+        }))
+        .unwrap();
+        assert_eq!(
+            read_u32s(&view.decode(&INDEX_SEQUENCE_DATA).unwrap()),
+            INDEX_SEQUENCE
+        );
+
+        assert!(
+            decode_index_sequence(&INDEX_SEQUENCE_DATA[1..], INDEX_SEQUENCE.len(), 4).is_none()
+        );
+    }
+
+    #[test]
+    fn octahedral_filter() {
+        // +x, and -z which is folded over the lower hemisphere, the last component is kept
+        let mut data = [127, 0, 127, 5, 127, 127, 127, 6].map(|v: i8| v.to_le_bytes()[0]);
+        decode_octahedral_filter(&mut data, 4);
+        assert_eq!(data.map(|v| v as i8), [127, 0, 0, 5, 0, 0, -127, 6]);
+
+        // 45 degrees between +x and +y
+        let mut data = [16383, 16383, 32766, 7]
+            .into_iter()
+            .flat_map(i16::to_le_bytes)
+            .collect::<Vec<_>>();
+        decode_octahedral_filter(&mut data, 8);
+        let component = (32767. * std::f32::consts::FRAC_1_SQRT_2).round() as i16;
+        assert_eq!(read_i16s(&data), [component, component, 0, 7]);
+    }
+
+    #[test]
+    fn quaternion_filter() {
+        // the identity, whose largest component w is omitted, and +x whose largest is x, both
+        // with 12 bits of precision
+        let mut data = [0, 0, 0, 0x0ffc | 3, 0, 0, 0, 0x0ffc]
+            .into_iter()
+            .flat_map(i16::to_le_bytes)
+            .collect::<Vec<_>>();
+        decode_quaternion_filter(&mut data, 8);
+        assert_eq!(read_i16s(&data), [0, 0, 0, 32767, 32767, 0, 0, 0]);
+
+        // (0, 0, 0.6, 0.8)
+        let z = (0.6 / std::f32::consts::FRAC_1_SQRT_2 * 4095.).round() as i16;
+        let mut data = [0, 0, z, 0x0ffc | 3]
+            .into_iter()
+            .flat_map(i16::to_le_bytes)
+            .collect::<Vec<_>>();
+        decode_quaternion_filter(&mut data, 8);
+        let decoded = read_i16s(&data);
+        assert_eq!(decoded[..2], [0, 0]);
+        assert!(
+            (f32::from(decoded[2]) / 32767. - 0.6).abs() < 1e-3,
+            "{decoded:?}"
+        );
+        assert!(
+            (f32::from(decoded[3]) / 32767. - 0.8).abs() < 1e-3,
+            "{decoded:?}"
+        );
+    }
+
+    #[test]
+    fn exponential_filter() {
+        // mantissa * 2^exponent
+        let mut data = [(-1 << 24) | 3, (2 << 24) | 0x00ff_fffb, 0]
+            .into_iter()
+            .flat_map(i32::to_le_bytes)
+            .collect::<Vec<_>>();
+        decode_exponential_filter(&mut data, 12);
+        let decoded = read_u32s(&data)
+            .into_iter()
+            .map(f32::from_bits)
+            .collect::<Vec<_>>();
+        assert_eq!(decoded, [1.5, -20., 0.]);
+    }
+}
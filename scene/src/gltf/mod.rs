@@ -0,0 +1,788 @@
+mod accessor;
+mod draco;
+pub mod export;
+mod instancing;
+mod meshopt;
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::BufReader,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use gltf::{
+    accessor::Dimensions,
+    buffer, image,
+    json::validation::{self, Validate},
+    mesh,
+    mesh::Semantic,
+    texture,
+};
+use rayon::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    io::{FileLoader, Result},
+    options::{ImportOptions, SceneSelector},
+    BoundingBox, Image, Instance, Material, Node, PrimitiveInfo, PrimitiveSize, Scene, TextureInfo,
+    Variant, Vertex,
+};
+
+// required extensions that are decoded here rather than by the gltf crate
+const SUPPORTED_EXTENSIONS: &[&str] = &[
+    "KHR_mesh_quantization",
+    draco::EXTENSION_NAME,
+    meshopt::EXTENSION_NAME,
+    instancing::EXTENSION_NAME,
+];
+
+pub struct Gltf;
+
+impl FileLoader for Gltf {
+    const SUPPORTED_EXTENSIONS: &'static [&'static str] = &["gltf", "glb"];
+
+    #[allow(clippy::too_many_lines)]
+    fn load(filename: impl AsRef<Path>, options: &ImportOptions) -> Result<Scene> {
+        let filename = filename.as_ref();
+        let filedir = filename.parent().unwrap_or_else(|| Path::new("./"));
+
+        let gltf::Gltf { document, blob } = read(filename)?;
+        let document = validate(document)?;
+        let buffers = load_buffers(&document, filedir, blob)?;
+
+        let selected_scene = match &options.scene {
+            Some(SceneSelector::Index(index)) => document
+                .scenes()
+                .nth(*index)
+                .ok_or_else(|| format!("No scene #{index} found"))?,
+            Some(SceneSelector::Name(name)) => document
+                .scenes()
+                .find(|scene| scene.name() == Some(name))
+                .ok_or_else(|| format!("No scene named {name:?} found"))?,
+            None => document
+                .default_scene()
+                .or_else(|| document.scenes().next())
+                .ok_or("No scenes found")?,
+        };
+
+        let mut scene = Scene::default();
+
+        scene.info.variants = document
+            .variants()
+            .into_iter()
+            .flatten()
+            .map(|variant| Variant {
+                name: variant.name().to_owned(),
+                materials: Vec::new(),
+            })
+            .collect();
+
+        // json image index -> loaded image index
+        let mut processed_images = HashMap::new();
+        let mut handle_image = |scene: &mut Scene, image: gltf::Image| {
+            *processed_images.entry(image.index()).or_insert_with(|| {
+                scene.data.images.push(Image {
+                    source: match image.source() {
+                        image::Source::Uri { uri, .. } => uri_path(filedir, uri),
+                        image::Source::View { .. } => unreachable!("rejected by `validate`"),
+                    },
+                });
+                scene.data.images.len() - 1
+            })
+        };
+
+        // json texture index -> loaded texture index
+        let mut processed_textures = HashMap::new();
+        let mut handle_texture = |scene: &mut Scene, tex_info: texture::Info| {
+            let texture = tex_info.texture();
+            *processed_textures
+                .entry(texture.index())
+                .or_insert_with(|| {
+                    let image_index = handle_image(scene, texture.source()) as _;
+                    scene.info.textures.push(TextureInfo { image_index });
+                    scene.info.textures.len() - 1
+                })
+        };
+
+        // json material index -> loaded material index
+        let mut processed_materials = HashMap::new();
+        let mut handle_material = |scene: &mut Scene, material: gltf::Material| {
+            *processed_materials
+                .entry(material.index().unwrap_or_default())
+                .or_insert_with(|| {
+                    let pbr = material.pbr_metallic_roughness();
+                    let color_texture = pbr
+                        .base_color_texture()
+                        .map_or(-1, |tex_info| handle_texture(scene, tex_info) as _);
+                    let emittance_texture = material
+                        .emissive_texture()
+                        .map_or(-1, |tex_info| handle_texture(scene, tex_info) as _);
+                    let metallic_roughness_texture = pbr
+                        .metallic_roughness_texture()
+                        .map_or(-1, |tex_info| handle_texture(scene, tex_info) as _);
+                    scene.data.materials.push(Material {
+                        color: glam::Vec4::from(pbr.base_color_factor()).truncate(),
+                        color_texture,
+                        emittance: glam::Vec3::from(material.emissive_factor())
+                            * material.emissive_strength().unwrap_or(1.),
+                        emittance_texture,
+                        metallic: pbr.metallic_factor(),
+                        roughness: pbr.roughness_factor(),
+                        metallic_roughness_texture,
+                    });
+                    scene.data.materials.len() - 1
+                })
+        };
+
+        // the geometry of every mesh of the scene is decoded in parallel, then added in order
+        let mut decoded_meshes = decode_meshes(selected_scene.nodes(), &document, &buffers)?;
+
+        let mut bounding_boxes = Vec::new();
+        let mut add_primitive =
+            |scene: &mut Scene, primitive: mesh::Primitive, decoded: DecodedPrimitive| {
+                let DecodedPrimitive {
+                    indices,
+                    vertices,
+                    colors,
+                    bounding_box,
+                } = decoded;
+
+                let material = handle_material(scene, primitive.material()) as _;
+
+                let primitive_index = scene.info.primitive_infos.len();
+                for mapping in primitive.mappings() {
+                    let material = handle_material(scene, mapping.material()) as _;
+                    for &variant in mapping.variants() {
+                        scene
+                            .info
+                            .variants
+                            .get_mut(variant as usize)
+                            .ok_or_else(|| format!("No material variant #{variant} found"))?
+                            .materials
+                            .push((primitive_index, material));
+                    }
+                }
+
+                // Add primitive to scene
+                let indices_offset = scene.data.indices.len() as u32;
+                scene.data.indices.extend(indices);
+                let indices_size = scene.data.indices.len() as u32 - indices_offset;
+
+                let vertices_offset = scene.data.vertices.len() as u32;
+                scene.data.vertices.extend(vertices);
+                let vertices_size = scene.data.vertices.len() as u32 - vertices_offset;
+
+                let colors_offset = colors.map_or_else(PrimitiveInfo::no_colors, |colors| {
+                    let colors_offset = scene.data.colors.len();
+                    scene.data.colors.extend(colors);
+                    colors_offset as _
+                });
+
+                scene.info.primitive_infos.push(PrimitiveInfo {
+                    indices_offset,
+                    vertices_offset,
+                    material,
+                    colors_offset,
+                });
+
+                scene.info.primitive_sizes.push(PrimitiveSize {
+                    indices_size,
+                    vertices_size,
+                });
+
+                bounding_boxes.push(bounding_box);
+                Result::Ok(())
+            };
+
+        // json mesh index -> loaded primitives range
+        let mut processed_meshes = HashMap::new();
+        let mut handle_mesh = |scene: &mut Scene, mesh: &mesh::Mesh| {
+            if let Some(primitives) = processed_meshes.get(&mesh.index()) {
+                return Result::Ok(Range::clone(primitives));
+            }
+            let primitives_start = scene.info.primitive_infos.len();
+            let decoded = decoded_meshes
+                .remove(&mesh.index())
+                .expect("Mesh wasn't decoded");
+            for (primitive, decoded) in mesh.primitives().zip(decoded) {
+                add_primitive(scene, primitive, decoded)?;
+            }
+            let primitives = primitives_start..scene.info.primitive_infos.len();
+            processed_meshes.insert(mesh.index(), primitives.clone());
+            Ok(primitives)
+        };
+
+        selected_scene.nodes().traverse(
+            None,
+            glam::Mat4::IDENTITY,
+            &mut |node: &gltf::scene::Node<'_>, parent, transform| {
+                let primitives = node
+                    .mesh()
+                    .map_or(Ok(0..0), |mesh| handle_mesh(&mut scene, &mesh))?;
+
+                scene.info.nodes.push(Node {
+                    name: node.name().unwrap_or_default().to_owned(),
+                    transform: glam::Mat4::from_cols_array_2d(&node.transform().matrix()),
+                    parent,
+                    primitives: primitives.clone(),
+                });
+                let node_index = scene.info.nodes.len() - 1;
+
+                let local_transforms = instancing::read_transforms(node, &document, &buffers)?
+                    .unwrap_or_else(|| vec![glam::Mat4::IDENTITY]);
+                for local_transform in local_transforms {
+                    scene
+                        .info
+                        .instances
+                        .extend(primitives.clone().map(|primitive_index| Instance {
+                            primitive_index,
+                            transform: transform * local_transform,
+                            node: Some(node_index),
+                            local_transform,
+                        }));
+                }
+
+                Ok(node_index)
+            },
+        )?;
+
+        scene.info.bounding_box = scene
+            .info
+            .instances
+            .iter()
+            .map(|instance| bounding_boxes[instance.primitive_index].transform(instance.transform))
+            .fold(BoundingBox::default(), BoundingBox::union);
+
+        Ok(scene)
+    }
+
+    fn dependencies(filename: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+        let filename = filename.as_ref();
+        let filedir = filename.parent().unwrap_or_else(|| Path::new("./"));
+
+        let document = read(filename)?.document;
+
+        // images are only referenced by path in the processed scene
+        Ok(std::iter::once(filename.to_owned())
+            .chain(
+                document
+                    .buffers()
+                    .filter_map(|buffer| match buffer.source() {
+                        buffer::Source::Uri(uri) if !uri.starts_with("data:") => {
+                            Some(uri_path(filedir, uri))
+                        }
+                        _ => None,
+                    }),
+            )
+            .collect())
+    }
+}
+
+fn read(filename: &Path) -> Result<gltf::Gltf> {
+    let file = File::open(filename).map_err(|error| format!("Couldn't open gltf file: {error}"))?;
+    gltf::Gltf::from_reader_without_validation(BufReader::new(file))
+        .map_err(|error| format!("Couldn't read gltf headers: {error}").into())
+}
+
+// the geometry of a primitive, read from its accessors
+struct DecodedPrimitive {
+    indices: Vec<u32>,
+    vertices: Vec<Vertex>,
+    colors: Option<Vec<glam::Vec4>>,
+    bounding_box: BoundingBox,
+}
+
+// json mesh index -> decoded primitives, of the meshes used by the nodes
+fn decode_meshes(
+    nodes: gltf::scene::iter::Nodes<'_>,
+    document: &gltf::Document,
+    buffers: &[buffer::Data],
+) -> Result<HashMap<usize, Vec<DecodedPrimitive>>> {
+    let mut meshes = HashMap::new();
+    let mut pending = nodes.collect::<Vec<_>>();
+    while let Some(node) = pending.pop() {
+        if let Some(mesh) = node.mesh() {
+            meshes.insert(mesh.index(), mesh);
+        }
+        pending.extend(node.children());
+    }
+
+    meshes
+        .into_par_iter()
+        .map(|(idx, mesh)| {
+            let primitives = mesh
+                .primitives()
+                .collect::<Vec<_>>()
+                .into_par_iter()
+                .map(|primitive| decode_primitive(&primitive, document, buffers))
+                .collect::<Result<_>>()?;
+            Ok((idx, primitives))
+        })
+        .collect()
+}
+
+fn decode_primitive(
+    primitive: &mesh::Primitive,
+    document: &gltf::Document,
+    buffers: &[buffer::Data],
+) -> Result<DecodedPrimitive> {
+    if primitive.mode() != mesh::Mode::Triangles {
+        return Err(format!("Unsupported primitive mode {:?}", primitive.mode()).into());
+    }
+
+    let draco = primitive
+        .extension_value(draco::EXTENSION_NAME)
+        .map(|extension| {
+            let compressed = draco::CompressedPrimitive::deserialize(extension)
+                .map_err(|error| format!("Invalid Draco compressed primitive: {error}"))?;
+            let data = document
+                .views()
+                .nth(compressed.buffer_view)
+                .and_then(|view| {
+                    buffers
+                        .get(view.buffer().index())?
+                        .get(view.offset()..view.offset() + view.length())
+                })
+                .ok_or("Draco compressed buffer view out of range of its buffer")?;
+            compressed.decode(data)
+        })
+        .transpose()?;
+
+    // attributes may be quantized or compressed, so they are read directly instead of through
+    // the gltf reader
+    let positions = read_attribute::<3>(primitive, &Semantic::Positions, draco.as_ref(), buffers)?
+        .ok_or("No positions found")?;
+    let normals = read_attribute(primitive, &Semantic::Normals, draco.as_ref(), buffers)?
+        .unwrap_or_else(|| vec![Default::default(); positions.len()]);
+    let [tex_coords0, tex_coords1] = [0, 1].map(|set| {
+        read_attribute(
+            primitive,
+            &Semantic::TexCoords(set),
+            draco.as_ref(),
+            buffers,
+        )
+        .map(|tex_coords| tex_coords.unwrap_or_else(|| vec![Default::default(); positions.len()]))
+    });
+    let (tex_coords0, tex_coords1) = (tex_coords0?, tex_coords1?);
+
+    let indices = if let Some(draco) = &draco {
+        draco.indices.clone()
+    } else {
+        accessor::check_bounds(&primitive.indices().ok_or("No indices found")?, buffers)?;
+        primitive
+            .reader(|buffer| Some(&buffers[buffer.index()]))
+            .read_indices()
+            .ok_or("No indices found")?
+            .into_u32()
+            .collect()
+    };
+
+    // computed rather than taken from the accessor, whose bounds may be quantized
+    let bounding_box = positions
+        .iter()
+        .fold(BoundingBox::default(), |bbox, &position| {
+            BoundingBox::new(bbox.min.min(position.into()), bbox.max.max(position.into()))
+        });
+
+    let vertices = positions
+        .into_iter()
+        .zip(normals)
+        .zip(tex_coords0)
+        .zip(tex_coords1)
+        .map(Vertex::from)
+        .collect::<Vec<_>>();
+
+    let colors = match primitive
+        .get(&Semantic::Colors(0))
+        .map(|colors| colors.dimensions())
+    {
+        Some(Dimensions::Vec3) => {
+            read_attribute::<3>(primitive, &Semantic::Colors(0), draco.as_ref(), buffers)?.map(
+                |colors| {
+                    colors
+                        .into_iter()
+                        .map(|color| glam::Vec3::from(color).extend(1.))
+                        .collect::<Vec<_>>()
+                },
+            )
+        }
+        _ => read_attribute::<4>(primitive, &Semantic::Colors(0), draco.as_ref(), buffers)?
+            .map(|colors| colors.into_iter().map(glam::Vec4::from).collect()),
+    };
+    if colors
+        .as_ref()
+        .is_some_and(|colors| colors.len() != vertices.len())
+    {
+        return Err("Vertex color count does not match vertex count".into());
+    }
+
+    Ok(DecodedPrimitive {
+        indices,
+        vertices,
+        colors,
+        bounding_box,
+    })
+}
+
+// uris are percent-encoded, and relative ones resolve against the gltf file
+fn uri_path(filedir: &Path, uri: &str) -> PathBuf {
+    filedir.join(urlencoding::decode(uri).as_deref().unwrap_or(uri))
+}
+
+// the attribute as floats, decoded from the Draco mesh if it's compressed
+fn read_attribute<const N: usize>(
+    primitive: &mesh::Primitive,
+    semantic: &Semantic,
+    draco: Option<&draco::DecodedMesh>,
+    buffers: &[buffer::Data],
+) -> Result<Option<Vec<[f32; N]>>> {
+    primitive
+        .get(semantic)
+        .map(|accessor| {
+            draco
+                .and_then(|draco| draco.read_f32(semantic, &accessor))
+                .unwrap_or_else(|| accessor::read_f32(&accessor, buffers))
+        })
+        .transpose()
+}
+
+fn validate(document: gltf::Document) -> Result<gltf::Document> {
+    let mut json = document.into_json();
+
+    for extension in &json.extensions_required {
+        if !SUPPORTED_EXTENSIONS.contains(&extension.as_str()) {
+            return Err(format!("Unsupported required gltf extension: {extension}").into());
+        }
+    }
+    // only referenced by path in the processed scene
+    if json.images.iter().any(|image| image.buffer_view.is_some()) {
+        return Err("Embedded images not supported".into());
+    }
+
+    json.extensions_required.clear();
+
+    // the accessors of Draco compressed primitives may have no buffer views of their own
+    let compressed = draco::compressed_accessors(&json)
+        .into_iter()
+        .map(|accessor| format!("accessors[{accessor}].bufferView"))
+        .collect::<HashSet<_>>();
+    let mut errors = Vec::new();
+    json.validate(&json, gltf::json::Path::new, &mut |path, error| {
+        let path = path();
+        if !(matches!(error, validation::Error::Missing) && compressed.contains(path.as_str())) {
+            errors.push((path, error));
+        }
+    });
+    if !errors.is_empty() {
+        return Err(format!("Invalid gltf file: {}", gltf::Error::Validation(errors)).into());
+    }
+
+    Ok(gltf::Document::from_json_without_validation(json))
+}
+
+fn load_buffers(
+    document: &gltf::Document,
+    filedir: &Path,
+    mut blob: Option<Vec<u8>>,
+) -> Result<Vec<buffer::Data>> {
+    let mut buffers = document
+        .buffers()
+        .map(|buffer| {
+            // fallback buffers only provide storage for decompressed buffer views
+            let is_fallback = buffer
+                .extension_value(meshopt::EXTENSION_NAME)
+                .and_then(|extension| extension.get("fallback"))
+                .and_then(serde_json::Value::as_bool)
+                .unwrap_or_default();
+            if is_fallback {
+                return Ok(buffer::Data(vec![0; buffer.length()]));
+            }
+
+            let data =
+                buffer::Data::from_source_and_blob(buffer.source(), Some(filedir), &mut blob)
+                    .map_err(|error| format!("Unable to read gltf buffers: {error}"))?;
+            if data.len() < buffer.length() {
+                return Err("Gltf buffer is too short".into());
+            }
+            Ok(data)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // decoded in parallel, then copied to the fallback buffers
+    let compressed = document
+        .views()
+        .filter_map(|view| {
+            let extension = view.extension_value(meshopt::EXTENSION_NAME)?;
+            Some(
+                meshopt::CompressedView::deserialize(extension)
+                    .map(|compressed| (view, compressed))
+                    .map_err(|error| format!("Invalid meshopt compressed buffer view: {error}")),
+            )
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let decoded = compressed
+        .into_par_iter()
+        .map(|(view, compressed)| {
+            let start = compressed.byte_offset;
+            let data = buffers
+                .get(compressed.buffer)
+                .and_then(|buffer| buffer.get(start..start + compressed.byte_length))
+                .ok_or("Meshopt compressed buffer view out of range of its buffer")?;
+            Ok((view, compressed.decode(data)?))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    for (view, decoded) in decoded {
+        let offset = view.offset();
+        buffers[view.buffer().index()]
+            .0
+            .get_mut(offset..offset + decoded.len())
+            .ok_or("Meshopt decompressed buffer view out of range of its buffer")?
+            .copy_from_slice(&decoded);
+    }
+
+    Ok(buffers)
+}
+
+// `f` receives each node with the loaded index of its parent and its global transform,
+// and returns the loaded index of the node, the traversal stops at its first error
+trait Traversable {
+    fn traverse(
+        self,
+        parent: Option<usize>,
+        transform: glam::Mat4,
+        f: &mut impl FnMut(&gltf::scene::Node<'_>, Option<usize>, glam::Mat4) -> Result<usize>,
+    ) -> Result<()>;
+}
+
+impl Traversable for gltf::scene::Node<'_> {
+    fn traverse(
+        self,
+        parent: Option<usize>,
+        transform: glam::Mat4,
+        f: &mut impl FnMut(&gltf::scene::Node<'_>, Option<usize>, glam::Mat4) -> Result<usize>,
+    ) -> Result<()> {
+        let global_transform =
+            transform * glam::Mat4::from_cols_array_2d(&self.transform().matrix());
+        let node_index = f(&self, parent, global_transform)?;
+        self.children()
+            .traverse(Some(node_index), global_transform, f)
+    }
+}
+
+macro_rules! impl_traversable {
+    ($t:ty) => {
+        impl Traversable for $t {
+            fn traverse(
+                self,
+                parent: Option<usize>,
+                transform: glam::Mat4,
+                f: &mut impl FnMut(
+                    &gltf::scene::Node<'_>,
+                    Option<usize>,
+                    glam::Mat4,
+                ) -> Result<usize>,
+            ) -> Result<()> {
+                self.into_iter()
+                    .try_for_each(|elem| elem.traverse(parent, transform, f))
+            }
+        }
+    };
+}
+impl_traversable!(gltf::scene::iter::Nodes<'_>);
+impl_traversable!(gltf::scene::iter::Children<'_>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate, testing::TempDir};
+
+    fn validate_required(extension: &str) -> std::result::Result<(), String> {
+        let json = format!(
+            r#"{{"asset": {{"version": "2.0"}}, "extensionsUsed": ["{extension}"], "extensionsRequired": ["{extension}"]}}"#
+        );
+        let document = gltf::Gltf::from_slice_without_validation(json.as_bytes())
+            .unwrap()
+            .document;
+        validate(document)
+            .map(|_| ())
+            .map_err(|error| error.to_string())
+    }
+
+    #[test]
+    fn required_extensions() {
+        for extension in SUPPORTED_EXTENSIONS {
+            assert_eq!(validate_required(extension), Ok(()));
+        }
+        assert_eq!(
+            validate_required("KHR_unknown"),
+            Err("Unsupported required gltf extension: KHR_unknown".to_owned())
+        );
+    }
+
+    #[test]
+    fn variants_out_of_range() {
+        let dir = TempDir::new("variants");
+        let filename = dir.join("cube.gltf");
+        export::save(&generate::cube(), &filename);
+
+        // a single variant, which the mapping of the first primitive points past
+        let mut json: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&filename).unwrap()).unwrap();
+        json["extensionsUsed"] = serde_json::json!(["KHR_materials_variants"]);
+        json["extensions"] = serde_json::json!({
+            "KHR_materials_variants": { "variants": [{ "name": "only" }] },
+        });
+        json["meshes"][0]["primitives"][0]["extensions"] = serde_json::json!({
+            "KHR_materials_variants": { "mappings": [{ "material": 0, "variants": [1] }] },
+        });
+        std::fs::write(&filename, json.to_string()).unwrap();
+
+        assert_eq!(
+            Gltf::load(&filename, &ImportOptions::default())
+                .err()
+                .unwrap()
+                .to_string(),
+            "No material variant #1 found"
+        );
+    }
+
+    #[test]
+    fn vertex_colors() {
+        let dir = TempDir::new("colors");
+        let filename = dir.join("cube.gltf");
+        export::save(&generate::cube(), &filename);
+
+        // float rgb, normalized byte and normalized short rgba colors for the 24 vertices
+        let mut data = Vec::new();
+        let mut expected = [Vec::new(), Vec::new(), Vec::new()];
+        for vertex in 0..24_u8 {
+            let red = f32::from(vertex) / 23.;
+            data.extend([red, 0.5, 1.].iter().flat_map(|value| value.to_le_bytes()));
+            expected[0].push(glam::Vec4::new(red, 0.5, 1., 1.));
+        }
+        for vertex in 0..24_u8 {
+            data.extend([vertex * 10, 255, 0, 51]);
+            expected[1].push(glam::Vec4::new(f32::from(vertex * 10) / 255., 1., 0., 0.2));
+        }
+        for vertex in 0..24_u16 {
+            let rgba = [vertex * 1000, u16::MAX, 0, 13107];
+            data.extend(rgba.iter().flat_map(|value| value.to_le_bytes()));
+            expected[2].push(glam::Vec4::new(
+                f32::from(vertex * 1000) / 65535.,
+                1.,
+                0.,
+                0.2,
+            ));
+        }
+        std::fs::write(dir.join("colors.bin"), &data).unwrap();
+
+        let mut json: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&filename).unwrap()).unwrap();
+        json["buffers"]
+            .as_array_mut()
+            .unwrap()
+            .push(serde_json::json!({ "uri": "colors.bin", "byteLength": data.len() }));
+        let first_view = json["bufferViews"].as_array().unwrap().len();
+        for (offset, length) in [(0, 288), (288, 96), (384, 192)] {
+            json["bufferViews"].as_array_mut().unwrap().push(
+                serde_json::json!({ "buffer": 1, "byteOffset": offset, "byteLength": length }),
+            );
+        }
+        let first_accessor = json["accessors"].as_array().unwrap().len();
+        for accessor in [
+            serde_json::json!({
+                "bufferView": first_view, "componentType": 5126, "count": 24, "type": "VEC3",
+            }),
+            serde_json::json!({
+                "bufferView": first_view + 1, "componentType": 5121, "normalized": true,
+                "count": 24, "type": "VEC4",
+            }),
+            serde_json::json!({
+                "bufferView": first_view + 2, "componentType": 5123, "normalized": true,
+                "count": 24, "type": "VEC4",
+            }),
+            // one color short
+            serde_json::json!({
+                "bufferView": first_view, "componentType": 5126, "count": 23, "type": "VEC3",
+            }),
+        ] {
+            json["accessors"].as_array_mut().unwrap().push(accessor);
+        }
+
+        let load = |accessor: usize| {
+            let mut json = json.clone();
+            json["meshes"][0]["primitives"][0]["attributes"]["COLOR_0"] = accessor.into();
+            std::fs::write(&filename, json.to_string()).unwrap();
+            Gltf::load(&filename, &ImportOptions::default())
+        };
+        for (idx, expected) in expected.iter().enumerate() {
+            let scene = load(first_accessor + idx).unwrap();
+            assert_eq!(scene.info.primitive_infos[0].colors_offset, 0);
+            assert_eq!(scene.data.colors.len(), expected.len());
+            for (color, expected) in scene.data.colors.iter().zip(expected) {
+                assert!(
+                    color.abs_diff_eq(*expected, 1e-6),
+                    "color {color} of format #{idx} should be {expected}"
+                );
+            }
+        }
+        assert_eq!(
+            load(first_accessor + 3).err().unwrap().to_string(),
+            "Vertex color count does not match vertex count"
+        );
+    }
+
+    #[test]
+    fn scene_selection() {
+        let dir = TempDir::new("scenes");
+        let filename = dir.join("cube.gltf");
+        export::save(&generate::cube(), &filename);
+
+        // a second scene with the cube moved along x
+        let mut json: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&filename).unwrap()).unwrap();
+        let mut moved = json["nodes"][0].clone();
+        moved["translation"] = serde_json::json!([5., 0., 0.]);
+        json["nodes"].as_array_mut().unwrap().push(moved);
+        json["scenes"] = serde_json::json!([
+            { "name": "Main", "nodes": [0] },
+            { "name": "Moved", "nodes": [1] },
+        ]);
+        std::fs::write(&filename, json.to_string()).unwrap();
+
+        let load = |scene: Option<SceneSelector>| {
+            let options = ImportOptions {
+                scene,
+                ..ImportOptions::default()
+            };
+            Gltf::load(&filename, &options).map(|scene| scene.info.bounding_box.center())
+        };
+        assert_eq!(load(None).unwrap(), glam::Vec3::ZERO);
+        for selector in ["1", "Moved"] {
+            assert_eq!(
+                load(Some(SceneSelector::parse(selector))).unwrap(),
+                glam::Vec3::new(5., 0., 0.)
+            );
+        }
+        assert_eq!(
+            load(Some(SceneSelector::parse("Main"))).unwrap(),
+            glam::Vec3::ZERO
+        );
+        assert_eq!(
+            load(Some(SceneSelector::Index(2)))
+                .err()
+                .unwrap()
+                .to_string(),
+            "No scene #2 found"
+        );
+        assert_eq!(
+            load(Some(SceneSelector::parse("Missing")))
+                .err()
+                .unwrap()
+                .to_string(),
+            "No scene named \"Missing\" found"
+        );
+    }
+}
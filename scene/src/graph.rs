@@ -0,0 +1,348 @@
+use crate::{BoundingBox, Scene};
+
+impl Scene {
+    pub fn find_nodes<'a>(&'a self, name: &'a str) -> impl Iterator<Item = usize> + 'a {
+        self.info
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(move |(_, node)| node.name == name)
+            .map(|(idx, _)| idx)
+    }
+
+    pub fn find_nodes_matching<'a>(&'a self, pattern: &'a str) -> impl Iterator<Item = usize> + 'a {
+        self.info
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(move |(_, node)| matches_pattern(pattern, &node.name))
+            .map(|(idx, _)| idx)
+    }
+
+    pub fn world_transform(&self, node: usize) -> glam::Mat4 {
+        let node = &self.info.nodes[node];
+        node.parent.map_or(node.transform, |parent| {
+            self.world_transform(parent) * node.transform
+        })
+    }
+
+    // returns a mask of the given node and all its descendants
+    pub fn subtree(&self, node: usize) -> Vec<bool> {
+        self.subtrees([node])
+    }
+
+    // returns a mask of the given nodes and all their descendants, parents come first so a
+    // single pass reaches every descendant
+    pub fn subtrees(&self, nodes: impl IntoIterator<Item = usize>) -> Vec<bool> {
+        let mut mask = vec![false; self.info.nodes.len()];
+        for node in nodes {
+            mask[node] = true;
+        }
+        for (idx, node) in self.info.nodes.iter().enumerate() {
+            if let Some(parent) = node.parent {
+                mask[idx] |= mask[parent];
+            }
+        }
+        mask
+    }
+
+    // removes the instances of every node with the given name and of their descendants
+    pub fn hide(&mut self, name: &str) -> usize {
+        firestorm::profile_method!(hide);
+
+        let hidden = self.subtrees(self.find_nodes(name));
+
+        let count = hidden.iter().filter(|&&hidden| hidden).count();
+        self.info
+            .instances
+            .retain(|instance| !instance.node.is_some_and(|node| hidden[node]));
+        self.update_bounding_box();
+        count
+    }
+
+    // keeps the instances of the nodes below a node matching one of the `include` patterns,
+    // or of all nodes if there are none, unless they are below one matching an `exclude` pattern
+    pub fn filter_nodes(&mut self, include: &[String], exclude: &[String]) -> usize {
+        firestorm::profile_method!(filter_nodes);
+
+        let included = if include.is_empty() {
+            vec![true; self.info.nodes.len()]
+        } else {
+            self.subtrees(
+                include
+                    .iter()
+                    .flat_map(|pattern| self.find_nodes_matching(pattern)),
+            )
+        };
+        let excluded = self.subtrees(
+            exclude
+                .iter()
+                .flat_map(|pattern| self.find_nodes_matching(pattern)),
+        );
+        let kept = included
+            .iter()
+            .zip(&excluded)
+            .map(|(&included, &excluded)| included && !excluded)
+            .collect::<Vec<_>>();
+
+        let count = kept.iter().filter(|&&kept| !kept).count();
+        self.info
+            .instances
+            .retain(|instance| instance.node.map_or(include.is_empty(), |node| kept[node]));
+        self.update_bounding_box();
+        count
+    }
+
+    // transforms the whole scene, e.g. to convert its units or up axis
+    pub fn transform(&mut self, transform: glam::Mat4) {
+        firestorm::profile_method!(transform);
+
+        for node in &mut self.info.nodes {
+            if node.parent.is_none() {
+                node.transform = transform * node.transform;
+            }
+        }
+        for instance in &mut self.info.instances {
+            instance.transform = transform * instance.transform;
+        }
+        for camera in &mut self.info.cameras {
+            *camera = camera.clone().transform(transform);
+        }
+        self.update_bounding_box();
+    }
+
+    // replaces the local transform of every node with the given name
+    pub fn set_transform(&mut self, name: &str, transform: glam::Mat4) -> usize {
+        firestorm::profile_method!(set_transform);
+
+        let nodes = self.find_nodes(name).collect::<Vec<_>>();
+        for &node in &nodes {
+            self.info.nodes[node].transform = transform;
+        }
+
+        let world_transforms = (0..self.info.nodes.len())
+            .scan(Vec::new(), |world_transforms: &mut Vec<glam::Mat4>, idx| {
+                let node = &self.info.nodes[idx];
+                let world = node.parent.map_or(node.transform, |parent| {
+                    world_transforms[parent] * node.transform
+                });
+                world_transforms.push(world);
+                Some(world)
+            })
+            .collect::<Vec<_>>();
+
+        for instance in &mut self.info.instances {
+            if let Some(node) = instance.node {
+                instance.transform = world_transforms[node] * instance.local_transform;
+            }
+        }
+        self.update_bounding_box();
+        nodes.len()
+    }
+
+    pub fn update_bounding_box(&mut self) {
+        firestorm::profile_method!(update_bounding_box);
+
+        let bounding_boxes = self
+            .info
+            .primitive_infos
+            .iter()
+            .zip(&self.info.primitive_sizes)
+            .map(|(info, size)| {
+                let start = info.vertices_offset as usize;
+                self.data.vertices[start..start + size.vertices_size as usize]
+                    .iter()
+                    .fold(BoundingBox::default(), |bbox, vertex| {
+                        let position = vertex.position.truncate();
+                        BoundingBox::new(bbox.min.min(position), bbox.max.max(position))
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        self.info.bounding_box = self
+            .info
+            .instances
+            .iter()
+            .map(|instance| bounding_boxes[instance.primitive_index].transform(instance.transform))
+            .fold(BoundingBox::default(), BoundingBox::union);
+    }
+}
+
+// `*` matches any sequence of characters and `?` any single character
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+
+    let (mut p, mut n) = (0, 0);
+    // position after the last `*` and the name position it currently matches up to
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p + 1, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_n)) => {
+                    backtrack = Some((star_p, star_n + 1));
+                    p = star_p;
+                    n = star_n + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::{self, Builder, Mesh};
+
+    // Car -> Body -> Wheel_L and Car -> Wheel_R, with Tree on its own, a unit cube each
+    fn car() -> Scene {
+        let mut builder = Builder::new();
+        let material = builder.material(generate::diffuse(glam::Vec3::ONE));
+        let mesh = Mesh::cube(glam::Vec3::ONE);
+        for (name, offset) in [
+            ("Car", glam::Vec3::ZERO),
+            ("Body", glam::Vec3::Y),
+            ("Wheel_L", glam::Vec3::NEG_X),
+            ("Wheel_R", glam::Vec3::X),
+            ("Tree", glam::Vec3::new(-5., 0., 0.)),
+        ] {
+            builder.add(name, &mesh, material, glam::Mat4::from_translation(offset));
+        }
+        let mut scene = builder.build();
+        for (node, parent) in [(1, 0), (2, 1), (3, 0)] {
+            scene.info.nodes[node].parent = Some(parent);
+        }
+        for idx in 0..scene.info.instances.len() {
+            scene.info.instances[idx].transform = scene.world_transform(idx);
+        }
+        scene.update_bounding_box();
+        scene
+    }
+
+    fn instance_nodes(scene: &Scene) -> Vec<usize> {
+        scene
+            .info
+            .instances
+            .iter()
+            .filter_map(|instance| instance.node)
+            .collect()
+    }
+
+    fn translation(scene: &Scene, node: usize) -> glam::Vec3 {
+        let instance = scene
+            .info
+            .instances
+            .iter()
+            .find(|instance| instance.node == Some(node))
+            .unwrap();
+        instance.transform.w_axis.truncate()
+    }
+
+    #[test]
+    fn patterns() {
+        for (pattern, name) in [
+            ("", ""),
+            ("*", ""),
+            ("*", "Wheel"),
+            ("Wheel_*", "Wheel_L"),
+            ("*_L", "Wheel_L"),
+            ("W?eel_?", "Wheel_R"),
+            ("*e*_*", "Wheel_L"),
+            ("a*b*c", "axxbyybc"),
+            ("**", "Tree"),
+        ] {
+            assert!(
+                matches_pattern(pattern, name),
+                "{pattern} should match {name}"
+            );
+        }
+        for (pattern, name) in [
+            ("", "Tree"),
+            ("?", ""),
+            ("Wheel", "Wheel_L"),
+            ("Wheel_?", "Wheel_LR"),
+            ("*_L", "Wheel_R"),
+            ("a*b*c", "axxcyyb"),
+        ] {
+            assert!(
+                !matches_pattern(pattern, name),
+                "{pattern} shouldn't match {name}"
+            );
+        }
+    }
+
+    #[test]
+    fn subtrees() {
+        let scene = car();
+        assert_eq!(scene.subtree(0), [true, true, true, true, false]);
+        assert_eq!(scene.subtree(1), [false, true, true, false, false]);
+        assert_eq!(scene.subtrees([2, 3]), [false, false, true, true, false]);
+        assert_eq!(scene.subtrees([1, 4]), [false, true, true, false, true]);
+        assert_eq!(scene.subtrees([]), [false; 5]);
+    }
+
+    #[test]
+    fn hide_subtree() {
+        let mut scene = car();
+        assert_eq!(translation(&scene, 2), glam::Vec3::new(-1., 1., 0.));
+        assert_eq!(scene.info.bounding_box.max, glam::Vec3::new(1.5, 1.5, 0.5));
+
+        assert_eq!(scene.hide("Body"), 2);
+        assert_eq!(instance_nodes(&scene), [0, 3, 4]);
+        assert_eq!(
+            scene.info.bounding_box.min,
+            glam::Vec3::new(-5.5, -0.5, -0.5)
+        );
+        assert_eq!(scene.info.bounding_box.max, glam::Vec3::new(1.5, 0.5, 0.5));
+
+        assert_eq!(scene.hide("Missing"), 0);
+        assert_eq!(instance_nodes(&scene), [0, 3, 4]);
+    }
+
+    #[test]
+    fn filter_nodes() {
+        let mut scene = car();
+        assert_eq!(
+            scene.filter_nodes(&["Car".to_owned()], &["Wheel_*".to_owned()]),
+            3
+        );
+        assert_eq!(instance_nodes(&scene), [0, 1]);
+
+        let mut scene = car();
+        assert_eq!(scene.filter_nodes(&[], &["T*".to_owned()]), 1);
+        assert_eq!(instance_nodes(&scene), [0, 1, 2, 3]);
+        assert_eq!(
+            scene.info.bounding_box.min,
+            glam::Vec3::new(-1.5, -0.5, -0.5)
+        );
+
+        let mut scene = car();
+        assert_eq!(scene.filter_nodes(&[], &[]), 0);
+        assert_eq!(instance_nodes(&scene), [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn set_transform() {
+        let mut scene = car();
+        let lifted = glam::Mat4::from_translation(glam::Vec3::new(0., 2., 0.));
+        assert_eq!(scene.set_transform("Body", lifted), 1);
+        assert_eq!(scene.info.nodes[1].transform, lifted);
+        // the descendants move with it, the others stay
+        assert_eq!(translation(&scene, 1), glam::Vec3::new(0., 2., 0.));
+        assert_eq!(translation(&scene, 2), glam::Vec3::new(-1., 2., 0.));
+        assert_eq!(translation(&scene, 3), glam::Vec3::X);
+        assert_eq!(scene.info.bounding_box.max, glam::Vec3::new(1.5, 2.5, 0.5));
+
+        assert_eq!(scene.set_transform("Missing", lifted), 0);
+    }
+}
@@ -1,27 +1,61 @@
 use std::{
     fs::File,
-    io::{BufReader, BufWriter},
-    path::Path,
+    io::{BufReader, BufWriter, Write},
+    path::{Component, Path, PathBuf},
 };
 
-use super::Scene;
+use super::{description::Description, gltf::Gltf, options::ImportOptions, Scene};
+
+// the errors of malformed or missing inputs, which a batch reports per file
+pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
 
 pub trait FileLoader {
     const SUPPORTED_EXTENSIONS: &'static [&'static str];
-    fn load(filename: impl AsRef<Path>) -> Scene;
+    fn load(filename: impl AsRef<Path>, options: &ImportOptions) -> Result<Scene>;
+    // files whose contents affect the loaded scene, including the file itself
+    fn dependencies(filename: impl AsRef<Path>) -> Result<Vec<PathBuf>>;
 
     fn can_load(filename: impl AsRef<Path>) -> bool {
-        let extension = filename
+        filename
             .as_ref()
             .extension()
             .and_then(|s| s.to_str())
-            .expect("No file extension found");
-        Self::SUPPORTED_EXTENSIONS.contains(&extension)
+            .is_some_and(|extension| Self::SUPPORTED_EXTENSIONS.contains(&extension))
     }
 }
 
 const FILE_EXTENSION: &str = "tsnasset";
 
+pub fn import(file: impl AsRef<Path>) -> Result<Scene> {
+    import_with_options(file, &ImportOptions::default())
+}
+
+pub fn import_with_options(file: impl AsRef<Path>, options: &ImportOptions) -> Result<Scene> {
+    firestorm::profile_fn!(scene_import);
+
+    let filepath = file.as_ref();
+    let mut scene = if Gltf::can_load(filepath) {
+        Gltf::load(filepath, options)?
+    } else if Description::can_load(filepath) {
+        Description::load(filepath, options)?
+    } else {
+        return Err(format!("No loader found for {}", filepath.display()).into());
+    };
+    options.apply(&mut scene)?;
+    Ok(scene)
+}
+
+pub fn dependencies(file: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+    let filepath = file.as_ref();
+    if Gltf::can_load(filepath) {
+        Gltf::dependencies(filepath)
+    } else if Description::can_load(filepath) {
+        Description::dependencies(filepath)
+    } else {
+        Err(format!("No loader found for {}", filepath.display()).into())
+    }
+}
+
 pub fn load(file: impl AsRef<Path>) -> Scene {
     firestorm::profile_fn!(scene_load);
 
@@ -32,14 +66,128 @@ pub fn load(file: impl AsRef<Path>) -> Scene {
     );
     let file = File::open(filepath).expect("Unable to open scene asset file");
     let reader = flate2::bufread::GzDecoder::new(BufReader::new(file));
-    rmp_serde::decode::from_read(reader).expect("Failed to load scene asset")
+    let mut scene: Scene =
+        rmp_serde::decode::from_read(reader).expect("Failed to load scene asset");
+
+    // stored relative to the asset, see `save`
+    let asset_dir = filepath.parent().unwrap_or_else(|| Path::new("./"));
+    for image in &mut scene.data.images {
+        image.source = asset_dir.join(&image.source);
+    }
+    scene
+}
+
+pub fn output_path(file: impl AsRef<Path>) -> PathBuf {
+    file.as_ref().with_extension(FILE_EXTENSION)
 }
 
-pub fn save(scene: &Scene, file: impl AsRef<Path>) {
-    let output_filename = file.as_ref().with_extension(FILE_EXTENSION);
-    let output_file = File::create(&output_filename).expect("Unable to open file for writing");
+// image sources are stored relative to the asset, so that it loads from any working directory
+pub fn save(mut scene: Scene, file: impl AsRef<Path>) -> Result<()> {
+    let output_filename = output_path(file);
+
+    let asset_dir = output_filename.parent().unwrap_or_else(|| Path::new("./"));
+    for image in &mut scene.data.images {
+        image.source = relative_path(&image.source, asset_dir);
+    }
+
+    let output_file = File::create(&output_filename).map_err(|error| {
+        format!(
+            "Unable to open {} for writing: {error}",
+            output_filename.display()
+        )
+    })?;
     let mut writer =
         flate2::write::GzEncoder::new(BufWriter::new(output_file), flate2::Compression::default());
-    rmp_serde::encode::write(&mut writer, &scene).expect("Failed to save processed asset");
-    println!("Asset processed and saved to {}", output_filename.display());
+    rmp_serde::encode::write(&mut writer, &scene)
+        .map_err(|error| format!("Failed to save processed asset: {error}"))?;
+    // errors of the last writes only surface here, and would leave a truncated asset behind
+    writer
+        .finish()
+        .and_then(|mut writer| writer.flush())
+        .map_err(|error| format!("Failed to save processed asset: {error}"))?;
+    Ok(())
+}
+
+// lexically, as the files may not exist yet, falling back to the absolute path
+pub(crate) fn relative_path(path: &Path, base: &Path) -> PathBuf {
+    let (Ok(path), Ok(base)) = (std::path::absolute(path), std::path::absolute(base)) else {
+        return path.to_owned();
+    };
+
+    let path_components = path.components().collect::<Vec<_>>();
+    let base_components = base.components().collect::<Vec<_>>();
+    let common = path_components
+        .iter()
+        .zip(&base_components)
+        .take_while(|(path, base)| path == base)
+        .count();
+    // nothing in common, e.g. on different drives
+    if common == 0 {
+        return path;
+    }
+
+    std::iter::repeat_n(Component::ParentDir, base_components.len() - common)
+        .chain(path_components[common..].iter().copied())
+        .collect()
+}
+
+pub fn export(scene: &Scene, file: impl AsRef<Path>) {
+    let filepath = file.as_ref();
+    assert!(
+        Gltf::can_load(filepath),
+        "Scenes can only be exported as gltf or glb"
+    );
+    super::gltf::export::save(scene, filepath);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate, testing::TempDir, Image, TextureInfo};
+
+    // the sources of a loaded asset resolve against its directory wherever it's moved to
+    #[test]
+    fn image_sources_follow_the_asset() {
+        let root = TempDir::new("io");
+        let (saved, moved) = (root.join("saved"), root.join("moved"));
+        std::fs::create_dir_all(saved.join("textures")).unwrap();
+
+        let mut scene = generate::cube();
+        scene.data.images.push(Image {
+            source: saved.join("textures/white.png"),
+        });
+        scene.info.textures.push(TextureInfo { image_index: 0 });
+        save(scene, saved.join("cube")).unwrap();
+
+        std::fs::rename(&saved, &moved).unwrap();
+        let scene = load(moved.join("cube.tsnasset"));
+        assert_eq!(
+            scene.data.images[0].source,
+            moved.join("textures/white.png")
+        );
+    }
+
+    #[test]
+    fn relative_paths() {
+        let base = Path::new("/assets/scenes");
+        for (path, relative) in [
+            ("/assets/scenes/white.png", "white.png"),
+            ("/assets/scenes/textures/white.png", "textures/white.png"),
+            ("/assets/textures/white.png", "../textures/white.png"),
+            ("/white.png", "../../white.png"),
+        ] {
+            assert_eq!(relative_path(Path::new(path), base), Path::new(relative));
+        }
+    }
+
+    #[test]
+    fn unknown_files_have_no_loader() {
+        for file in ["scene", "scene.obj"] {
+            assert_eq!(
+                import(file).err().unwrap().to_string(),
+                format!("No loader found for {file}")
+            );
+            assert!(dependencies(file).is_err());
+        }
+    }
 }
@@ -1,5 +1,16 @@
+pub mod batch;
+pub mod bvh;
+pub mod description;
+pub mod generate;
 pub mod gltf;
+pub mod graph;
 pub mod io;
+pub mod options;
+pub mod stats;
+#[cfg(test)]
+mod testing;
+pub mod validate;
+pub mod variants;
 
 use serde::{Deserialize, Serialize};
 
@@ -17,6 +28,8 @@ pub struct Data {
     pub vertices: Vec<Vertex>,
     pub materials: Vec<Material>,
     pub images: Vec<Image>,
+    #[serde(default)]
+    pub colors: Vec<glam::Vec4>,
 }
 
 #[derive(Default, Deserialize, Serialize)]
@@ -26,6 +39,19 @@ pub struct Info {
     pub instances: Vec<Instance>,
     pub textures: Vec<TextureInfo>,
     pub bounding_box: BoundingBox,
+    #[serde(default)]
+    pub environment: Environment,
+    #[serde(default)]
+    pub cameras: Vec<Camera>,
+    #[serde(default)]
+    pub render_settings: RenderSettings,
+    #[serde(default)]
+    pub nodes: Vec<Node>,
+    #[serde(default)]
+    pub variants: Vec<Variant>,
+    // problems found while importing, reported by `validate` rather than saved with the asset
+    #[serde(skip)]
+    pub import_warnings: Vec<String>,
 }
 
 #[derive(Default, Deserialize, Serialize)]
@@ -43,6 +69,27 @@ pub struct PrimitiveSize {
 pub struct Instance {
     pub primitive_index: usize,
     pub transform: glam::Mat4,
+    #[serde(default)]
+    pub node: Option<usize>,
+    // relative to the node, for nodes with several instances of their mesh
+    #[serde(default)]
+    pub local_transform: glam::Mat4,
+}
+
+// nodes are stored parents first, so `parent` is always less than the node's own index
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Node {
+    pub name: String,
+    pub transform: glam::Mat4,
+    pub parent: Option<usize>,
+    pub primitives: std::ops::Range<usize>,
+}
+
+// a named set of alternate materials, as (primitive index, material index) pairs
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Variant {
+    pub name: String,
+    pub materials: Vec<(usize, u32)>,
 }
 
 #[derive(Default, Deserialize, Serialize)]
@@ -50,12 +97,164 @@ pub struct TextureInfo {
     pub image_index: u32,
 }
 
+#[derive(Clone, Copy, Deserialize, Serialize)]
+pub struct Environment {
+    pub color: glam::Vec3,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Camera {
+    #[serde(default)]
+    pub name: String,
+    pub position: glam::Vec3,
+    pub target: glam::Vec3,
+    #[serde(default = "Camera::default_fov")]
+    pub fov: f32,
+}
+
+#[derive(Clone, Copy, Default, Deserialize, Serialize)]
+pub struct RenderSettings {
+    pub resolution: Option<(u32, u32)>,
+    pub min_bounces: Option<u32>,
+    pub max_bounces: Option<u32>,
+}
+
 #[derive(Clone, Copy, Deserialize, Serialize)]
 pub struct BoundingBox {
     pub min: glam::Vec3,
     pub max: glam::Vec3,
 }
 
+impl Scene {
+    pub fn merge(&mut self, other: Self, transform: glam::Mat4) {
+        firestorm::profile_method!(merge);
+
+        let Self { data, info } = other;
+
+        let vertices_offset = self.data.vertices.len() as u32;
+        let colors_offset = self.data.colors.len() as i32;
+        let indices_offset = self.data.indices.len() as u32;
+        let materials_offset = self.data.materials.len() as u32;
+        let images_offset = self.data.images.len() as u32;
+        let textures_offset = self.info.textures.len() as i32;
+        let primitives_offset = self.info.primitive_infos.len();
+        let nodes_offset = self.info.nodes.len();
+
+        let remap_texture = |texture: i32| {
+            if texture < 0 {
+                texture
+            } else {
+                texture + textures_offset
+            }
+        };
+
+        self.data.indices.extend(data.indices);
+        self.data.vertices.extend(data.vertices);
+        self.data.colors.extend(data.colors);
+        self.data
+            .materials
+            .extend(data.materials.into_iter().map(|material| Material {
+                color_texture: remap_texture(material.color_texture),
+                emittance_texture: remap_texture(material.emittance_texture),
+                metallic_roughness_texture: remap_texture(material.metallic_roughness_texture),
+                ..material
+            }));
+        self.data.images.extend(data.images);
+
+        self.info
+            .primitive_infos
+            .extend(
+                info.primitive_infos
+                    .into_iter()
+                    .map(|primitive| PrimitiveInfo {
+                        indices_offset: primitive.indices_offset + indices_offset,
+                        vertices_offset: primitive.vertices_offset + vertices_offset,
+                        material: primitive.material + materials_offset,
+                        colors_offset: if primitive.colors_offset < 0 {
+                            primitive.colors_offset
+                        } else {
+                            primitive.colors_offset + colors_offset
+                        },
+                    }),
+            );
+        self.info.primitive_sizes.extend(info.primitive_sizes);
+        self.info
+            .instances
+            .extend(info.instances.into_iter().map(|instance| Instance {
+                primitive_index: instance.primitive_index + primitives_offset,
+                transform: transform * instance.transform,
+                node: instance.node.map(|node| node + nodes_offset),
+                ..instance
+            }));
+        self.info
+            .nodes
+            .extend(info.nodes.into_iter().map(|node| Node {
+                transform: if node.parent.is_some() {
+                    node.transform
+                } else {
+                    transform * node.transform
+                },
+                parent: node.parent.map(|parent| parent + nodes_offset),
+                primitives: node.primitives.start + primitives_offset
+                    ..node.primitives.end + primitives_offset,
+                ..node
+            }));
+        for variant in info.variants {
+            let materials = variant.materials.into_iter().map(|(primitive, material)| {
+                (primitive + primitives_offset, material + materials_offset)
+            });
+            match self.info.find_variant(&variant.name) {
+                Some(idx) => self.info.variants[idx].materials.extend(materials),
+                None => self.info.variants.push(Variant {
+                    materials: materials.collect(),
+                    ..variant
+                }),
+            }
+        }
+        self.info
+            .textures
+            .extend(info.textures.into_iter().map(|texture| TextureInfo {
+                image_index: texture.image_index + images_offset,
+            }));
+        self.info.cameras.extend(
+            info.cameras
+                .into_iter()
+                .map(|camera| camera.transform(transform)),
+        );
+
+        self.info.bounding_box = self
+            .info
+            .bounding_box
+            .union(info.bounding_box.transform(transform));
+        self.info.import_warnings.extend(info.import_warnings);
+    }
+}
+
+impl Camera {
+    const fn default_fov() -> f32 {
+        45.
+    }
+
+    #[must_use]
+    pub fn transform(self, transform: glam::Mat4) -> Self {
+        Self {
+            position: transform.transform_point3(self.position),
+            target: transform.transform_point3(self.target),
+            ..self
+        }
+    }
+}
+
+impl RenderSettings {
+    // (min, max), russian roulette starts after the min
+    pub fn bounces(&self) -> (u32, u32) {
+        (
+            self.min_bounces.unwrap_or(shared::conf::MIN_BOUNCES),
+            self.max_bounces.unwrap_or(shared::conf::MAX_BOUNCES),
+        )
+    }
+}
+
 impl PrimitiveSize {
     pub const fn count(&self) -> u32 {
         self.indices_size / 3
@@ -72,9 +271,17 @@ impl BoundingBox {
 
     #[must_use]
     pub fn transform(self, transform: glam::Mat4) -> Self {
-        let a = (transform * self.min.extend(1.0)).truncate();
-        let b = (transform * self.max.extend(1.0)).truncate();
-        Self::new(a.min(b), a.max(b))
+        if self.is_empty() {
+            return self;
+        }
+        (0..8)
+            .map(|corner| {
+                let select = glam::BVec3::new(corner & 1 != 0, corner & 2 != 0, corner & 4 != 0);
+                transform.transform_point3(glam::Vec3::select(select, self.max, self.min))
+            })
+            .fold(Self::default(), |bbox, point| {
+                Self::new(bbox.min.min(point), bbox.max.max(point))
+            })
     }
 
     #[must_use]
@@ -89,6 +296,18 @@ impl BoundingBox {
     pub fn size(&self) -> glam::Vec3 {
         self.max - self.min
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.cmpgt(self.max).any()
+    }
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self {
+            color: glam::Vec3::ONE,
+        }
+    }
 }
 
 impl Default for BoundingBox {
@@ -96,3 +315,249 @@ impl Default for BoundingBox {
         Self::new(glam::Vec3::INFINITY, glam::Vec3::NEG_INFINITY)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+    use crate::generate;
+
+    // gives a generated scene a texture, vertex colors, a child node and a variant, which the
+    // generators don't produce, so that merging has every kind of index to remap
+    fn decorated(mut scene: Scene) -> Scene {
+        scene.data.images.push(Image {
+            source: "texture.png".into(),
+        });
+        scene.info.textures.push(TextureInfo { image_index: 0 });
+        scene.data.materials[0].color_texture = 0;
+
+        let vertices_size = scene.info.primitive_sizes[0].vertices_size as usize;
+        scene.data.colors = vec![glam::Vec4::ONE; vertices_size];
+        scene.info.primitive_infos[0].colors_offset = 0;
+
+        let transform = glam::Mat4::from_translation(glam::Vec3::Y);
+        scene.info.nodes.push(Node {
+            name: "Child".to_owned(),
+            transform,
+            parent: Some(0),
+            primitives: 0..1,
+        });
+        scene.info.instances.push(Instance {
+            primitive_index: 0,
+            transform: scene.world_transform(0) * transform,
+            node: Some(scene.info.nodes.len() - 1),
+            local_transform: glam::Mat4::IDENTITY,
+        });
+
+        scene.data.materials.push(generate::diffuse(glam::Vec3::X));
+        scene.info.variants.push(Variant {
+            name: "Red".to_owned(),
+            materials: vec![(0, scene.data.materials.len() as u32 - 1)],
+        });
+
+        scene.update_bounding_box();
+        scene
+    }
+
+    #[test]
+    #[allow(clippy::too_many_lines)]
+    fn merge_remaps_indices() {
+        let mut scene = decorated(generate::cornell_box());
+        let original = decorated(generate::cornell_box());
+        // merged and compared against
+        let other = decorated(generate::cube());
+        let expected = decorated(generate::cube());
+
+        let transform = glam::Mat4::from_scale_rotation_translation(
+            glam::Vec3::splat(2.),
+            glam::Quat::from_rotation_y(FRAC_PI_2),
+            glam::Vec3::new(10., 0., 0.),
+        );
+        scene.merge(other, transform);
+
+        let indices_offset = original.data.indices.len() as u32;
+        let vertices_offset = original.data.vertices.len() as u32;
+        let colors_offset = original.data.colors.len() as i32;
+        let materials_offset = original.data.materials.len() as u32;
+        let images_offset = original.data.images.len() as u32;
+        let textures_offset = original.info.textures.len() as i32;
+        let primitives_offset = original.info.primitive_infos.len();
+        let nodes_offset = original.info.nodes.len();
+
+        assert_eq!(
+            &scene.data.indices[..indices_offset as usize],
+            original.data.indices
+        );
+        assert_eq!(
+            &scene.data.indices[indices_offset as usize..],
+            expected.data.indices
+        );
+        assert_eq!(
+            scene.data.vertices.len(),
+            original.data.vertices.len() + expected.data.vertices.len()
+        );
+        assert_eq!(
+            scene.data.colors.len(),
+            original.data.colors.len() + expected.data.colors.len()
+        );
+        assert_eq!(
+            scene.data.images.len(),
+            original.data.images.len() + expected.data.images.len()
+        );
+
+        for (merged, primitive) in scene.info.primitive_infos[primitives_offset..]
+            .iter()
+            .zip(&expected.info.primitive_infos)
+        {
+            assert_eq!(
+                merged.indices_offset,
+                primitive.indices_offset + indices_offset
+            );
+            assert_eq!(
+                merged.vertices_offset,
+                primitive.vertices_offset + vertices_offset
+            );
+            assert_eq!(merged.material, primitive.material + materials_offset);
+            if primitive.colors_offset < 0 {
+                assert_eq!(merged.colors_offset, primitive.colors_offset);
+            } else {
+                assert_eq!(
+                    merged.colors_offset,
+                    primitive.colors_offset + colors_offset
+                );
+            }
+        }
+        assert_eq!(
+            scene.info.primitive_infos.len(),
+            primitives_offset + expected.info.primitive_infos.len()
+        );
+        assert_eq!(
+            scene.info.primitive_sizes.len(),
+            scene.info.primitive_infos.len()
+        );
+
+        for (merged, material) in scene.data.materials[materials_offset as usize..]
+            .iter()
+            .zip(&expected.data.materials)
+        {
+            assert_eq!(merged.color, material.color);
+            for (merged, texture) in [
+                (merged.color_texture, material.color_texture),
+                (merged.emittance_texture, material.emittance_texture),
+                (
+                    merged.metallic_roughness_texture,
+                    material.metallic_roughness_texture,
+                ),
+            ] {
+                let expected = if texture < 0 {
+                    texture
+                } else {
+                    texture + textures_offset
+                };
+                assert_eq!(merged, expected);
+            }
+        }
+        assert_eq!(
+            scene.data.materials[materials_offset as usize].color_texture,
+            1
+        );
+
+        for (merged, texture) in scene.info.textures[original.info.textures.len()..]
+            .iter()
+            .zip(&expected.info.textures)
+        {
+            assert_eq!(merged.image_index, texture.image_index + images_offset);
+        }
+
+        let instances_offset = original.info.instances.len();
+        assert_eq!(
+            scene.info.instances.len(),
+            instances_offset + expected.info.instances.len()
+        );
+        for (merged, instance) in scene.info.instances[instances_offset..]
+            .iter()
+            .zip(&expected.info.instances)
+        {
+            assert_eq!(
+                merged.primitive_index,
+                instance.primitive_index + primitives_offset
+            );
+            assert_eq!(merged.node, instance.node.map(|node| node + nodes_offset));
+            assert!(merged
+                .transform
+                .abs_diff_eq(transform * instance.transform, 1e-5));
+            assert_eq!(merged.local_transform, instance.local_transform);
+        }
+
+        assert_eq!(
+            scene.info.nodes.len(),
+            nodes_offset + expected.info.nodes.len()
+        );
+        for (merged, node) in scene.info.nodes[nodes_offset..]
+            .iter()
+            .zip(&expected.info.nodes)
+        {
+            assert_eq!(merged.name, node.name);
+            assert_eq!(
+                merged.parent,
+                node.parent.map(|parent| parent + nodes_offset)
+            );
+            assert_eq!(
+                merged.primitives,
+                node.primitives.start + primitives_offset..node.primitives.end + primitives_offset
+            );
+            // only root nodes are moved, children stay relative to them
+            let expected_transform = if node.parent.is_some() {
+                node.transform
+            } else {
+                transform * node.transform
+            };
+            assert!(merged.transform.abs_diff_eq(expected_transform, 1e-5));
+        }
+        // the node hierarchy and the instances still agree after the merge
+        for instance in &scene.info.instances {
+            let node = instance.node.unwrap();
+            assert!(instance
+                .transform
+                .abs_diff_eq(scene.world_transform(node) * instance.local_transform, 1e-5));
+        }
+
+        // variants with the same name are merged into one
+        assert_eq!(scene.info.variants.len(), 1);
+        assert_eq!(
+            scene.info.variants[0].materials,
+            [
+                original.info.variants[0].materials[0],
+                (
+                    primitives_offset,
+                    expected.info.variants[0].materials[0].1 + materials_offset
+                ),
+            ]
+        );
+
+        assert_eq!(
+            scene.info.cameras.len(),
+            original.info.cameras.len() + expected.info.cameras.len()
+        );
+        let camera = scene.info.cameras.last().unwrap();
+        assert!(camera.position.abs_diff_eq(
+            transform.transform_point3(expected.info.cameras[0].position),
+            1e-5
+        ));
+
+        // the cornell box spans [-1, 1] x [0, 2] x [-1, 1], and the cube and its child one unit
+        // above [-0.5, 0.5] x [-0.5, 1.5] x [-0.5, 0.5] before being scaled, turned and moved
+        let bounding_box = scene.info.bounding_box;
+        let (min, max) = (glam::Vec3::new(-1., -1., -1.), glam::Vec3::new(11., 3., 1.));
+        assert!(
+            bounding_box.min.abs_diff_eq(min, 1e-5) && bounding_box.max.abs_diff_eq(max, 1e-5),
+            "merged scene is bounded by {:?} {:?}, expected {min:?} {max:?}",
+            bounding_box.min,
+            bounding_box.max
+        );
+        scene.update_bounding_box();
+        assert!(scene.info.bounding_box.min.abs_diff_eq(min, 1e-5));
+        assert!(scene.info.bounding_box.max.abs_diff_eq(max, 1e-5));
+    }
+}
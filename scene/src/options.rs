@@ -0,0 +1,290 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{io::Result, Scene};
+
+// options applied while importing a source file, e.g. in a description
+//
+// import = { scene = "Main", exclude = ["Camera*"], up_axis = "z", units = "cm", fit = 2.0 }
+#[derive(Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ImportOptions {
+    // gltf scene to import instead of the default one
+    pub scene: Option<SceneSelector>,
+    // node name patterns, where `*` matches any sequence of characters and `?` any single one
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    #[serde(default)]
+    pub up_axis: UpAxis,
+    #[serde(default)]
+    pub units: Units,
+    // uniformly rescales the scene so that its largest extent has this size
+    pub fit: Option<f32>,
+    // moves the center of the bounding box to the origin
+    #[serde(default)]
+    pub recenter: bool,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum SceneSelector {
+    Index(usize),
+    Name(String),
+}
+
+#[derive(Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpAxis {
+    #[default]
+    Y,
+    Z,
+}
+
+#[derive(Clone, Copy, Default, Deserialize, Serialize)]
+pub enum Units {
+    #[default]
+    #[serde(rename = "m")]
+    Meters,
+    #[serde(rename = "cm")]
+    Centimeters,
+    #[serde(rename = "mm")]
+    Millimeters,
+    #[serde(rename = "in")]
+    Inches,
+    #[serde(rename = "ft")]
+    Feet,
+}
+
+impl ImportOptions {
+    // applies everything but the scene selection, which is up to the loader
+    pub fn apply(&self, scene: &mut Scene) -> Result<()> {
+        firestorm::profile_method!(apply);
+
+        for pattern in self.include.iter().chain(&self.exclude) {
+            if scene.find_nodes_matching(pattern).next().is_none() {
+                return Err(format!("No node matches {pattern:?}").into());
+            }
+        }
+        if !self.include.is_empty() || !self.exclude.is_empty() {
+            scene.filter_nodes(&self.include, &self.exclude);
+        }
+
+        let conversion =
+            glam::Mat4::from_scale(glam::Vec3::splat(self.units.meters())) * self.up_axis.to_y_up();
+        if conversion != glam::Mat4::IDENTITY {
+            scene.transform(conversion);
+        }
+
+        if let Some(size) = self.fit {
+            let extent = scene.info.bounding_box.size().max_element();
+            if extent <= 0. {
+                return Err("Can't fit a scene without extent".into());
+            }
+            scene.transform(glam::Mat4::from_scale(glam::Vec3::splat(size / extent)));
+        }
+
+        if self.recenter {
+            if scene.info.bounding_box.is_empty() {
+                return Err("Can't recenter an empty scene".into());
+            }
+            scene.transform(glam::Mat4::from_translation(
+                -scene.info.bounding_box.center(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl SceneSelector {
+    pub fn parse(selector: &str) -> Self {
+        selector
+            .parse()
+            .map_or_else(|_| Self::Name(selector.to_owned()), Self::Index)
+    }
+}
+
+impl UpAxis {
+    pub fn parse(axis: &str) -> Option<Self> {
+        match axis {
+            "y" | "Y" => Some(Self::Y),
+            "z" | "Z" => Some(Self::Z),
+            _ => None,
+        }
+    }
+
+    fn to_y_up(self) -> glam::Mat4 {
+        match self {
+            Self::Y => glam::Mat4::IDENTITY,
+            // (x, y, z) -> (x, z, -y)
+            Self::Z => glam::Mat4::from_rotation_x(-std::f32::consts::FRAC_PI_2),
+        }
+    }
+}
+
+impl Units {
+    pub fn parse(units: &str) -> Option<Self> {
+        match units {
+            "m" => Some(Self::Meters),
+            "cm" => Some(Self::Centimeters),
+            "mm" => Some(Self::Millimeters),
+            "in" => Some(Self::Inches),
+            "ft" => Some(Self::Feet),
+            _ => None,
+        }
+    }
+
+    const fn meters(self) -> f32 {
+        match self {
+            Self::Meters => 1.,
+            Self::Centimeters => 0.01,
+            Self::Millimeters => 0.001,
+            Self::Inches => 0.0254,
+            Self::Feet => 0.3048,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate, BoundingBox};
+
+    fn applied(mut scene: Scene, options: &ImportOptions) -> BoundingBox {
+        options.apply(&mut scene).unwrap();
+        scene.info.bounding_box
+    }
+
+    fn assert_bounds(bounding_box: &BoundingBox, min: glam::Vec3, max: glam::Vec3) {
+        assert!(
+            bounding_box.min.abs_diff_eq(min, 1e-5) && bounding_box.max.abs_diff_eq(max, 1e-5),
+            "expected {min}..{max}, got {}..{}",
+            bounding_box.min,
+            bounding_box.max
+        );
+    }
+
+    #[test]
+    fn z_up() {
+        // the plane lies in xz and ends up in xy
+        let options = ImportOptions {
+            up_axis: UpAxis::Z,
+            ..ImportOptions::default()
+        };
+        assert_bounds(
+            &applied(generate::plane(), &options),
+            glam::Vec3::new(-1., -1., 0.),
+            glam::Vec3::new(1., 1., 0.),
+        );
+    }
+
+    #[test]
+    fn units() {
+        for (units, meters) in [
+            (Units::Meters, 1.),
+            (Units::Centimeters, 0.01),
+            (Units::Millimeters, 0.001),
+            (Units::Inches, 0.0254),
+            (Units::Feet, 0.3048),
+        ] {
+            let options = ImportOptions {
+                units,
+                ..ImportOptions::default()
+            };
+            assert_bounds(
+                &applied(generate::cube(), &options),
+                glam::Vec3::splat(-0.5 * meters),
+                glam::Vec3::splat(0.5 * meters),
+            );
+        }
+    }
+
+    #[test]
+    fn fit_and_recenter() {
+        let fit = ImportOptions {
+            fit: Some(4.),
+            ..ImportOptions::default()
+        };
+        assert_bounds(
+            &applied(generate::plane(), &fit),
+            glam::Vec3::new(-2., 0., -2.),
+            glam::Vec3::new(2., 0., 2.),
+        );
+
+        // the cornell box stands on the floor at y = 0
+        let recenter = ImportOptions {
+            recenter: true,
+            ..ImportOptions::default()
+        };
+        assert_bounds(
+            &applied(generate::cornell_box(), &recenter),
+            glam::Vec3::splat(-1.),
+            glam::Vec3::splat(1.),
+        );
+    }
+
+    // the conversion comes first so that fit gives the final size, whatever the units
+    #[test]
+    fn order() {
+        let options = ImportOptions {
+            up_axis: UpAxis::Z,
+            units: Units::Centimeters,
+            fit: Some(1.),
+            recenter: true,
+            ..ImportOptions::default()
+        };
+        assert_bounds(
+            &applied(generate::cornell_box(), &options),
+            glam::Vec3::splat(-0.5),
+            glam::Vec3::splat(0.5),
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let error = |scene: Scene, options: ImportOptions| {
+            let mut scene = scene;
+            options.apply(&mut scene).err().unwrap().to_string()
+        };
+        assert_eq!(
+            error(
+                generate::cube(),
+                ImportOptions {
+                    exclude: vec!["Missing*".to_owned()],
+                    ..ImportOptions::default()
+                }
+            ),
+            "No node matches \"Missing*\""
+        );
+        assert_eq!(
+            error(
+                Scene::default(),
+                ImportOptions {
+                    fit: Some(1.),
+                    ..ImportOptions::default()
+                }
+            ),
+            "Can't fit a scene without extent"
+        );
+        assert_eq!(
+            error(
+                Scene::default(),
+                ImportOptions {
+                    recenter: true,
+                    ..ImportOptions::default()
+                }
+            ),
+            "Can't recenter an empty scene"
+        );
+    }
+
+    #[test]
+    fn scene_selectors() {
+        assert!(matches!(SceneSelector::parse("1"), SceneSelector::Index(1)));
+        assert!(matches!(
+            SceneSelector::parse("Main"),
+            SceneSelector::Name(name) if name == "Main"
+        ));
+    }
+}
@@ -0,0 +1,277 @@
+use std::{mem::size_of, path::PathBuf};
+
+use serde::Serialize;
+
+use crate::{BoundingBox, Material, PrimitiveInfo, Scene, SceneDesc, Vertex};
+
+// Bytes per texel of the images uploaded by the renderer (RGBA8, no mips)
+const IMAGE_TEXEL_SIZE: u64 = 4;
+
+// acceleration structure sizes are only known to the driver, these are rough estimates from
+// typical upper bounds of what they report per triangle and per instance before compaction
+const BLAS_TRIANGLE_SIZE: u64 = 64;
+const BLAS_SCRATCH_TRIANGLE_SIZE: u64 = 32;
+const TLAS_INSTANCE_SIZE: u64 = 128;
+const TLAS_SCRATCH_INSTANCE_SIZE: u64 = 64;
+// VkAccelerationStructureInstanceKHR, uploaded to build the TLAS
+const INSTANCE_SIZE: u64 = 64;
+
+#[derive(Serialize)]
+pub struct Stats {
+    pub vertices: usize,
+    pub indices: usize,
+    pub triangles: usize,
+    pub primitives: usize,
+    pub instances: usize,
+    pub nodes: usize,
+    pub instanced_triangles: usize,
+    // instances of primitives that don't exist, left out of the instanced triangles
+    pub invalid_instances: usize,
+    pub materials: usize,
+    pub textures: usize,
+    pub images: usize,
+    pub variants: usize,
+    pub bounding_box: BoundingBox,
+    pub material_usage: Vec<MaterialUsage>,
+    pub gpu_memory: GpuMemory,
+}
+
+#[derive(Serialize)]
+pub struct MaterialUsage {
+    pub material: usize,
+    pub primitives: usize,
+    pub color_texture: Option<u32>,
+    pub emittance_texture: Option<u32>,
+    pub metallic_roughness_texture: Option<u32>,
+}
+
+#[derive(Serialize)]
+pub struct GpuMemory {
+    pub vertices: u64,
+    pub colors: u64,
+    pub indices: u64,
+    pub primitives: u64,
+    pub materials: u64,
+    pub scene_desc: u64,
+    pub images: u64,
+    pub blases: u64,
+    pub tlas: u64,
+    pub scratch: u64,
+    pub total: u64,
+    pub unreadable_images: Vec<PathBuf>,
+}
+
+impl Stats {
+    pub fn compute(scene: &Scene) -> Self {
+        firestorm::profile_method!(compute);
+
+        let mut invalid_instances = 0;
+        let instanced_triangles = scene
+            .info
+            .instances
+            .iter()
+            .filter_map(|instance| {
+                let size = scene.info.primitive_sizes.get(instance.primitive_index);
+                if size.is_none() {
+                    invalid_instances += 1;
+                }
+                Some(size?.count() as usize)
+            })
+            .sum();
+
+        let material_usage = scene
+            .data
+            .materials
+            .iter()
+            .enumerate()
+            .map(|(material, info)| MaterialUsage::new(scene, material, info))
+            .collect();
+
+        Self {
+            vertices: scene.data.vertices.len(),
+            indices: scene.data.indices.len(),
+            triangles: scene.data.indices.len() / 3,
+            primitives: scene.info.primitive_infos.len(),
+            instances: scene.info.instances.len(),
+            nodes: scene.info.nodes.len(),
+            instanced_triangles,
+            invalid_instances,
+            materials: scene.data.materials.len(),
+            textures: scene.info.textures.len(),
+            images: scene.data.images.len(),
+            variants: scene.info.variants.len(),
+            bounding_box: scene.info.bounding_box,
+            material_usage,
+            gpu_memory: GpuMemory::estimate(scene),
+        }
+    }
+}
+
+impl MaterialUsage {
+    fn new(scene: &Scene, material: usize, info: &Material) -> Self {
+        let texture = |index: i32| u32::try_from(index).ok();
+        Self {
+            material,
+            primitives: scene
+                .info
+                .primitive_infos
+                .iter()
+                .filter(|primitive| primitive.material as usize == material)
+                .count(),
+            color_texture: texture(info.color_texture),
+            emittance_texture: texture(info.emittance_texture),
+            metallic_roughness_texture: texture(info.metallic_roughness_texture),
+        }
+    }
+}
+
+impl GpuMemory {
+    // mirrors the buffers, images and acceleration structures allocated by `World::create`, the
+    // scratch buffers only live through the build but count towards its peak, image sources are
+    // read where `io::load` resolved them, next to the asset
+    fn estimate(scene: &Scene) -> Self {
+        let bytes = |count: usize, size: usize| (count * size) as u64;
+
+        let vertices = bytes(scene.data.vertices.len(), size_of::<Vertex>());
+        // placeholder element when no primitive has vertex colors
+        let colors = bytes(scene.data.colors.len().max(1), size_of::<glam::Vec4>());
+        let indices = bytes(scene.data.indices.len(), size_of::<u32>());
+        let primitives = bytes(scene.info.primitive_infos.len(), size_of::<PrimitiveInfo>());
+        let materials = bytes(scene.data.materials.len(), size_of::<Material>());
+        let scene_desc = bytes(1, size_of::<SceneDesc>());
+
+        let mut unreadable_images = Vec::new();
+        let images = if scene.data.images.is_empty() {
+            // placeholder pixel
+            IMAGE_TEXEL_SIZE
+        } else {
+            scene
+                .data
+                .images
+                .iter()
+                .filter_map(|image| {
+                    let dimensions = image::image_dimensions(&image.source);
+                    if dimensions.is_err() {
+                        unreadable_images.push(image.source.clone());
+                    }
+                    let (width, height) = dimensions.ok()?;
+                    Some(u64::from(width) * u64::from(height))
+                })
+                .sum::<u64>()
+                * IMAGE_TEXEL_SIZE
+        };
+
+        let (blases, tlas, scratch) = Self::estimate_acceleration_structures(scene);
+
+        Self {
+            vertices,
+            colors,
+            indices,
+            primitives,
+            materials,
+            scene_desc,
+            images,
+            blases,
+            tlas,
+            scratch,
+            total: vertices
+                + colors
+                + indices
+                + primitives
+                + materials
+                + scene_desc
+                + images
+                + blases
+                + tlas
+                + scratch,
+            unreadable_images,
+        }
+    }
+
+    // a BLAS per primitive built with one scratch buffer sized for the largest, then the TLAS
+    // over the instances with its own
+    fn estimate_acceleration_structures(scene: &Scene) -> (u64, u64, u64) {
+        let triangles = scene
+            .info
+            .primitive_sizes
+            .iter()
+            .map(|size| u64::from(size.count()));
+        let blases = triangles.clone().sum::<u64>() * BLAS_TRIANGLE_SIZE;
+        let blas_scratch = triangles.max().unwrap_or_default() * BLAS_SCRATCH_TRIANGLE_SIZE;
+
+        let instances = scene.info.instances.len() as u64;
+        let tlas = instances * (TLAS_INSTANCE_SIZE + INSTANCE_SIZE);
+        let tlas_scratch = instances * TLAS_SCRATCH_INSTANCE_SIZE;
+
+        (blases, tlas, blas_scratch + tlas_scratch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate, testing::TempDir, Image, Instance, TextureInfo};
+
+    #[test]
+    fn gpu_memory() {
+        let memory = Stats::compute(&generate::cube()).gpu_memory;
+        assert_eq!(memory.vertices, 24 * 48);
+        assert_eq!(memory.indices, 36 * 4);
+        assert_eq!(memory.images, IMAGE_TEXEL_SIZE);
+        assert_eq!(memory.blases, 12 * BLAS_TRIANGLE_SIZE);
+        assert_eq!(memory.tlas, TLAS_INSTANCE_SIZE + INSTANCE_SIZE);
+        assert_eq!(
+            memory.scratch,
+            12 * BLAS_SCRATCH_TRIANGLE_SIZE + TLAS_SCRATCH_INSTANCE_SIZE
+        );
+        assert_eq!(
+            memory.total,
+            memory.vertices
+                + memory.colors
+                + memory.indices
+                + memory.primitives
+                + memory.materials
+                + memory.scene_desc
+                + memory.images
+                + memory.blases
+                + memory.tlas
+                + memory.scratch
+        );
+    }
+
+    #[test]
+    fn unreadable_images() {
+        let dir = TempDir::new("stats");
+        image::RgbaImage::new(4, 2)
+            .save(dir.join("texture.png"))
+            .unwrap();
+
+        let mut scene = generate::cube();
+        for source in [dir.join("texture.png"), dir.join("missing.png")] {
+            scene.data.images.push(Image { source });
+            scene.info.textures.push(TextureInfo {
+                image_index: scene.data.images.len() as u32 - 1,
+            });
+        }
+        let memory = Stats::compute(&scene).gpu_memory;
+
+        assert_eq!(memory.images, 4 * 2 * IMAGE_TEXEL_SIZE);
+        assert_eq!(memory.unreadable_images, [dir.join("missing.png")]);
+    }
+
+    #[test]
+    fn invalid_instances() {
+        let mut scene = generate::cube();
+        scene.info.instances.push(Instance {
+            primitive_index: 1,
+            transform: glam::Mat4::IDENTITY,
+            node: None,
+            local_transform: glam::Mat4::IDENTITY,
+        });
+        let stats = Stats::compute(&scene);
+
+        assert_eq!(stats.instances, 2);
+        assert_eq!(stats.instanced_triangles, 12);
+        assert_eq!(stats.invalid_instances, 1);
+    }
+}
@@ -0,0 +1,71 @@
+// helpers shared by the tests of the crate
+
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{gltf::export, Scene};
+
+// a fresh directory in the temporary one, removed with everything written in it when dropped,
+// including when the test panics
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "tsunami-{name}-{}-{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&path).unwrap();
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn join(&self, path: impl AsRef<Path>) -> PathBuf {
+        self.path.join(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        // a panicking test may have left anything in it, and failing here would hide its message
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+// a temporary directory of input files, for the loaders and the batch to read
+pub struct Fixture {
+    dir: TempDir,
+}
+
+impl Fixture {
+    pub fn new(name: &str) -> Self {
+        Self {
+            dir: TempDir::new(name),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        self.dir.path()
+    }
+
+    pub fn path(&self, file: &str) -> PathBuf {
+        self.dir.join(file)
+    }
+
+    pub fn write(&self, file: &str, contents: &str) {
+        std::fs::write(self.path(file), contents).unwrap();
+    }
+
+    pub fn export(&self, file: &str, scene: &Scene) {
+        export::save(scene, self.path(file));
+    }
+}
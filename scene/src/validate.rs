@@ -0,0 +1,645 @@
+use std::fmt;
+
+use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::{Material, PrimitiveInfo, PrimitiveSize, Scene, Vertex};
+
+#[derive(Default, Serialize)]
+pub struct Report {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub location: Location,
+    pub message: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Clone, Copy, Serialize)]
+pub enum Location {
+    Scene,
+    Primitive(usize),
+    Instance(usize),
+    Node(usize),
+    Material(usize),
+    Texture(usize),
+    Image(usize),
+    Camera(usize),
+    Variant(usize),
+}
+
+impl Scene {
+    pub fn validate(&self) -> Report {
+        firestorm::profile_method!(validate);
+
+        let mut report = Report::default();
+
+        self.validate_primitives(&mut report);
+        self.validate_instances(&mut report);
+        self.validate_nodes(&mut report);
+        self.validate_materials(&mut report);
+        self.validate_textures(&mut report);
+        self.validate_cameras(&mut report);
+        self.validate_variants(&mut report);
+
+        let bounding_box = &self.info.bounding_box;
+        if bounding_box.min.is_nan() || bounding_box.max.is_nan() {
+            report.error(Location::Scene, "bounding box contains NaN".to_owned());
+        }
+        for warning in &self.info.import_warnings {
+            report.warning(Location::Scene, warning.clone());
+        }
+
+        report
+    }
+
+    fn validate_primitives(&self, report: &mut Report) {
+        let (infos, sizes) = (&self.info.primitive_infos, &self.info.primitive_sizes);
+        if infos.len() != sizes.len() {
+            report.error(
+                Location::Scene,
+                format!(
+                    "{} primitive infos but {} primitive sizes",
+                    infos.len(),
+                    sizes.len()
+                ),
+            );
+        }
+
+        let mut instanced = vec![false; infos.len()];
+        for instance in &self.info.instances {
+            if let Some(instanced) = instanced.get_mut(instance.primitive_index) {
+                *instanced = true;
+            }
+        }
+
+        for (idx, (info, size)) in infos.iter().zip(sizes).enumerate() {
+            let location = Location::Primitive(idx);
+
+            if info.material as usize >= self.data.materials.len() {
+                report.error(
+                    location,
+                    format!(
+                        "material {} is past the {} materials",
+                        info.material,
+                        self.data.materials.len()
+                    ),
+                );
+            }
+
+            if !instanced[idx] {
+                report.warning(location, "not referenced by any instance".to_owned());
+            }
+
+            if size.vertices_size == 0 || size.indices_size == 0 {
+                report.error(location, "primitive has no geometry".to_owned());
+                continue;
+            }
+            if size.indices_size % 3 != 0 {
+                report.error(
+                    location,
+                    format!("indices_size {} is not a multiple of 3", size.indices_size),
+                );
+            }
+
+            let indices = (info.indices_offset as usize)
+                .checked_add(size.indices_size as usize)
+                .and_then(|end| self.data.indices.get(info.indices_offset as usize..end));
+            let vertices = (info.vertices_offset as usize)
+                .checked_add(size.vertices_size as usize)
+                .and_then(|end| self.data.vertices.get(info.vertices_offset as usize..end));
+
+            let Some(indices) = indices else {
+                report.error(
+                    location,
+                    format!(
+                        "indices {}..{} are out of range of the {} indices",
+                        info.indices_offset,
+                        u64::from(info.indices_offset) + u64::from(size.indices_size),
+                        self.data.indices.len()
+                    ),
+                );
+                continue;
+            };
+            let Some(vertices) = vertices else {
+                report.error(
+                    location,
+                    format!(
+                        "vertices {}..{} are out of range of the {} vertices",
+                        info.vertices_offset,
+                        u64::from(info.vertices_offset) + u64::from(size.vertices_size),
+                        self.data.vertices.len()
+                    ),
+                );
+                continue;
+            };
+
+            let out_of_range = indices.iter().filter(|&&i| i >= size.vertices_size);
+            if let Some(first) = out_of_range.clone().next() {
+                report.error(
+                    location,
+                    format!(
+                        "{} indices point past vertices_size {} (first: {first})",
+                        out_of_range.count(),
+                        size.vertices_size
+                    ),
+                );
+                continue;
+            }
+
+            Self::validate_vertices(report, location, indices, vertices);
+
+            self.validate_colors(report, location, info, size);
+        }
+    }
+
+    fn validate_colors(
+        &self,
+        report: &mut Report,
+        location: Location,
+        info: &PrimitiveInfo,
+        size: &PrimitiveSize,
+    ) {
+        if let Ok(colors_offset) = usize::try_from(info.colors_offset) {
+            let colors = colors_offset
+                .checked_add(size.vertices_size as usize)
+                .and_then(|end| self.data.colors.get(colors_offset..end));
+            match colors {
+                None => report.error(
+                    location,
+                    format!(
+                        "colors {}..{} are out of range of the {} colors",
+                        info.colors_offset,
+                        i64::from(info.colors_offset) + i64::from(size.vertices_size),
+                        self.data.colors.len()
+                    ),
+                ),
+                Some(colors) if !colors.iter().all(|color| color.is_finite()) => {
+                    report.error(location, "vertex colors are not finite".to_owned());
+                }
+                Some(_) => {}
+            }
+        } else if info.colors_offset != PrimitiveInfo::no_colors() {
+            report.error(
+                location,
+                format!(
+                    "colors_offset {} is neither -1 nor valid",
+                    info.colors_offset
+                ),
+            );
+        }
+    }
+
+    fn validate_vertices(
+        report: &mut Report,
+        location: Location,
+        indices: &[u32],
+        vertices: &[Vertex],
+    ) {
+        let count = |f: fn(&Vertex) -> bool| vertices.iter().filter(|v| !f(v)).count();
+
+        let bad_positions = count(|v| v.position.is_finite());
+        if bad_positions > 0 {
+            report.error(
+                location,
+                format!("{bad_positions} vertices have non-finite positions"),
+            );
+        }
+        let bad_normals = count(|v| v.normal.is_finite());
+        if bad_normals > 0 {
+            report.error(
+                location,
+                format!("{bad_normals} vertices have non-finite normals"),
+            );
+        }
+        let bad_tex_coords = count(|v| v.tex_coords.is_finite());
+        if bad_tex_coords > 0 {
+            report.error(
+                location,
+                format!("{bad_tex_coords} vertices have non-finite texture coordinates"),
+            );
+        }
+        let zero_normals = count(|v| v.normal.truncate().length_squared() > 0.);
+        if zero_normals > 0 {
+            report.warning(
+                location,
+                format!("{zero_normals} vertices have zero-length normals"),
+            );
+        }
+
+        let degenerate = indices
+            .chunks_exact(3)
+            .filter(|tri| {
+                let [a, b, c] =
+                    [tri[0], tri[1], tri[2]].map(|i| vertices[i as usize].position.truncate());
+                (b - a).cross(c - a).length_squared() == 0.
+            })
+            .count();
+        if degenerate > 0 {
+            report.warning(
+                location,
+                format!("{degenerate} degenerate (zero area) triangles"),
+            );
+        }
+    }
+
+    fn validate_instances(&self, report: &mut Report) {
+        for (idx, instance) in self.info.instances.iter().enumerate() {
+            let location = Location::Instance(idx);
+            if instance.primitive_index >= self.info.primitive_infos.len() {
+                report.error(
+                    location,
+                    format!(
+                        "primitive {} is past the {} primitives",
+                        instance.primitive_index,
+                        self.info.primitive_infos.len()
+                    ),
+                );
+            }
+            if let Some(node) = instance.node.filter(|&node| node >= self.info.nodes.len()) {
+                report.error(
+                    location,
+                    format!("node {node} is past the {} nodes", self.info.nodes.len()),
+                );
+            }
+            if !instance.transform.is_finite() {
+                report.error(location, "transform is not finite".to_owned());
+            } else if instance.transform.determinant() == 0. {
+                report.warning(location, "transform is degenerate".to_owned());
+            }
+        }
+    }
+
+    fn validate_nodes(&self, report: &mut Report) {
+        for (idx, node) in self.info.nodes.iter().enumerate() {
+            let location = Location::Node(idx);
+            if let Some(parent) = node.parent.filter(|&parent| parent >= idx) {
+                report.error(
+                    location,
+                    format!("parent {parent} is not stored before the node"),
+                );
+            }
+            if node.primitives.end > self.info.primitive_infos.len()
+                || node.primitives.start > node.primitives.end
+            {
+                report.error(
+                    location,
+                    format!(
+                        "primitives {:?} are out of range of the {} primitives",
+                        node.primitives,
+                        self.info.primitive_infos.len()
+                    ),
+                );
+            }
+            if !node.transform.is_finite() {
+                report.error(location, "transform is not finite".to_owned());
+            }
+        }
+    }
+
+    fn validate_materials(&self, report: &mut Report) {
+        for (idx, material) in self.data.materials.iter().enumerate() {
+            let location = Location::Material(idx);
+
+            let Material {
+                color,
+                color_texture,
+                emittance,
+                emittance_texture,
+                metallic,
+                roughness,
+                metallic_roughness_texture,
+            } = *material;
+
+            for (name, texture) in [
+                ("color_texture", color_texture),
+                ("emittance_texture", emittance_texture),
+                ("metallic_roughness_texture", metallic_roughness_texture),
+            ] {
+                if texture < -1 || texture >= self.info.textures.len() as i32 {
+                    report.error(
+                        location,
+                        format!(
+                            "{name} {texture} is not -1 or one of the {} textures",
+                            self.info.textures.len()
+                        ),
+                    );
+                }
+            }
+
+            for (name, value) in [("color", color), ("emittance", emittance)] {
+                if !value.is_finite() {
+                    report.error(location, format!("{name} is not finite"));
+                } else if value.min_element() < 0. {
+                    report.warning(location, format!("{name} has negative components"));
+                }
+            }
+
+            for (name, value) in [("metallic", metallic), ("roughness", roughness)] {
+                if !value.is_finite() {
+                    report.error(location, format!("{name} is not finite"));
+                } else if !(0. ..=1.).contains(&value) {
+                    report.warning(location, format!("{name} {value} is outside [0, 1]"));
+                }
+            }
+        }
+    }
+
+    fn validate_textures(&self, report: &mut Report) {
+        // the size of the renderer's descriptor array of textures
+        let max_textures = shared::conf::MAX_NUM_TEXTURES as usize;
+        if self.info.textures.len() > max_textures {
+            report.error(
+                Location::Scene,
+                format!(
+                    "{} textures are more than the {max_textures} the renderer can bind",
+                    self.info.textures.len()
+                ),
+            );
+        }
+
+        for (idx, texture) in self.info.textures.iter().enumerate() {
+            if texture.image_index as usize >= self.data.images.len() {
+                report.error(
+                    Location::Texture(idx),
+                    format!(
+                        "image {} is past the {} images",
+                        texture.image_index,
+                        self.data.images.len()
+                    ),
+                );
+            }
+        }
+
+        // relative to the working directory, which `io::load` resolves the sources of assets for
+        let missing = self
+            .data
+            .images
+            .par_iter()
+            .enumerate()
+            .filter(|(_, image)| !image.source.is_file())
+            .collect::<Vec<_>>();
+        for (idx, image) in missing {
+            report.error(
+                Location::Image(idx),
+                format!("source {} does not exist", image.source.display()),
+            );
+        }
+    }
+
+    fn validate_cameras(&self, report: &mut Report) {
+        for (idx, camera) in self.info.cameras.iter().enumerate() {
+            let location = Location::Camera(idx);
+            if !(camera.fov > 0. && camera.fov < 180.) {
+                report.error(location, format!("fov {} is outside (0, 180)", camera.fov));
+            }
+            if (camera.target - camera.position).length_squared() == 0. {
+                report.error(location, "position and target coincide".to_owned());
+            }
+        }
+    }
+
+    fn validate_variants(&self, report: &mut Report) {
+        for (idx, variant) in self.info.variants.iter().enumerate() {
+            let location = Location::Variant(idx);
+            if self.info.find_variant(&variant.name) != Some(idx) {
+                report.error(location, format!("name {:?} is not unique", variant.name));
+            }
+            for &(primitive, material) in &variant.materials {
+                if primitive >= self.info.primitive_infos.len() {
+                    report.error(
+                        location,
+                        format!(
+                            "primitive {primitive} is past the {} primitives",
+                            self.info.primitive_infos.len()
+                        ),
+                    );
+                }
+                if material as usize >= self.data.materials.len() {
+                    report.error(
+                        location,
+                        format!(
+                            "material {material} is past the {} materials",
+                            self.data.materials.len()
+                        ),
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl Report {
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == severity)
+            .count()
+    }
+
+    fn error(&mut self, location: Location, message: String) {
+        self.push(Severity::Error, location, message);
+    }
+
+    fn warning(&mut self, location: Location, message: String) {
+        self.push(Severity::Warning, location, message);
+    }
+
+    fn push(&mut self, severity: Severity, location: Location, message: String) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            location,
+            message,
+        });
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for diagnostic in &self.diagnostics {
+            writeln!(f, "{diagnostic}")?;
+        }
+        write!(
+            f,
+            "{} errors, {} warnings",
+            self.count(Severity::Error),
+            self.count(Severity::Warning)
+        )
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}: {}", self.severity, self.location, self.message)
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Warning => "warning",
+            Self::Error => "error",
+        })
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Scene => f.write_str("scene"),
+            Self::Primitive(idx) => write!(f, "primitive #{idx}"),
+            Self::Instance(idx) => write!(f, "instance #{idx}"),
+            Self::Node(idx) => write!(f, "node #{idx}"),
+            Self::Material(idx) => write!(f, "material #{idx}"),
+            Self::Texture(idx) => write!(f, "texture #{idx}"),
+            Self::Image(idx) => write!(f, "image #{idx}"),
+            Self::Camera(idx) => write!(f, "camera #{idx}"),
+            Self::Variant(idx) => write!(f, "variant #{idx}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate, Image, TextureInfo};
+
+    fn diagnostics(scene: &Scene) -> Vec<String> {
+        scene
+            .validate()
+            .diagnostics
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    fn assert_diagnostic(scene: &Scene, expected: &str) {
+        let diagnostics = diagnostics(scene);
+        assert!(
+            diagnostics.iter().any(|diagnostic| diagnostic == expected),
+            "expected {expected:?} in {diagnostics:#?}"
+        );
+    }
+
+    #[test]
+    fn generated_scene_has_no_diagnostics() {
+        assert_eq!(diagnostics(&generate::cube()), Vec::<String>::new());
+    }
+
+    #[test]
+    fn index_past_vertices() {
+        let mut scene = generate::cube();
+        let vertices_size = scene.info.primitive_sizes[0].vertices_size;
+        scene.data.indices[4] = vertices_size;
+        assert_diagnostic(
+            &scene,
+            &format!(
+                "error: primitive #0: 1 indices point past vertices_size {vertices_size} \
+                 (first: {vertices_size})"
+            ),
+        );
+    }
+
+    #[test]
+    fn material_out_of_range() {
+        let mut scene = generate::cube();
+        scene.info.primitive_infos[0].material = 3;
+        assert_diagnostic(
+            &scene,
+            "error: primitive #0: material 3 is past the 1 materials",
+        );
+    }
+
+    #[test]
+    fn texture_out_of_range() {
+        let mut scene = generate::cube();
+        scene.data.materials[0].emittance_texture = 0;
+        assert_diagnostic(
+            &scene,
+            "error: material #0: emittance_texture 0 is not -1 or one of the 0 textures",
+        );
+        scene.data.materials[0].emittance_texture = -2;
+        assert_diagnostic(
+            &scene,
+            "error: material #0: emittance_texture -2 is not -1 or one of the 0 textures",
+        );
+    }
+
+    #[test]
+    fn nan_position() {
+        let mut scene = generate::cube();
+        scene.data.vertices[5].position.y = f32::NAN;
+        assert_diagnostic(
+            &scene,
+            "error: primitive #0: 1 vertices have non-finite positions",
+        );
+    }
+
+    #[test]
+    fn bad_colors_offset() {
+        let mut scene = generate::cube();
+        scene.info.primitive_infos[0].colors_offset = -2;
+        assert_diagnostic(
+            &scene,
+            "error: primitive #0: colors_offset -2 is neither -1 nor valid",
+        );
+
+        let vertices_size = scene.info.primitive_sizes[0].vertices_size as usize;
+        scene.data.colors = vec![glam::Vec4::ONE; vertices_size];
+        scene.info.primitive_infos[0].colors_offset = 1;
+        assert_diagnostic(
+            &scene,
+            &format!(
+                "error: primitive #0: colors 1..{} are out of range of the {vertices_size} colors",
+                vertices_size + 1
+            ),
+        );
+    }
+
+    #[test]
+    fn too_many_textures() {
+        let mut scene = generate::cube();
+        scene.data.images.push(Image {
+            source: "texture.png".into(),
+        });
+        let max_textures = shared::conf::MAX_NUM_TEXTURES as usize;
+        scene.info.textures = (0..=max_textures)
+            .map(|_| TextureInfo { image_index: 0 })
+            .collect();
+        assert_diagnostic(
+            &scene,
+            &format!(
+                "error: scene: {} textures are more than the {max_textures} the renderer can bind",
+                max_textures + 1
+            ),
+        );
+
+        scene.info.textures.pop();
+        assert!(!diagnostics(&scene)
+            .iter()
+            .any(|diagnostic| diagnostic.starts_with("error: scene:")));
+    }
+
+    #[test]
+    fn missing_image() {
+        let mut scene = generate::cube();
+        scene.data.images.push(Image {
+            source: "missing.png".into(),
+        });
+        assert_diagnostic(&scene, "error: image #0: source missing.png does not exist");
+    }
+}
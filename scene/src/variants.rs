@@ -0,0 +1,74 @@
+use crate::Info;
+
+impl Info {
+    pub fn find_variant(&self, name: &str) -> Option<usize> {
+        self.variants
+            .iter()
+            .position(|variant| variant.name == name)
+    }
+
+    // sets the material of every primitive to its default, overridden by the variant if given
+    pub fn apply_variant(&mut self, default_materials: &[u32], variant: Option<usize>) {
+        firestorm::profile_method!(apply_variant);
+
+        for (primitive, &material) in self.primitive_infos.iter_mut().zip(default_materials) {
+            primitive.material = material;
+        }
+
+        if let Some(variant) = variant {
+            for &(primitive, material) in &self.variants[variant].materials {
+                self.primitive_infos[primitive].material = material;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{generate, Variant};
+
+    fn materials(info: &crate::Info) -> Vec<u32> {
+        info.primitive_infos
+            .iter()
+            .map(|primitive| primitive.material)
+            .collect()
+    }
+
+    #[test]
+    fn apply_variant() {
+        let mut scene = generate::cornell_box();
+        let defaults = materials(&scene.info);
+        let blue = scene.data.materials.len() as u32;
+        scene.data.materials.push(generate::diffuse(glam::Vec3::Z));
+        scene.info.variants = vec![
+            Variant {
+                name: "Blue".to_owned(),
+                materials: vec![(1, blue), (3, blue)],
+            },
+            Variant {
+                name: "Light".to_owned(),
+                materials: vec![(0, defaults[5])],
+            },
+        ];
+
+        let variant = scene.info.find_variant("Blue");
+        assert_eq!(variant, Some(0));
+        scene.info.apply_variant(&defaults, variant);
+        let mut expected = defaults.clone();
+        expected[1] = blue;
+        expected[3] = blue;
+        assert_eq!(materials(&scene.info), expected);
+
+        // the primitives of the previous variant get their defaults back
+        scene
+            .info
+            .apply_variant(&defaults, scene.info.find_variant("Light"));
+        let mut expected = defaults.clone();
+        expected[0] = defaults[5];
+        assert_eq!(materials(&scene.info), expected);
+
+        scene.info.apply_variant(&defaults, None);
+        assert_eq!(materials(&scene.info), defaults);
+        assert_eq!(scene.info.find_variant("Missing"), None);
+    }
+}
@@ -64,13 +64,15 @@ vec3 sample_specular_half_vector(vec3 wo, float alpha, vec2 uv) {
 
   const float len_sq = dot(wo.xy, wo.xy);
   const vec3 tv1 = len_sq > 0 ? vec3(-wo.y, wo.x, 0) / sqrt(len_sq) : vec3(1, 0, 0);
-  const vec3 tv2 = cross(wo, tv1);
+  // the disk is projected around the stretched view vector, building it from wo skews the
+  // sampled normals away from the distribution of visible normals for rough surfaces
+  const vec3 tv2 = cross(vh, tv1);
 
   const float r = sqrt(uv.x);
   const float phi = 2 * PI * uv.y;
   const float t1 = r * cos(phi);
   float t2 = r * sin(phi);
-  const float s = 0.5 * (1 + wo.z);
+  const float s = 0.5 * (1 + vh.z);
   t2 = mix(sqrt(1 - t1 * t1), t2, s);
 
   const vec3 nh = t1 * tv1 + t2 * tv2 + sqrt(max(0, 1 - t1 * t1 - t2 * t2)) * vh;
@@ -110,7 +112,8 @@ float specular_probability(MaterialHit material, vec3 wo, vec3 n) {
 
 bool bsdf_sample(MaterialHit material, bool is_specular, vec3 wo, vec3 n, vec2 r,
                  out vec3 wi, out vec3 weight) {
-  if (dot(n, wo) <= 0) false;
+  // seen from below, e.g. with interpolated normals at silhouettes, the path ends there
+  if (dot(n, wo) <= 0) return false;
 
   const vec4 frame = quat_frame(n);
   wo = quat_rotate(frame, wo);
@@ -123,10 +126,13 @@ bool bsdf_sample(MaterialHit material, bool is_specular, vec3 wo, vec3 n, vec2 r
   } else {
     wi = sample_hemisphere(r);
 
-    const vec3 h = sample_specular_half_vector(wo, alpha, r);
-    const float wo_dot_h = clamp_unit_nonzero(dot(wo, h));
+    // the fresnel transmission both ways keeps the diffuse lobe reciprocal and leaves the energy
+    // reflected by the specular lobe out
+    const float n_dot_wo = clamp_unit_nonzero(wo.z);
+    const float n_dot_wi = clamp_unit_nonzero(wi.z);
     weight = base_color_to_diffuse_reflectance(material.base_color, material.metallic)
-              * (vec3(1) - eval_fresnel(specular_f0, wo_dot_h));
+              * (vec3(1) - eval_fresnel(specular_f0, n_dot_wo))
+              * (vec3(1) - eval_fresnel(specular_f0, n_dot_wi));
   }
 
   if (luminance(weight) == 0) return false;
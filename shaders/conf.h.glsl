@@ -0,0 +1,14 @@
+// AUTO-GENERATED: do not edit
+
+#ifndef CONF_H_GLSL_
+#define CONF_H_GLSL_
+
+const float T_MIN = 0.0001;
+const uint MAX_NUM_TEXTURES = 128u;
+const uint UNIFORMS_BINDING = 0u;
+const uint SCENE_DESC_BINDING = 1u;
+const uint TLAS_BINDING = 2u;
+const uint OUTPUT_IMAGE_BINDING = 3u;
+const uint TEXTURES_BINDING = 4u;
+
+#endif
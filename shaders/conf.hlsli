@@ -0,0 +1,14 @@
+// AUTO-GENERATED: do not edit
+
+#ifndef CONF_HLSLI_
+#define CONF_HLSLI_
+
+static const float T_MIN = 0.0001;
+static const uint MAX_NUM_TEXTURES = 128u;
+static const uint UNIFORMS_BINDING = 0u;
+static const uint SCENE_DESC_BINDING = 1u;
+static const uint TLAS_BINDING = 2u;
+static const uint OUTPUT_IMAGE_BINDING = 3u;
+static const uint TEXTURES_BINDING = 4u;
+
+#endif
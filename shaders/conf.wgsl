@@ -0,0 +1,9 @@
+// AUTO-GENERATED: do not edit
+
+const T_MIN: f32 = 0.0001;
+const MAX_NUM_TEXTURES: u32 = 128u;
+const UNIFORMS_BINDING: u32 = 0u;
+const SCENE_DESC_BINDING: u32 = 1u;
+const TLAS_BINDING: u32 = 2u;
+const OUTPUT_IMAGE_BINDING: u32 = 3u;
+const TEXTURES_BINDING: u32 = 4u;
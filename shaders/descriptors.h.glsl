@@ -0,0 +1,34 @@
+// AUTO-GENERATED: do not edit
+
+#ifndef DESCRIPTORS_H_GLSL_
+#define DESCRIPTORS_H_GLSL_
+
+#include "inputs.h.glsl"
+#include "scene.h.glsl"
+
+#if defined(SHADER_STAGE_VERTEX) || defined(SHADER_STAGE_RAY_GENERATION)
+layout(set=0, binding=0) uniform _Uniforms { Uniforms uniforms; };
+#endif
+
+#if defined(SHADER_STAGE_VERTEX) || defined(SHADER_STAGE_FRAGMENT) || defined(SHADER_STAGE_RAY_GENERATION) || defined(SHADER_STAGE_CLOSEST_HIT)
+layout(set=0, binding=1) uniform _SceneDesc { SceneDesc scene_desc; };
+layout(buffer_reference, scalar) buffer Vertices { Vertex v[]; };
+layout(buffer_reference, scalar) buffer Indices { uvec3 i[]; };
+layout(buffer_reference, scalar) buffer Materials { Material m[]; };
+layout(buffer_reference, scalar) buffer Primitives { PrimitiveInfo p[]; };
+layout(buffer_reference, scalar) buffer Colors { vec4 c[]; };
+#endif
+
+#if defined(SHADER_STAGE_RAY_GENERATION)
+layout(set=0, binding=2) uniform accelerationStructureEXT tlas;
+#endif
+
+#if defined(SHADER_STAGE_RAY_GENERATION)
+layout(set=0, binding=3, rgba32f) uniform image2D output_image;
+#endif
+
+#if defined(SHADER_STAGE_FRAGMENT) || defined(SHADER_STAGE_RAY_GENERATION)
+layout(set=0, binding=4) uniform sampler2D textures[];
+#endif
+
+#endif
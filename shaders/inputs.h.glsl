@@ -1,5 +1,8 @@
 // AUTO-GENERATED: do not edit
 
+#ifndef INPUTS_H_GLSL_
+#define INPUTS_H_GLSL_
+
 struct Transform {
   mat4 forward;
   mat4 inverse;
@@ -17,9 +20,16 @@ struct Uniforms {
 struct RasterizerConstants {
   mat4 model_transform;
   uint material_index;
-  vec3 pad;
+  uint primitive_index;
+  vec2 pad;
 };
 
 struct PathtracerConstants {
+  vec3 environment;
   uint frame;
+  uint min_bounces;
+  uint max_bounces;
+  vec2 pad;
 };
+
+#endif
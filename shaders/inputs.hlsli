@@ -0,0 +1,35 @@
+// AUTO-GENERATED: do not edit
+
+#ifndef INPUTS_HLSLI_
+#define INPUTS_HLSLI_
+
+struct Transform {
+  float4x4 forward;
+  float4x4 inverse;
+};
+
+struct Camera {
+  Transform view;
+  Transform proj;
+};
+
+struct Uniforms {
+  Camera camera;
+};
+
+struct RasterizerConstants {
+  float4x4 model_transform;
+  uint material_index;
+  uint primitive_index;
+  float2 pad;
+};
+
+struct PathtracerConstants {
+  float3 environment;
+  uint frame;
+  uint min_bounces;
+  uint max_bounces;
+  float2 pad;
+};
+
+#endif
@@ -0,0 +1,30 @@
+// AUTO-GENERATED: do not edit
+
+struct Transform {
+  forward: mat4x4<f32>,
+  inverse: mat4x4<f32>,
+}
+
+struct Camera {
+  view: Transform,
+  proj: Transform,
+}
+
+struct Uniforms {
+  camera: Camera,
+}
+
+struct RasterizerConstants {
+  model_transform: mat4x4<f32>,
+  material_index: u32,
+  primitive_index: u32,
+  pad: vec2<f32>,
+}
+
+struct PathtracerConstants {
+  environment: vec3<f32>,
+  frame: u32,
+  min_bounces: u32,
+  max_bounces: u32,
+  pad: vec2<f32>,
+}
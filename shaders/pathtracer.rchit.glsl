@@ -5,15 +5,11 @@
 #extension GL_EXT_scalar_block_layout : require
 #extension GL_EXT_shader_explicit_arithmetic_types_int64 : require
 
+#include "conf.h.glsl"
+#include "descriptors.h.glsl"
 #include "ray.common.glsl"
 #include "scene.h.glsl"
 
-layout(set=0, binding=1) uniform _SceneDesc { SceneDesc scene_desc; };
-
-layout(buffer_reference, scalar) buffer Vertices { Vertex v[]; };
-layout(buffer_reference, scalar) buffer Indices { uvec3 i[]; };
-layout(buffer_reference, scalar) buffer Primitives { PrimitiveInfo p[]; };
-
 layout(location=0) rayPayloadInEXT HitInfo payload;
 hitAttributeEXT vec2 hit_uv;
 
@@ -34,6 +30,12 @@ void main() {
   const vec3 normal = normalize(v0.normal.xyz * bary.x + v1.normal.xyz * bary.y + v2.normal.xyz * bary.z);
   payload.normal = vec4(normalize(gl_ObjectToWorldEXT * vec4(normal, 0)), 0);
   payload.uv = v0.tex_coords.xy * bary.x + v1.tex_coords.xy * bary.y + v2.tex_coords.xy * bary.z;
+  payload.color = vec3(1);
+  if (primitive.colors_offset > -1) {
+    Colors colors = Colors(scene_desc.colors_address);
+    const uvec3 cidx = idx - primitive.vertices_offset + uint(primitive.colors_offset);
+    payload.color = (colors.c[cidx.x] * bary.x + colors.c[cidx.y] * bary.y + colors.c[cidx.z] * bary.z).xyz;
+  }
   payload.material = primitive.material;
   payload.hit = true;
 }
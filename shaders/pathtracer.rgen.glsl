@@ -5,28 +5,32 @@
 #extension GL_EXT_scalar_block_layout : require
 #extension GL_EXT_shader_explicit_arithmetic_types_int64 : require
 
+#include "conf.h.glsl"
+#include "descriptors.h.glsl"
 #include "inputs.h.glsl"
 #include "ray.common.glsl"
 #include "bsdf.common.glsl"
 
-const vec3 ENV_COLOR = vec3(1);
-
 layout(push_constant) uniform _PushConstants { PathtracerConstants constants; };
 
-layout(set=0, binding=0) uniform _Uniforms { Uniforms uniforms; };
-layout(set=0, binding=1) uniform _SceneDesc { SceneDesc scene_desc; };
-layout(set=0, binding=2) uniform accelerationStructureEXT tlas;
-layout(set=0, binding=3, rgba32f) uniform image2D output_image;
-layout(set=0, binding=4) uniform sampler2D[] textures;
+layout(location=0) rayPayloadEXT HitInfo payload;
 
-layout(buffer_reference, scalar) buffer Materials { Material m[]; };
+// overridden by the variants in variants.toml
+#ifndef BOUNCES
+#define BOUNCES int(constants.max_bounces)
+#endif
 
-layout(location=0) rayPayloadEXT HitInfo payload;
+#define DEBUG_VIEW_NONE 0
+#define DEBUG_VIEW_NORMALS 1
+#define DEBUG_VIEW_BASE_COLOR 2
+#ifndef DEBUG_VIEW
+#define DEBUG_VIEW DEBUG_VIEW_NONE
+#endif
 
 
-MaterialHit material_info_at_hit(Material material, vec2 coords) {
+MaterialHit material_info_at_hit(Material material, vec2 coords, vec3 vertex_color) {
   MaterialHit info;
-  info.base_color = material.color;
+  info.base_color = material.color * vertex_color;
   if (material.color_texture > -1) {
     info.base_color *= texture(textures[material.color_texture], coords).xyz;
   }
@@ -67,11 +71,11 @@ void main() {
 
   vec3 radiance = vec3(0);
   vec3 throughput = vec3(1);
-  for (int depth = 0; depth < MAX_BOUNCES; ++depth) {
+  for (int depth = 0; depth < BOUNCES; ++depth) {
     traceRayEXT(tlas, RAY_FLAGS, 0xff, 0, 0, 0, ray.origin.xyz, T_MIN, ray.direction.xyz, T_MAX, 0);
 
     if (!payload.hit) {
-      radiance += throughput * ENV_COLOR;
+      radiance += throughput * constants.environment;
       break;
     }
 
@@ -79,15 +83,23 @@ void main() {
     vec3 n = payload.normal.xyz;
     if (dot(n, wo) < 0) n = -n;
 
-    const MaterialHit material = material_info_at_hit(materials.m[payload.material], payload.uv);
+    const MaterialHit material = material_info_at_hit(materials.m[payload.material], payload.uv, payload.color);
+
+#if DEBUG_VIEW == DEBUG_VIEW_NORMALS
+    radiance = 0.5 * n + 0.5;
+    break;
+#elif DEBUG_VIEW == DEBUG_VIEW_BASE_COLOR
+    radiance = material.base_color;
+    break;
+#endif
 
     radiance += throughput * material.emittance;
 
     // Don't need to sample BSDF on last bounce
-    if (depth == MAX_BOUNCES - 1) break;
+    if (depth == BOUNCES - 1) break;
 
     // Russian Roulette
-    if (depth > MIN_BOUNCES) {
+    if (depth > constants.min_bounces) {
       float p_rr = min(0.95, luminance(throughput));
       if (p_rr < rng_float(rng)) break;
       else throughput /= p_rr;
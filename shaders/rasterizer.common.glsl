@@ -3,6 +3,7 @@
 
 struct Interface {
   vec4 tex_coords;
+  vec4 color;
 };
 
 #endif
@@ -1,12 +1,16 @@
 #version 460
+#extension GL_EXT_buffer_reference2 : require
+#extension GL_EXT_scalar_block_layout : require
+#extension GL_EXT_shader_explicit_arithmetic_types_int64 : require
 
+#include "conf.h.glsl"
+#include "descriptors.h.glsl"
 #include "inputs.h.glsl"
 #include "rasterizer.common.glsl"
+#include "scene.h.glsl"
 
 layout(push_constant) uniform _PushConstants { RasterizerConstants constants; };
 
-layout(binding=0) uniform _Uniforms { Uniforms uniforms; };
-
 layout(location=0) in vec4 position;
 layout(location=1) in vec4 tex_coords;
 
@@ -18,4 +22,12 @@ void main() {
               * constants.model_transform
               * position;
   out_data.tex_coords = tex_coords;
+
+  const PrimitiveInfo primitive = Primitives(scene_desc.primitives_address).p[constants.primitive_index];
+  out_data.color = vec4(1);
+  if (primitive.colors_offset > -1) {
+    // gl_VertexIndex includes the vertex offset of the draw
+    const uint idx = uint(gl_VertexIndex) - primitive.vertices_offset + uint(primitive.colors_offset);
+    out_data.color = Colors(scene_desc.colors_address).c[idx];
+  }
 }
@@ -1,13 +1,11 @@
 #ifndef RAY_COMMON_GLSL_
 #define RAY_COMMON_GLSL_
 
+#include "conf.h.glsl"
 #include "rng.common.glsl"
 #include "globals.common.glsl"
 
-const uint MIN_BOUNCES = 3;
-const uint MAX_BOUNCES = 8;
 const uint RAY_FLAGS = gl_RayFlagsOpaqueEXT;
-const float T_MIN = 1e-4;
 const float T_MAX = FLOAT_MAX;
 
 struct Ray {
@@ -19,6 +17,7 @@ struct HitInfo {
   vec4 position;
   vec4 normal;
   vec2 uv;
+  vec3 color;
   uint material;
   bool hit;
 };
@@ -1,10 +1,15 @@
 // AUTO-GENERATED: do not edit
 
+#ifndef SCENE_H_GLSL_
+#define SCENE_H_GLSL_
+
 struct SceneDesc {
   uint64_t vertices_address;
   uint64_t indices_address;
   uint64_t materials_address;
   uint64_t primitives_address;
+  uint64_t colors_address;
+  uint64_t pad;
 };
 
 struct Vertex {
@@ -27,4 +32,7 @@ struct PrimitiveInfo {
   uint indices_offset;
   uint vertices_offset;
   uint material;
+  int colors_offset;
 };
+
+#endif
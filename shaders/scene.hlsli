@@ -0,0 +1,38 @@
+// AUTO-GENERATED: do not edit
+
+#ifndef SCENE_HLSLI_
+#define SCENE_HLSLI_
+
+struct SceneDesc {
+  uint64_t vertices_address;
+  uint64_t indices_address;
+  uint64_t materials_address;
+  uint64_t primitives_address;
+  uint64_t colors_address;
+  uint64_t pad;
+};
+
+struct Vertex {
+  float4 position;
+  float4 normal;
+  float4 tex_coords;
+};
+
+struct Material {
+  float3 color;
+  int color_texture;
+  float3 emittance;
+  int emittance_texture;
+  float metallic;
+  float roughness;
+  int metallic_roughness_texture;
+};
+
+struct PrimitiveInfo {
+  uint indices_offset;
+  uint vertices_offset;
+  uint material;
+  int colors_offset;
+};
+
+#endif
@@ -1,55 +1,35 @@
 use std::{env, path::Path};
 
-use glsl::GlslStruct;
-use shared::{inputs, scene};
+use shared::headers;
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
-const GLSL_EXTENSION: &str = "h.glsl";
-
-struct GlslHeader {
-    name: &'static str,
-    definitions: Vec<String>,
-}
-
-impl GlslHeader {
-    fn glsl_definition(&self) -> String {
-        String::from("// AUTO-GENERATED: do not edit\n\n") + &self.definitions.join("\n")
-    }
-}
-
+// generate_glsl_headers <dir> [--check], the check fails if the headers in dir are stale
 fn main() -> Result<()> {
     let output_dirname = env::args().nth(1).expect("No output directory specified");
     let output_dir = Path::new(&output_dirname);
     assert!(output_dir.is_dir());
+    let check = env::args().nth(2).is_some_and(|arg| arg == "--check");
+
+    let mut stale = Vec::new();
+    for header in glsl::headers(headers::languages) {
+        let output_file = output_dir.join(header.file_name());
+        if std::fs::read_to_string(&output_file).ok().as_ref() == Some(&header.source) {
+            continue;
+        }
+        if check {
+            stale.push(header.file_name());
+        } else {
+            std::fs::write(output_file, header.source)?;
+        }
+    }
 
-    let headers = [
-        GlslHeader {
-            name: "inputs",
-            definitions: vec![
-                inputs::Transform::glsl_struct_definition(),
-                inputs::Camera::glsl_struct_definition(),
-                inputs::Uniforms::glsl_struct_definition(),
-                inputs::RasterizerConstants::glsl_struct_definition(),
-                inputs::PathtracerConstants::glsl_struct_definition(),
-            ],
-        },
-        GlslHeader {
-            name: "scene",
-            definitions: vec![
-                scene::SceneDesc::glsl_struct_definition(),
-                scene::Vertex::glsl_struct_definition(),
-                scene::Material::glsl_struct_definition(),
-                scene::PrimitiveInfo::glsl_struct_definition(),
-            ],
-        },
-    ];
-
-    for header in headers {
-        let mut output_file = output_dir.to_owned();
-        output_file.push(header.name);
-        output_file.set_extension(GLSL_EXTENSION);
-        std::fs::write(output_file, header.glsl_definition())?;
+    if !stale.is_empty() {
+        return Err(format!(
+            "Stale headers, regenerate them with generate_glsl_headers: {}",
+            stale.join(", ")
+        )
+        .into());
     }
 
     Ok(())
@@ -0,0 +1,689 @@
+// mirrors bsdf.common.glsl and the helpers it uses from globals.common.glsl, changes are
+// checked with its tests before being ported to the shaders
+use std::f32::consts::PI;
+
+const MIN_DIELECTRICS_F0: f32 = 0.04;
+
+#[derive(Clone, Copy)]
+pub struct MaterialHit {
+    pub base_color: glam::Vec3,
+    pub metallic: f32,
+    pub emittance: glam::Vec3,
+    pub roughness: f32,
+}
+
+const fn clamp_unit_nonzero(value: f32) -> f32 {
+    value.clamp(0.00001, 1.)
+}
+
+const fn clamp_unit(value: f32) -> f32 {
+    value.clamp(0., 1.)
+}
+
+const fn clamp_pos(value: f32) -> f32 {
+    value.max(0.)
+}
+
+pub fn luminance(color: glam::Vec3) -> f32 {
+    color.dot(glam::Vec3::new(0.2126, 0.7152, 0.0722))
+}
+
+fn base_color_to_specular_f0(base_color: glam::Vec3, metallic: f32) -> glam::Vec3 {
+    glam::Vec3::splat(MIN_DIELECTRICS_F0).lerp(base_color, metallic)
+}
+
+fn base_color_to_diffuse_reflectance(base_color: glam::Vec3, metallic: f32) -> glam::Vec3 {
+    base_color * (1. - metallic)
+}
+
+fn shadowed_f90(f0: glam::Vec3) -> f32 {
+    (luminance(f0) / MIN_DIELECTRICS_F0).min(1.)
+}
+
+// Schlick's approximation
+fn eval_fresnel(f0: glam::Vec3, n_dot_s: f32) -> glam::Vec3 {
+    f0 + (shadowed_f90(f0) - f0) * (1. - n_dot_s).powi(5)
+}
+
+// GGX
+fn ggx_d(alpha_sq: f32, n_dot_h: f32) -> f32 {
+    let b = n_dot_h.mul_add(n_dot_h * (alpha_sq - 1.), 1.);
+    alpha_sq / (PI * b * b)
+}
+
+// GGX
+fn smith_g1(alpha_sq: f32, n_dot_s_sq: f32) -> f32 {
+    2. / ((alpha_sq.mul_add(1. - n_dot_s_sq, n_dot_s_sq) / n_dot_s_sq).sqrt() + 1.)
+}
+
+// GGX VNDF height correlated
+fn specular_sample_weight(alpha_sq: f32, n_dot_l_sq: f32, n_dot_wo_sq: f32) -> f32 {
+    let g1wo = smith_g1(alpha_sq, n_dot_wo_sq);
+    let g1l = smith_g1(alpha_sq, n_dot_l_sq);
+    g1l / g1wo.mul_add(-g1l, g1wo + g1l)
+}
+
+// GGX VNDF
+fn sample_specular_half_vector(wo: glam::Vec3, alpha: f32, uv: glam::Vec2) -> glam::Vec3 {
+    let vh = glam::Vec3::new(alpha * wo.x, alpha * wo.y, wo.z).normalize();
+
+    let len_sq = wo.x.mul_add(wo.x, wo.y * wo.y);
+    let tv1 = if len_sq > 0. {
+        glam::Vec3::new(-wo.y, wo.x, 0.) / len_sq.sqrt()
+    } else {
+        glam::Vec3::X
+    };
+    let tv2 = vh.cross(tv1);
+
+    let r = uv.x.sqrt();
+    let phi = 2. * PI * uv.y;
+    let t1 = r * phi.cos();
+    let t2 = r * phi.sin();
+    let s = 0.5 * (1. + vh.z);
+    let t2 = lerp((1. - t1 * t1).sqrt(), t2, s);
+
+    let nh = t1 * tv1 + t2 * tv2 + (1. - t1 * t1 - t2 * t2).max(0.).sqrt() * vh;
+
+    glam::Vec3::new(alpha * nh.x, alpha * nh.y, nh.z.max(0.)).normalize()
+}
+
+// returns the sampled direction and its weight
+fn sample_specular_microfacet(
+    wo: glam::Vec3,
+    alpha: f32,
+    specular_f0: glam::Vec3,
+    r: glam::Vec2,
+) -> (glam::Vec3, glam::Vec3) {
+    let h = if alpha == 0. {
+        glam::Vec3::Z
+    } else {
+        sample_specular_half_vector(wo, alpha, r)
+    };
+
+    let l = reflect(-wo, h);
+
+    let n = glam::Vec3::Z;
+    let h_dot_l = clamp_unit_nonzero(h.dot(l));
+    let n_dot_l = clamp_unit_nonzero(n.dot(l));
+    let n_dot_wo = clamp_unit_nonzero(n.dot(wo));
+
+    let fresnel = eval_fresnel(specular_f0, h_dot_l);
+
+    let weight =
+        fresnel * specular_sample_weight(alpha * alpha, n_dot_l * n_dot_l, n_dot_wo * n_dot_wo);
+
+    (l, weight)
+}
+
+pub fn specular_probability(material: &MaterialHit, wo: glam::Vec3, n: glam::Vec3) -> f32 {
+    let specular_f0 = luminance(base_color_to_specular_f0(
+        material.base_color,
+        material.metallic,
+    ));
+    let diffuse_reflectance = luminance(base_color_to_diffuse_reflectance(
+        material.base_color,
+        material.metallic,
+    ));
+
+    let specular = clamp_unit(luminance(eval_fresnel(
+        glam::Vec3::splat(specular_f0),
+        clamp_pos(wo.dot(n)),
+    )));
+    let diffuse = diffuse_reflectance * (1. - specular);
+
+    let p = specular / (specular + diffuse).max(0.0001);
+    p.clamp(0.1, 0.9)
+}
+
+// returns the sampled direction and its weight
+pub fn sample(
+    material: &MaterialHit,
+    is_specular: bool,
+    wo: glam::Vec3,
+    n: glam::Vec3,
+    r: glam::Vec2,
+) -> Option<(glam::Vec3, glam::Vec3)> {
+    if n.dot(wo) <= 0. {
+        return None;
+    }
+
+    let frame = quat_frame(n);
+    let wo = quat_rotate(frame, wo);
+
+    let alpha = material.roughness * material.roughness;
+    let specular_f0 = base_color_to_specular_f0(material.base_color, material.metallic);
+
+    let (wi, weight) = if is_specular {
+        sample_specular_microfacet(wo, alpha, specular_f0, r)
+    } else {
+        let wi = sample_hemisphere(r);
+        let weight = diffuse_fresnel_weight(material, specular_f0, wo, wi);
+        (wi, weight)
+    };
+
+    if luminance(weight) == 0. {
+        return None;
+    }
+
+    let wi = quat_rotate(quat_invert_rotation(frame), wi).normalize();
+
+    (n.dot(wi) > 0.).then_some((wi, weight))
+}
+
+// value of the given lobe of the BSDF, without the cosine term, which the weights of `sample`
+// are consistent with, the BSDF being the sum of both lobes
+pub fn eval(
+    material: &MaterialHit,
+    is_specular: bool,
+    wo: glam::Vec3,
+    wi: glam::Vec3,
+    n: glam::Vec3,
+) -> glam::Vec3 {
+    if n.dot(wo) <= 0. || n.dot(wi) <= 0. {
+        return glam::Vec3::ZERO;
+    }
+
+    let frame = quat_frame(n);
+    let wo = quat_rotate(frame, wo);
+    let wi = quat_rotate(frame, wi);
+
+    let alpha = material.roughness * material.roughness;
+    let specular_f0 = base_color_to_specular_f0(material.base_color, material.metallic);
+
+    if !is_specular {
+        return diffuse_fresnel_weight(material, specular_f0, wo, wi) / PI;
+    }
+
+    // perfect mirrors only reflect in a single direction
+    if alpha == 0. {
+        return glam::Vec3::ZERO;
+    }
+
+    let h = (wo + wi).normalize();
+    let n_dot_l = clamp_unit_nonzero(wi.z);
+    let n_dot_wo = clamp_unit_nonzero(wo.z);
+    let g2 = smith_g1(alpha * alpha, n_dot_wo * n_dot_wo)
+        * specular_sample_weight(alpha * alpha, n_dot_l * n_dot_l, n_dot_wo * n_dot_wo);
+    eval_fresnel(specular_f0, clamp_unit_nonzero(h.dot(wi))) * ggx_d(alpha * alpha, h.z) * g2
+        / (4. * n_dot_wo * n_dot_l)
+}
+
+// density of the directions sampled by the given lobe of `sample`
+pub fn pdf(
+    material: &MaterialHit,
+    is_specular: bool,
+    wo: glam::Vec3,
+    wi: glam::Vec3,
+    n: glam::Vec3,
+) -> f32 {
+    if n.dot(wo) <= 0. || n.dot(wi) <= 0. {
+        return 0.;
+    }
+
+    let frame = quat_frame(n);
+    let wo = quat_rotate(frame, wo);
+    let wi = quat_rotate(frame, wi);
+
+    if !is_specular {
+        return wi.z / PI;
+    }
+
+    // visible normals, through the jacobian of the reflection
+    let alpha = material.roughness * material.roughness;
+    let h = (wo + wi).normalize();
+    let n_dot_wo = clamp_unit_nonzero(wo.z);
+    smith_g1(alpha * alpha, n_dot_wo * n_dot_wo) * ggx_d(alpha * alpha, h.z) / (4. * n_dot_wo)
+}
+
+// the fresnel transmission both ways keeps the diffuse lobe reciprocal and leaves the energy
+// reflected by the specular lobe out
+fn diffuse_fresnel_weight(
+    material: &MaterialHit,
+    specular_f0: glam::Vec3,
+    wo: glam::Vec3,
+    wi: glam::Vec3,
+) -> glam::Vec3 {
+    let transmission =
+        |w: glam::Vec3| glam::Vec3::ONE - eval_fresnel(specular_f0, clamp_unit_nonzero(w.z));
+    base_color_to_diffuse_reflectance(material.base_color, material.metallic)
+        * transmission(wo)
+        * transmission(wi)
+}
+
+// rotation taking `v` to +z, as a quaternion
+fn quat_frame(v: glam::Vec3) -> glam::Vec4 {
+    if v.z < -0.99999 {
+        return glam::Vec4::X;
+    }
+    glam::Vec4::new(v.y, -v.x, 0., 1. + v.z).normalize()
+}
+
+fn quat_invert_rotation(q: glam::Vec4) -> glam::Vec4 {
+    glam::Vec4::new(-q.x, -q.y, -q.z, q.w)
+}
+
+fn quat_rotate(q: glam::Vec4, v: glam::Vec3) -> glam::Vec3 {
+    let xyz = q.truncate();
+    2. * xyz.dot(v) * xyz + q.w.mul_add(q.w, -xyz.dot(xyz)) * v + 2. * q.w * xyz.cross(v)
+}
+
+// cosine weighted
+fn sample_hemisphere(r: glam::Vec2) -> glam::Vec3 {
+    let (sin, cos) = (r.y * 2. * PI).sin_cos();
+    let uv = glam::Vec2::new(cos, sin);
+    (uv * r.x.sqrt()).extend((1. - r.x).sqrt())
+}
+
+fn reflect(incident: glam::Vec3, normal: glam::Vec3) -> glam::Vec3 {
+    incident - 2. * normal.dot(incident) * normal
+}
+
+fn lerp(from: f32, to: f32, t: f32) -> f32 {
+    (to - from).mul_add(t, from)
+}
+
+// statistical checks against the closed forms, sample counts are kept low enough for every test
+// run, raise them when changing the BSDF
+#[cfg(test)]
+mod tests {
+    use std::{f64::consts::PI, fmt::Write};
+
+    use super::*;
+    use crate::rng::Rng;
+
+    mod conf {
+        pub const SAMPLES: u32 = 200_000;
+        pub const PAIRS: u32 = 50_000;
+        // histogram over cos theta and phi
+        pub const THETA_BINS: usize = 10;
+        pub const PHI_BINS: usize = 20;
+        // per bin and dimension, to integrate the pdf
+        pub const SUBDIVISIONS: usize = 8;
+        // bins expecting fewer samples are pooled together
+        pub const MIN_EXPECTED: f64 = 5.;
+        // of the whole set of chi-square tests
+        pub const SIGNIFICANCE: f64 = 0.01;
+        pub const TOLERANCE: f32 = 1e-3;
+        // standard deviations allowed between estimates
+        pub const SIGMAS: f64 = 4.;
+    }
+
+    #[derive(Default)]
+    struct Checks {
+        failures: Vec<String>,
+    }
+
+    impl Checks {
+        fn check(&mut self, name: &str, passed: bool, details: &str) {
+            if !passed {
+                self.failures.push(format!("{name}: {details}"));
+            }
+        }
+
+        fn assert_passed(self) {
+            assert!(
+                self.failures.is_empty(),
+                "{} checks failed:\n  {}",
+                self.failures.len(),
+                self.failures.join("\n  ")
+            );
+        }
+    }
+
+    // sampled directions follow the density reported by `pdf`
+    #[test]
+    fn chi_square() {
+        let mut checks = Checks::default();
+        let cases = lobes(&[0.5, 0.8, 1.])
+            .into_iter()
+            .flat_map(|(name, material, is_specular)| {
+                [0., 45., 80.].map(|angle| (name.clone(), material, is_specular, angle))
+            })
+            .collect::<Vec<_>>();
+        // Šidák correction over all the tests
+        let significance = 1. - (1. - conf::SIGNIFICANCE).powf(1. / cases.len() as f64);
+
+        for (seed, (name, material, is_specular, angle)) in cases.into_iter().enumerate() {
+            let wo = direction(angle);
+
+            let mut observed = vec![0.; conf::THETA_BINS * conf::PHI_BINS];
+            let mut rng = Rng::new(glam::UVec2::new(seed as u32, 1), 0);
+            for _ in 0..conf::SAMPLES {
+                if let Some((wi, _)) = sample(&material, is_specular, wo, glam::Vec3::Z, rng.vec2())
+                {
+                    observed[bin(wi)] += 1.;
+                }
+            }
+
+            let expected = expected_counts(conf::SAMPLES, |wi| {
+                f64::from(pdf(&material, is_specular, wo, wi, glam::Vec3::Z))
+            });
+            let (statistic, dof) = pooled_statistic(&observed, &expected);
+            let p_value = gamma_q(f64::from(dof) / 2., statistic / 2.);
+
+            checks.check(
+                &format!("chi-square {name}, {angle} degrees"),
+                p_value > significance,
+                &format!("statistic {statistic:.1} with {dof} dof, p-value {p_value:.4}"),
+            );
+        }
+        checks.assert_passed();
+    }
+
+    // sample weights are the BSDF times the cosine over the pdf
+    #[test]
+    fn consistency() {
+        let mut checks = Checks::default();
+        for (seed, (name, material, is_specular)) in lobes(&[0.3, 0.6, 1.]).into_iter().enumerate()
+        {
+            let mut rng = Rng::new(glam::UVec2::new(seed as u32, 2), 0);
+            let mut worst = 0_f32;
+            for _ in 0..conf::PAIRS {
+                let wo = random_direction(&mut rng);
+                let Some((wi, weight)) =
+                    sample(&material, is_specular, wo, glam::Vec3::Z, rng.vec2())
+                else {
+                    continue;
+                };
+                // clamped dot products make grazing directions inexact
+                if wi.z < 1e-2 || wo.z < 1e-2 {
+                    continue;
+                }
+                let pdf = pdf(&material, is_specular, wo, wi, glam::Vec3::Z);
+                let expected = eval(&material, is_specular, wo, wi, glam::Vec3::Z) * wi.z / pdf;
+                let error = ((weight - expected).abs() / expected.max(glam::Vec3::splat(1e-6)))
+                    .max_element();
+                worst = worst.max(error);
+            }
+            checks.check(
+                &format!("consistency {name}"),
+                worst < conf::TOLERANCE,
+                &format!("largest relative error {worst:.2e}"),
+            );
+        }
+        checks.assert_passed();
+    }
+
+    // swapping the directions doesn't change the BSDF
+    #[test]
+    fn reciprocity() {
+        let mut checks = Checks::default();
+        for (seed, (name, material)) in materials(&[0.1, 0.5, 1.]).into_iter().enumerate() {
+            let mut rng = Rng::new(glam::UVec2::new(seed as u32, 3), 0);
+            let bsdf = |wo, wi| {
+                eval(&material, false, wo, wi, glam::Vec3::Z)
+                    + eval(&material, true, wo, wi, glam::Vec3::Z)
+            };
+            let mut worst = 0_f32;
+            for _ in 0..conf::PAIRS {
+                let wo = random_direction(&mut rng);
+                let wi = random_direction(&mut rng);
+                let forward = bsdf(wo, wi);
+                let backward = bsdf(wi, wo);
+                let error = ((forward - backward).abs() / forward.max(glam::Vec3::splat(1e-6)))
+                    .max_element();
+                worst = worst.max(error);
+            }
+            checks.check(
+                &format!("reciprocity {name}"),
+                worst < conf::TOLERANCE,
+                &format!("largest relative error {worst:.2e}"),
+            );
+        }
+        checks.assert_passed();
+    }
+
+    // white materials don't reflect more than they receive, estimated like the path tracer does, and
+    // the estimate matches the integral of the BSDF
+    #[test]
+    fn white_furnace() {
+        let mut checks = Checks::default();
+        let white = materials(&[0., 0.25, 0.5, 1.])
+            .into_iter()
+            .filter(|(_, material)| material.base_color == glam::Vec3::ONE);
+
+        for (seed, (name, material)) in white.enumerate() {
+            for (frame, angle) in [0., 45., 80.].into_iter().enumerate() {
+                let wo = direction(angle);
+                let mut rng = Rng::new(glam::UVec2::new(seed as u32, 4), frame as u32);
+
+                let (mut sum, mut sum_sq) = (0., 0.);
+                for _ in 0..conf::SAMPLES {
+                    let albedo = f64::from(luminance(estimate(&material, wo, &mut rng)));
+                    sum += albedo;
+                    sum_sq += albedo * albedo;
+                }
+                let mean = sum / f64::from(conf::SAMPLES);
+                let error = (mean.mul_add(-mean, sum_sq / f64::from(conf::SAMPLES))
+                    / f64::from(conf::SAMPLES))
+                .sqrt();
+
+                let mut details = format!("albedo {mean:.4} ± {error:.4}");
+                let mut passed = mean <= conf::SIGMAS.mul_add(error, 1.);
+
+                // the integral is too inaccurate for sharp highlights
+                if material.roughness >= 0.5 {
+                    let integral = expected_counts(1, |wi| {
+                        let bsdf = eval(&material, false, wo, wi, glam::Vec3::Z)
+                            + eval(&material, true, wo, wi, glam::Vec3::Z);
+                        f64::from(luminance(bsdf) * wi.z)
+                    })
+                    .iter()
+                    .sum::<f64>();
+                    write!(details, ", integral {integral:.4}").unwrap();
+                    passed &= (mean - integral).abs() <= conf::SIGMAS.mul_add(error, 1e-3);
+                }
+
+                checks.check(
+                    &format!("white furnace {name}, {angle} degrees"),
+                    passed,
+                    &details,
+                );
+            }
+        }
+        checks.assert_passed();
+    }
+
+    // mirrors the lobe selection of pathtracer.rgen.glsl
+    fn estimate(material: &MaterialHit, wo: glam::Vec3, rng: &mut Rng) -> glam::Vec3 {
+        let n = glam::Vec3::Z;
+        let mut throughput = glam::Vec3::ONE;
+        #[allow(clippy::float_cmp)]
+        let mut is_specular = material.metallic == 1. && material.roughness == 0.;
+        if !is_specular {
+            let p_spec = specular_probability(material, wo, n);
+            if rng.float() < p_spec {
+                is_specular = true;
+                throughput /= p_spec;
+            } else {
+                throughput /= 1. - p_spec;
+            }
+        }
+        sample(material, is_specular, wo, n, rng.vec2())
+            .map_or(glam::Vec3::ZERO, |(_, weight)| throughput * weight)
+    }
+
+    fn materials(roughnesses: &[f32]) -> Vec<(String, MaterialHit)> {
+        let colors = [
+            ("white", glam::Vec3::ONE),
+            ("orange", glam::Vec3::new(0.9, 0.5, 0.1)),
+        ];
+        let mut materials = Vec::new();
+        for (color_name, base_color) in colors {
+            for metallic in [0., 1.] {
+                for &roughness in roughnesses {
+                    materials.push((
+                        format!("{color_name} metallic {metallic} roughness {roughness}"),
+                        MaterialHit {
+                            base_color,
+                            metallic,
+                            emittance: glam::Vec3::ZERO,
+                            roughness,
+                        },
+                    ));
+                }
+            }
+        }
+        materials
+    }
+
+    // both lobes of every material, except diffuse lobes of metals which don't reflect anything
+    fn lobes(roughnesses: &[f32]) -> Vec<(String, MaterialHit, bool)> {
+        materials(roughnesses)
+            .into_iter()
+            .flat_map(|(name, material)| {
+                [false, true].map(|is_specular| {
+                    let lobe = if is_specular { "specular" } else { "diffuse" };
+                    (format!("{name} {lobe}"), material, is_specular)
+                })
+            })
+            .filter(|(_, material, is_specular)| *is_specular || material.metallic < 1.)
+            .collect()
+    }
+
+    // in the xz plane, at the given angle in degrees from the normal
+    fn direction(angle: f32) -> glam::Vec3 {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        glam::Vec3::new(sin, 0., cos)
+    }
+
+    // uniform over the upper hemisphere
+    fn random_direction(rng: &mut Rng) -> glam::Vec3 {
+        let r = rng.vec2();
+        let cos_theta = 1. - r.x;
+        let sin_theta = cos_theta.mul_add(-cos_theta, 1.).sqrt();
+        let (sin_phi, cos_phi) = (r.y * 2. * std::f32::consts::PI).sin_cos();
+        glam::Vec3::new(sin_theta * cos_phi, sin_theta * sin_phi, cos_theta)
+    }
+
+    fn bin(direction: glam::Vec3) -> usize {
+        let to_bin = |coordinate: f32, bins: usize| {
+            usize::try_from((coordinate * bins as f32) as i64).map_or(0, |bin| bin.min(bins - 1))
+        };
+        let theta = to_bin(1. - direction.z, conf::THETA_BINS);
+        let phi = to_bin(
+            direction.y.atan2(direction.x) / (2. * std::f32::consts::PI) + 0.5,
+            conf::PHI_BINS,
+        );
+        theta * conf::PHI_BINS + phi
+    }
+
+    // integrates the density over every bin with the midpoint rule, in cos theta and phi
+    fn expected_counts(samples: u32, pdf: impl Fn(glam::Vec3) -> f64) -> Vec<f64> {
+        let theta_step = 1. / (conf::THETA_BINS * conf::SUBDIVISIONS) as f64;
+        let phi_step = 2. * PI / (conf::PHI_BINS * conf::SUBDIVISIONS) as f64;
+
+        let mut expected = vec![0.; conf::THETA_BINS * conf::PHI_BINS];
+        for theta in 0..conf::THETA_BINS * conf::SUBDIVISIONS {
+            let cos_theta = (theta as f64 + 0.5).mul_add(-theta_step, 1.);
+            let sin_theta = cos_theta.mul_add(-cos_theta, 1.).sqrt();
+            for phi in 0..conf::PHI_BINS * conf::SUBDIVISIONS {
+                let angle = (phi as f64 + 0.5).mul_add(phi_step, -PI);
+                let direction =
+                    glam::DVec3::new(sin_theta * angle.cos(), sin_theta * angle.sin(), cos_theta)
+                        .as_vec3();
+                let bin = (theta / conf::SUBDIVISIONS) * conf::PHI_BINS + phi / conf::SUBDIVISIONS;
+                expected[bin] += pdf(direction) * theta_step * phi_step * f64::from(samples);
+            }
+        }
+        expected
+    }
+
+    // returns the chi-square statistic and its degrees of freedom, pooling unlikely bins together
+    fn pooled_statistic(observed: &[f64], expected: &[f64]) -> (f64, u32) {
+        let mut statistic = 0.;
+        let mut bins = 0;
+        let (mut pooled_observed, mut pooled_expected) = (0., 0.);
+        for (&observed, &expected) in observed.iter().zip(expected) {
+            if expected < conf::MIN_EXPECTED {
+                pooled_observed += observed;
+                pooled_expected += expected;
+            } else {
+                statistic += (observed - expected) * (observed - expected) / expected;
+                bins += 1;
+            }
+        }
+        if pooled_expected >= conf::MIN_EXPECTED {
+            statistic += (pooled_observed - pooled_expected) * (pooled_observed - pooled_expected)
+                / pooled_expected;
+            bins += 1;
+        } else if pooled_observed > conf::MIN_EXPECTED {
+            // samples where the density says there should be almost none
+            statistic = f64::INFINITY;
+        }
+        (statistic, bins.max(2) - 1)
+    }
+
+    // regularized upper incomplete gamma function Q(shape, x), as in Numerical Recipes
+    fn gamma_q(shape: f64, x: f64) -> f64 {
+        const ITERATIONS: usize = 1000;
+        const EPSILON: f64 = 1e-12;
+
+        if x.is_infinite() {
+            return 0.;
+        }
+        let prefix = (shape.mul_add(x.ln(), -x) - ln_gamma(shape)).exp();
+
+        if x < shape + 1. {
+            // series of P(shape, x)
+            let mut term = 1. / shape;
+            let mut sum = term;
+            for idx in 1..ITERATIONS {
+                term *= x / (shape + idx as f64);
+                sum += term;
+                if term.abs() < sum.abs() * EPSILON {
+                    break;
+                }
+            }
+            return sum.mul_add(-prefix, 1.);
+        }
+
+        // continued fraction of Q(shape, x), with the modified Lentz method
+        let tiny = f64::MIN_POSITIVE / EPSILON;
+        let mut denominator = x + 1. - shape;
+        let mut numerator_ratio = 1. / tiny;
+        let mut denominator_ratio = 1. / denominator;
+        let mut fraction = denominator_ratio;
+        for idx in 1..ITERATIONS {
+            let idx = idx as f64;
+            let coefficient = -idx * (idx - shape);
+            denominator += 2.;
+            denominator_ratio = coefficient.mul_add(denominator_ratio, denominator);
+            if denominator_ratio.abs() < tiny {
+                denominator_ratio = tiny;
+            }
+            numerator_ratio = denominator + coefficient / numerator_ratio;
+            if numerator_ratio.abs() < tiny {
+                numerator_ratio = tiny;
+            }
+            denominator_ratio = 1. / denominator_ratio;
+            let delta = denominator_ratio * numerator_ratio;
+            fraction *= delta;
+            if (delta - 1.).abs() < EPSILON {
+                break;
+            }
+        }
+        prefix * fraction
+    }
+
+    // Lanczos approximation
+    fn ln_gamma(x: f64) -> f64 {
+        const COEFFICIENTS: [f64; 6] = [
+            76.180_091_729_471_46,
+            -86.505_320_329_416_77,
+            24.014_098_240_830_91,
+            -1.231_739_572_450_155,
+            0.001_208_650_973_866_179,
+            -0.000_005_395_239_384_953,
+        ];
+        let tmp = x + 5.5;
+        let tmp = (x + 0.5).mul_add(tmp.ln(), -tmp);
+        let series = COEFFICIENTS
+            .iter()
+            .enumerate()
+            .fold(1.000_000_000_190_015, |sum, (idx, coefficient)| {
+                sum + coefficient / (x + 1. + idx as f64)
+            });
+        tmp + (2.506_628_274_631_000_5 * series / x).ln()
+    }
+}
@@ -0,0 +1,25 @@
+use glsl::glsl_const;
+
+// exported to conf.h.glsl for the shaders
+
+#[glsl_const(header = "conf")]
+pub const T_MIN: f32 = 1e-4;
+
+#[glsl_const(header = "conf")]
+pub const MAX_NUM_TEXTURES: u32 = 128;
+
+// descriptor bindings of the set shared by the passes
+#[glsl_const(header = "conf")]
+pub const UNIFORMS_BINDING: u32 = 0;
+#[glsl_const(header = "conf")]
+pub const SCENE_DESC_BINDING: u32 = 1;
+#[glsl_const(header = "conf")]
+pub const TLAS_BINDING: u32 = 2;
+#[glsl_const(header = "conf")]
+pub const OUTPUT_IMAGE_BINDING: u32 = 3;
+#[glsl_const(header = "conf")]
+pub const TEXTURES_BINDING: u32 = 4;
+
+// bounce limits of the scenes that don't set them, passed to the shaders in the push constants
+pub const MIN_BOUNCES: u32 = 3;
+pub const MAX_BOUNCES: u32 = 8;
@@ -0,0 +1,279 @@
+use std::fmt::Write;
+
+use glsl::Glsl;
+
+use crate::{conf, inputs, scene};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    Vertex,
+    Fragment,
+    RayGeneration,
+    Miss,
+    ClosestHit,
+    AnyHit,
+    Intersection,
+    Callable,
+    Compute,
+    Task,
+    Mesh,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Resource {
+    // a block holding a single struct, with the buffer references read through its addresses
+    UniformBuffer {
+        block: &'static str,
+        buffer_references: &'static [BufferReference],
+    },
+    AccelerationStructure,
+    StorageImage {
+        format: &'static str,
+    },
+    Texture,
+    // partially bound, allocated with a variable count of at most max_count
+    Textures {
+        max_count: u32,
+    },
+}
+
+// a scalar block with a runtime array, read through a device address
+#[derive(Clone, Copy, Debug)]
+pub struct BufferReference {
+    pub block: &'static str,
+    pub element: &'static str,
+    pub member: &'static str,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Binding {
+    pub name: &'static str,
+    pub binding: u32,
+    pub resource: Resource,
+    pub stages: &'static [Stage],
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct DescriptorSet {
+    pub set: u32,
+    pub bindings: &'static [Binding],
+}
+
+// the set shared by the rasterizer and the pathtracer
+pub const SCENE: DescriptorSet = DescriptorSet {
+    set: 0,
+    bindings: &[
+        Binding {
+            name: "uniforms",
+            binding: conf::UNIFORMS_BINDING,
+            resource: Resource::UniformBuffer {
+                block: inputs::Uniforms::NAME,
+                buffer_references: &[],
+            },
+            stages: &[Stage::Vertex, Stage::RayGeneration],
+        },
+        Binding {
+            name: "scene_desc",
+            binding: conf::SCENE_DESC_BINDING,
+            resource: Resource::UniformBuffer {
+                block: scene::SceneDesc::NAME,
+                buffer_references: &[
+                    BufferReference {
+                        block: "Vertices",
+                        element: scene::Vertex::NAME,
+                        member: "v",
+                    },
+                    BufferReference {
+                        block: "Indices",
+                        element: glam::UVec3::NAME,
+                        member: "i",
+                    },
+                    BufferReference {
+                        block: "Materials",
+                        element: scene::Material::NAME,
+                        member: "m",
+                    },
+                    BufferReference {
+                        block: "Primitives",
+                        element: scene::PrimitiveInfo::NAME,
+                        member: "p",
+                    },
+                    BufferReference {
+                        block: "Colors",
+                        element: glam::Vec4::NAME,
+                        member: "c",
+                    },
+                ],
+            },
+            stages: &[
+                Stage::Vertex,
+                Stage::Fragment,
+                Stage::RayGeneration,
+                Stage::ClosestHit,
+            ],
+        },
+        Binding {
+            name: "tlas",
+            binding: conf::TLAS_BINDING,
+            resource: Resource::AccelerationStructure,
+            stages: &[Stage::RayGeneration],
+        },
+        Binding {
+            name: "output_image",
+            binding: conf::OUTPUT_IMAGE_BINDING,
+            resource: Resource::StorageImage { format: "rgba32f" },
+            stages: &[Stage::RayGeneration],
+        },
+        Binding {
+            name: "textures",
+            binding: conf::TEXTURES_BINDING,
+            resource: Resource::Textures {
+                max_count: conf::MAX_NUM_TEXTURES,
+            },
+            stages: &[Stage::Fragment, Stage::RayGeneration],
+        },
+    ],
+};
+
+// declared by tonemap.frag.glsl itself, one set for each swapchain image
+pub const TONEMAP_INPUT: DescriptorSet = DescriptorSet {
+    set: 0,
+    bindings: &[Binding {
+        name: "tex",
+        binding: 0,
+        resource: Resource::Texture,
+        stages: &[Stage::Fragment],
+    }],
+};
+
+glsl::inventory::submit! {
+    glsl::GlslItem {
+        header: "descriptors",
+        name: "SCENE",
+        dependencies: || SCENE.glsl_dependencies(),
+        definition: |language| {
+            assert_eq!(language, glsl::Language::Glsl, "descriptors are only declared in GLSL");
+            SCENE.glsl_definitions().join("\n")
+        },
+        file: file!(),
+        line: line!(),
+    }
+}
+
+impl Stage {
+    // defined by the shader build for the stage being compiled
+    pub const fn glsl_macro(self) -> &'static str {
+        match self {
+            Self::Vertex => "SHADER_STAGE_VERTEX",
+            Self::Fragment => "SHADER_STAGE_FRAGMENT",
+            Self::RayGeneration => "SHADER_STAGE_RAY_GENERATION",
+            Self::Miss => "SHADER_STAGE_MISS",
+            Self::ClosestHit => "SHADER_STAGE_CLOSEST_HIT",
+            Self::AnyHit => "SHADER_STAGE_ANY_HIT",
+            Self::Intersection => "SHADER_STAGE_INTERSECTION",
+            Self::Callable => "SHADER_STAGE_CALLABLE",
+            Self::Compute => "SHADER_STAGE_COMPUTE",
+            Self::Task => "SHADER_STAGE_TASK",
+            Self::Mesh => "SHADER_STAGE_MESH",
+        }
+    }
+}
+
+impl DescriptorSet {
+    // the variable count the set is allocated with, if it has a variable count binding
+    pub fn variable_count(&self) -> Option<u32> {
+        self.bindings
+            .iter()
+            .find_map(|binding| match binding.resource {
+                Resource::Textures { max_count } => Some(max_count),
+                _ => None,
+            })
+    }
+
+    // the blocks and the buffer reference elements
+    pub fn glsl_dependencies(&self) -> Vec<&'static str> {
+        let mut dependencies = Vec::new();
+        for binding in self.bindings {
+            if let Resource::UniformBuffer {
+                block,
+                buffer_references,
+            } = binding.resource
+            {
+                dependencies.push(block);
+                dependencies.extend(buffer_references.iter().map(|reference| reference.element));
+            }
+        }
+        dependencies
+    }
+
+    // each declaration is only visible to the stages it's bound to
+    pub fn glsl_definitions(&self) -> Vec<String> {
+        self.bindings
+            .iter()
+            .map(|binding| {
+                let stages = binding
+                    .stages
+                    .iter()
+                    .map(|stage| format!("defined({})", stage.glsl_macro()))
+                    .collect::<Vec<_>>();
+                format!(
+                    "#if {}\n{}#endif\n",
+                    stages.join(" || "),
+                    binding.glsl_declaration(self.set)
+                )
+            })
+            .collect()
+    }
+}
+
+impl Binding {
+    fn glsl_declaration(&self, set: u32) -> String {
+        let Self { name, binding, .. } = self;
+        let mut def = String::new();
+        match self.resource {
+            Resource::UniformBuffer {
+                block,
+                buffer_references,
+            } => {
+                writeln!(
+                    def,
+                    "layout(set={set}, binding={binding}) uniform _{block} {{ {block} {name}; }};"
+                )
+                .unwrap();
+                for reference in buffer_references {
+                    let BufferReference {
+                        block,
+                        element,
+                        member,
+                    } = reference;
+                    writeln!(
+                        def,
+                        "layout(buffer_reference, scalar) buffer {block} {{ {element} {member}[]; }};"
+                    )
+                    .unwrap();
+                }
+            }
+            Resource::AccelerationStructure => writeln!(
+                def,
+                "layout(set={set}, binding={binding}) uniform accelerationStructureEXT {name};"
+            )
+            .unwrap(),
+            Resource::StorageImage { format } => writeln!(
+                def,
+                "layout(set={set}, binding={binding}, {format}) uniform image2D {name};"
+            )
+            .unwrap(),
+            Resource::Texture => writeln!(
+                def,
+                "layout(set={set}, binding={binding}) uniform sampler2D {name};"
+            )
+            .unwrap(),
+            Resource::Textures { .. } => writeln!(
+                def,
+                "layout(set={set}, binding={binding}) uniform sampler2D {name}[];"
+            )
+            .unwrap(),
+        }
+        def
+    }
+}
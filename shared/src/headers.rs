@@ -0,0 +1,16 @@
+use glsl::Language;
+
+// the languages each registered header is written in, the slang passes and the web viewer share
+// the inputs and the constants, wgsl can't read the scalar scene buffers
+pub fn languages(header: &str) -> &'static [Language] {
+    match header {
+        "conf" | "inputs" => &[
+            Language::Glsl,
+            Language::Hlsl,
+            Language::Slang,
+            Language::Wgsl,
+        ],
+        "scene" => &[Language::Glsl, Language::Hlsl, Language::Slang],
+        _ => &[Language::Glsl],
+    }
+}
@@ -6,6 +6,7 @@ use glsl::GlslStruct;
 
 #[repr(C)]
 #[derive(Copy, Clone, Default, GlslStruct, Pod, Zeroable)]
+#[glsl(layout(std140), header = "inputs")]
 pub struct Transform {
     pub forward: glam::Mat4,
     pub inverse: glam::Mat4,
@@ -13,6 +14,7 @@ pub struct Transform {
 
 #[repr(C)]
 #[derive(Copy, Clone, Default, GlslStruct, Pod, Zeroable)]
+#[glsl(layout(std140), header = "inputs")]
 pub struct Camera {
     pub view: Transform,
     pub proj: Transform,
@@ -20,22 +22,30 @@ pub struct Camera {
 
 #[repr(C)]
 #[derive(Copy, Clone, Default, GlslStruct, Pod, Zeroable)]
+#[glsl(layout(std140), header = "inputs")]
 pub struct Uniforms {
     pub camera: Camera,
 }
 
 #[repr(C)]
 #[derive(Copy, Clone, Default, GlslStruct, Pod, Zeroable)]
+#[glsl(layout(std430), header = "inputs")]
 pub struct RasterizerConstants {
     pub model_transform: glam::Mat4,
     pub material_index: u32,
-    pub pad: glam::Vec3,
+    pub primitive_index: u32,
+    pub pad: glam::Vec2,
 }
 
 #[repr(C)]
 #[derive(Copy, Clone, Default, GlslStruct, Pod, Zeroable)]
+#[glsl(layout(std430), header = "inputs")]
 pub struct PathtracerConstants {
+    pub environment: glam::Vec3,
     pub frame: u32,
+    pub min_bounces: u32,
+    pub max_bounces: u32,
+    pub pad: glam::Vec2,
 }
 
 impl Transform {
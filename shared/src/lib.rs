@@ -1,2 +1,8 @@
+pub mod bsdf;
+pub mod conf;
+pub mod descriptors;
+pub mod headers;
 pub mod inputs;
+pub mod pipelines;
+pub mod rng;
 pub mod scene;
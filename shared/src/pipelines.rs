@@ -0,0 +1,55 @@
+use std::mem::size_of;
+
+use crate::{
+    descriptors::{self, DescriptorSet, Stage},
+    inputs,
+};
+
+#[derive(Clone, Copy, Debug)]
+pub struct PushConstants {
+    pub size: u32,
+    pub stages: &'static [Stage],
+}
+
+// what a pipeline layout is created with, the shader build checks the spir-v of each of its
+// shaders against it
+#[derive(Clone, Copy, Debug)]
+pub struct PipelineLayout {
+    pub name: &'static str,
+    pub shaders: &'static [&'static str],
+    pub descriptor_sets: &'static [DescriptorSet],
+    pub push_constants: Option<PushConstants>,
+}
+
+pub const RASTERIZER: PipelineLayout = PipelineLayout {
+    name: "Rasterizer",
+    shaders: &["rasterizer.vert.glsl", "rasterizer.frag.glsl"],
+    descriptor_sets: &[descriptors::SCENE],
+    push_constants: Some(PushConstants {
+        size: size_of::<inputs::RasterizerConstants>() as _,
+        stages: &[Stage::Vertex, Stage::Fragment],
+    }),
+};
+
+pub const PATHTRACER: PipelineLayout = PipelineLayout {
+    name: "Pathtracer",
+    shaders: &[
+        "pathtracer.rgen.glsl",
+        "pathtracer.rmiss.glsl",
+        "pathtracer.rchit.glsl",
+    ],
+    descriptor_sets: &[descriptors::SCENE],
+    push_constants: Some(PushConstants {
+        size: size_of::<inputs::PathtracerConstants>() as _,
+        stages: &[Stage::RayGeneration],
+    }),
+};
+
+pub const TONEMAP: PipelineLayout = PipelineLayout {
+    name: "Tonemap",
+    shaders: &["tonemap.vert.glsl", "tonemap.frag.glsl"],
+    descriptor_sets: &[descriptors::TONEMAP_INPUT],
+    push_constants: None,
+};
+
+pub const ALL: &[PipelineLayout] = &[RASTERIZER, PATHTRACER, TONEMAP];
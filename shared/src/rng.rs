@@ -0,0 +1,48 @@
+// pcg4d, mirrors rng.common.glsl
+pub struct Rng {
+    state: glam::UVec4,
+}
+
+impl Rng {
+    pub const fn new(pixel: glam::UVec2, frame: u32) -> Self {
+        Self {
+            state: glam::UVec4::new(pixel.x, pixel.y, frame, 0),
+        }
+    }
+
+    fn next(&mut self) -> glam::UVec4 {
+        let mut v = self
+            .state
+            .wrapping_mul(glam::UVec4::splat(1_664_525))
+            .wrapping_add(glam::UVec4::splat(1_013_904_223));
+
+        v.x = v.x.wrapping_add(v.y.wrapping_mul(v.w));
+        v.y = v.y.wrapping_add(v.z.wrapping_mul(v.x));
+        v.z = v.z.wrapping_add(v.x.wrapping_mul(v.y));
+        v.w = v.w.wrapping_add(v.y.wrapping_mul(v.z));
+
+        v = v ^ (v >> 16);
+
+        v.x = v.x.wrapping_add(v.y.wrapping_mul(v.w));
+        v.y = v.y.wrapping_add(v.z.wrapping_mul(v.x));
+        v.z = v.z.wrapping_add(v.x.wrapping_mul(v.y));
+        v.w = v.w.wrapping_add(v.y.wrapping_mul(v.z));
+
+        self.state = v;
+        v
+    }
+
+    pub fn float(&mut self) -> f32 {
+        to_float(self.next().x)
+    }
+
+    pub fn vec2(&mut self) -> glam::Vec2 {
+        let v = self.next();
+        glam::Vec2::new(to_float(v.x), to_float(v.y))
+    }
+}
+
+// uniform in [0, 1) from the 23 high bits
+fn to_float(x: u32) -> f32 {
+    f32::from_bits((x >> 9) | 0x3f80_0000) - 1.
+}
@@ -5,15 +5,19 @@ use glsl::GlslStruct;
 
 #[repr(C)]
 #[derive(Copy, Clone, Default, GlslStruct, Pod, Zeroable)]
+#[glsl(layout(std140), header = "scene")]
 pub struct SceneDesc {
     pub vertices_address: u64,
     pub indices_address: u64,
     pub materials_address: u64,
     pub primitives_address: u64,
+    pub colors_address: u64,
+    pub pad: u64,
 }
 
 #[repr(C)]
 #[derive(Copy, Clone, Default, Deserialize, Serialize, GlslStruct, Pod, Zeroable)]
+#[glsl(header = "scene")]
 pub struct Vertex {
     pub position: glam::Vec4,
     pub normal: glam::Vec4,
@@ -22,6 +26,7 @@ pub struct Vertex {
 
 #[repr(C)]
 #[derive(Clone, Copy, Default, Deserialize, Serialize, GlslStruct, Pod, Zeroable)]
+#[glsl(header = "scene")]
 pub struct Material {
     pub color: glam::Vec3,
     pub color_texture: i32,
@@ -34,10 +39,20 @@ pub struct Material {
 
 #[repr(C)]
 #[derive(Clone, Copy, Default, Deserialize, Serialize, GlslStruct, Pod, Zeroable)]
+#[glsl(header = "scene")]
 pub struct PrimitiveInfo {
     pub indices_offset: u32,
     pub vertices_offset: u32,
     pub material: u32,
+    // -1 if the primitive has no vertex colors
+    #[serde(default = "PrimitiveInfo::no_colors")]
+    pub colors_offset: i32,
+}
+
+impl PrimitiveInfo {
+    pub const fn no_colors() -> i32 {
+        -1
+    }
 }
 
 impl Vertex {